@@ -0,0 +1,79 @@
+// Decides up front how (and whether) we can talk to the user, so a piped run
+// fails fast instead of blocking on a prompt nobody can see.
+use std::io::IsTerminal;
+
+// Same exit code clap uses for usage errors
+pub const EXIT_USAGE: u8 = 2;
+
+/// Returned (inside anyhow) when a command needs an answer nobody can give; main prints it
+/// and exits with EXIT_USAGE once the diagnostics summary is out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Refused(pub &'static str);
+
+impl std::fmt::Display for Refused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Refused {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interaction {
+    /// Prompt on stdin. With stdout piped, the listing and prompts go to stderr so they stay visible.
+    Interactive { prompt_to_stderr: bool },
+    /// The selection was given up front; stdin is never read.
+    NonInteractive,
+    /// Stdin isn't a terminal and nothing was preselected: a prompt would hang.
+    Refuse,
+}
+
+impl Interaction {
+    pub fn detect(preselected: bool) -> Self {
+        Self::decide(std::io::stdin().is_terminal(), std::io::stdout().is_terminal(), preselected)
+    }
+
+    pub fn decide(stdin_is_tty: bool, stdout_is_tty: bool, preselected: bool) -> Self {
+        match (preselected, stdin_is_tty) {
+            (true, _) => Interaction::NonInteractive,
+            (false, true) => Interaction::Interactive { prompt_to_stderr: !stdout_is_tty },
+            (false, false) => Interaction::Refuse,
+        }
+    }
+
+    /// Prints a line of the candidate listing or a prompt where the user will see it.
+    pub fn say(&self, line: &str) {
+        match self {
+            Interaction::Interactive { prompt_to_stderr: true } => eprintln!("{}", line),
+            _ => println!("{}", line),
+        }
+    }
+
+    pub fn refusal_message() -> &'static str {
+        "error: stdin is not a terminal, so the selection prompt can't be answered.\n\
+         Run this command from an interactive terminal, or pass --yes (top matches) or\n\
+         --select \"1,3\" to choose without a prompt; piping its output (e.g. `| tee log`) is fine."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decision_matrix() {
+        assert_eq!(Interaction::decide(true, true, false), Interaction::Interactive { prompt_to_stderr: false });
+        assert_eq!(Interaction::decide(true, false, false), Interaction::Interactive { prompt_to_stderr: true });
+        assert_eq!(Interaction::decide(false, true, false), Interaction::Refuse);
+        assert_eq!(Interaction::decide(false, false, false), Interaction::Refuse);
+    }
+
+    #[test]
+    fn test_preselection_never_reads_stdin() {
+        for stdin_is_tty in [true, false] {
+            for stdout_is_tty in [true, false] {
+                assert_eq!(Interaction::decide(stdin_is_tty, stdout_is_tty, true), Interaction::NonInteractive);
+            }
+        }
+    }
+}
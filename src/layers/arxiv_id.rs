@@ -0,0 +1,230 @@
+// arXiv identifiers in both schemes:
+//   new (2007+):   1706.03762, 1706.03762v5, 0704.0001
+//   old (to 2007): hep-th/9901001, cs.DS/0301001v2 (archive, optional subject class, YYMMNNN)
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArxivId {
+    // Old-style IDs only, e.g. "cs" and "DS" for cs.DS/0301001
+    archive: Option<String>,
+    subject_class: Option<String>,
+    number: String,
+    version: Option<u32>,
+}
+
+impl ArxivId {
+    /// Parses a bare ID, an `arXiv:` prefixed one, or an arxiv.org abs/pdf URL.
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut id = input.trim();
+        if id.len() >= 6 && id[..6].eq_ignore_ascii_case("arxiv:") {
+            id = &id[6..];
+        }
+        if let Some(pos) = id.find("/abs/").or_else(|| id.find("/pdf/")) {
+            if id[..pos].to_lowercase().contains("arxiv.org") {
+                id = &id[pos + 5..];
+            }
+        }
+        let id = id.trim_end_matches('/');
+        let id = id.strip_suffix(".pdf").unwrap_or(id);
+
+        let (id, version) = split_version(id)?;
+        match id.split_once('/') {
+            Some((category, number)) => Self::parse_old(category, number, version),
+            None => Self::parse_new(id, version),
+        }
+    }
+
+    fn parse_new(id: &str, version: Option<u32>) -> Option<Self> {
+        let (yymm, seq) = id.split_once('.')?;
+        if yymm.len() != 4 || !all_digits(yymm) || !(4..=5).contains(&seq.len()) || !all_digits(seq) {
+            return None;
+        }
+        // Sequence numbers went from four to five digits in 1501
+        if (seq.len() == 5) != (yymm >= "1501") {
+            return None;
+        }
+        valid_month(&yymm[2..])?;
+        Some(Self { archive: None, subject_class: None, number: id.to_string(), version })
+    }
+
+    fn parse_old(category: &str, number: &str, version: Option<u32>) -> Option<Self> {
+        if number.len() != 7 || !all_digits(number) {
+            return None;
+        }
+        valid_month(&number[2..4])?;
+        let (archive, subject_class) = match category.split_once('.') {
+            Some((a, s)) => (a, Some(s)),
+            None => (category, None),
+        };
+        if archive.is_empty() || !archive.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+            return None;
+        }
+        if let Some(s) = subject_class {
+            if s.is_empty() || !s.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+                return None;
+            }
+        }
+        Some(Self {
+            archive: Some(archive.to_string()),
+            subject_class: subject_class.map(|s| s.to_string()),
+            number: number.to_string(),
+            version,
+        })
+    }
+
+    /// The ID without its version, in arXiv's canonical form ("1706.03762", "cs/0301001").
+    pub fn base_id(&self) -> String {
+        match &self.archive {
+            Some(archive) => format!("{}/{}", archive, self.number),
+            None => self.number.clone(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// Category embedded in an old-style ID ("cs.DS", or just "hep-th"); new-style IDs have none.
+    pub fn category(&self) -> Option<String> {
+        let archive = self.archive.as_ref()?;
+        Some(match &self.subject_class {
+            Some(subject) => format!("{}.{}", archive, subject),
+            None => archive.clone(),
+        })
+    }
+
+    pub fn pdf_url(&self) -> String {
+        format!("https://arxiv.org/pdf/{}", self)
+    }
+}
+
+impl fmt::Display for ArxivId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base_id())?;
+        if let Some(v) = self.version {
+            write!(f, "v{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+fn all_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn valid_month(mm: &str) -> Option<()> {
+    let month: u32 = mm.parse().ok()?;
+    (1..=12).contains(&month).then_some(())
+}
+
+/// The bare, unversioned ID for any form an arXiv ID arrives in ("http://arxiv.org/abs/2301.01234v2"
+/// -> "2301.01234"), so the same preprint is stored the same way whichever source found it.
+/// Text that isn't an arXiv ID is kept, trimmed.
+pub fn normalize(raw: &str) -> String {
+    ArxivId::parse(raw).map(|id| id.base_id()).unwrap_or_else(|| raw.trim().to_string())
+}
+
+// "1706.03762v5" -> ("1706.03762", Some(5)); a trailing "v" with no digits is rejected
+fn split_version(id: &str) -> Option<(&str, Option<u32>)> {
+    match id.rfind('v') {
+        Some(pos) if pos > 0 && id[..pos].ends_with(|c: char| c.is_ascii_digit()) => {
+            let digits = &id[pos + 1..];
+            if !all_digits(digits) {
+                return None;
+            }
+            Some((&id[..pos], Some(digits.parse().ok()?)))
+        }
+        _ => Some((id, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> ArxivId {
+        ArxivId::parse(s).unwrap_or_else(|| panic!("failed to parse {:?}", s))
+    }
+
+    #[test]
+    fn test_new_style_without_version() {
+        let id = parse("1706.03762");
+        assert_eq!(id.base_id(), "1706.03762");
+        assert_eq!(id.version(), None);
+        assert_eq!(id.category(), None);
+        assert_eq!(id.to_string(), "1706.03762");
+    }
+
+    #[test]
+    fn test_new_style_with_version() {
+        let id = parse("1706.03762v5");
+        assert_eq!(id.base_id(), "1706.03762");
+        assert_eq!(id.version(), Some(5));
+        assert_eq!(id.to_string(), "1706.03762v5");
+        assert_eq!(id.pdf_url(), "https://arxiv.org/pdf/1706.03762v5");
+    }
+
+    #[test]
+    fn test_new_style_four_digit_sequence() {
+        assert_eq!(parse("0704.0001").base_id(), "0704.0001");
+        assert_eq!(parse("1412.6980v9").version(), Some(9));
+    }
+
+    #[test]
+    fn test_old_style_with_subject_class() {
+        let id = parse("cs.DS/0301001");
+        assert_eq!(id.base_id(), "cs/0301001");
+        assert_eq!(id.category().as_deref(), Some("cs.DS"));
+        assert_eq!(id.version(), None);
+
+        let id = parse("math.AG/0601001v3");
+        assert_eq!(id.base_id(), "math/0601001");
+        assert_eq!(id.category().as_deref(), Some("math.AG"));
+        assert_eq!(id.version(), Some(3));
+        assert_eq!(id.to_string(), "math/0601001v3");
+    }
+
+    #[test]
+    fn test_old_style_archive_only() {
+        let id = parse("hep-th/9901001");
+        assert_eq!(id.base_id(), "hep-th/9901001");
+        assert_eq!(id.category().as_deref(), Some("hep-th"));
+
+        let id = parse("quant-ph/0201082v2");
+        assert_eq!(id.category().as_deref(), Some("quant-ph"));
+        assert_eq!(id.version(), Some(2));
+        assert_eq!(id.pdf_url(), "https://arxiv.org/pdf/quant-ph/0201082v2");
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("http://arxiv.org/abs/2301.01234v2"), "2301.01234");
+        assert_eq!(normalize("2301.01234"), "2301.01234");
+        assert_eq!(normalize("http://arxiv.org/abs/math.GT/0309136v1"), "math/0309136");
+        assert_eq!(normalize("arXiv:hep-th/9901001v3"), "hep-th/9901001");
+        assert_eq!(normalize(" not-an-id "), "not-an-id");
+    }
+
+    #[test]
+    fn test_prefixes_and_urls() {
+        assert_eq!(parse("arXiv:1706.03762v2").to_string(), "1706.03762v2");
+        assert_eq!(parse("ARXIV:hep-th/9901001").base_id(), "hep-th/9901001");
+        assert_eq!(parse("http://arxiv.org/abs/2301.01234v2").to_string(), "2301.01234v2");
+        assert_eq!(parse("https://arxiv.org/pdf/2301.01234.pdf").to_string(), "2301.01234");
+        assert_eq!(parse("https://export.arxiv.org/abs/cs/0301001v1").to_string(), "cs/0301001v1");
+        assert_eq!(parse(" 1706.03762 ").base_id(), "1706.03762");
+    }
+
+    #[test]
+    fn test_rejects_malformed_ids() {
+        for bad in [
+            "", "abc", "1706.0376", "1706.037620", "1713.03762", "1700.03762",
+            "1412.12345", "1706.03762v", "1706.03762vx", "v2",
+            "cs.DS/030100", "cs.DS/0313001", "CS/0301001", "/0301001", "cs./0301001",
+            "https://example.org/abs/1706.03762",
+        ] {
+            assert!(ArxivId::parse(bad).is_none(), "accepted {:?}", bad);
+        }
+    }
+}
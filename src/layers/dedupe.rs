@@ -0,0 +1,130 @@
+// Suspected duplicates already in the library, such as a preprint and its published version
+// downloaded months apart. Only reported; nothing in the library is changed.
+use crate::layers::PaperMetadata;
+use crate::layers::merge::keys;
+use crate::layers::normalize::surname;
+use crate::layers::resolution::{title_similarity, Similarity, DEFAULT_THRESHOLD_RATIO};
+use crate::layers::stats::LibraryPaper;
+
+// The paper's identifiers: its DOI and arXiv ID from metadata.json. Titles are compared by
+// similarity instead, together with the authors.
+fn identifiers(paper: &LibraryPaper) -> Vec<String> {
+    let metadata = paper.metadata.clone()
+        .unwrap_or_else(|| PaperMetadata { title: paper.entry.title.clone(), ..Default::default() });
+    keys(&metadata).into_iter().filter(|k| !k.starts_with("title:")).collect()
+}
+
+fn surnames(paper: &LibraryPaper) -> Vec<String> {
+    paper.authors().into_iter().filter_map(surname).collect()
+}
+
+// Union-find root, halving the path on the way
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Groups of library papers that look like the same work, as indices into `papers`: those
+/// sharing a DOI or arXiv ID, and those whose titles are as close as a search match must be
+/// (`DEFAULT_THRESHOLD_RATIO`) with at least one author surname in common. Groups and their
+/// members are in library order; papers without a duplicate are left out.
+pub fn find_duplicates(papers: &[LibraryPaper]) -> Vec<Vec<usize>> {
+    let ids: Vec<Vec<String>> = papers.iter().map(identifiers).collect();
+    let names: Vec<Vec<String>> = papers.iter().map(surnames).collect();
+    let min_similarity = Similarity::from_ratio(DEFAULT_THRESHOLD_RATIO);
+    let mut parent: Vec<usize> = (0..papers.len()).collect();
+    for a in 0..papers.len() {
+        for b in a + 1..papers.len() {
+            let same_id = ids[a].iter().any(|k| ids[b].contains(k));
+            let same_work = || names[a].iter().any(|n| names[b].contains(n))
+                && title_similarity(&papers[a].entry.title, &papers[b].entry.title) >= min_similarity;
+            if same_id || same_work() {
+                let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+                parent[ra.max(rb)] = ra.min(rb);
+            }
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: Vec<Option<usize>> = vec![None; papers.len()];
+    for i in 0..papers.len() {
+        let r = root(&mut parent, i);
+        match group_of[r] {
+            Some(g) => groups[g].push(i),
+            None => {
+                group_of[r] = Some(groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::download::ManifestEntry;
+    use crate::output::{formatter, DedupeView, OutputFormat};
+
+    fn library() -> Vec<LibraryPaper> {
+        let path = format!("{}/tests/fixtures/dedupe/manifest.json", env!("CARGO_MANIFEST_DIR"));
+        let entries: Vec<ManifestEntry> = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        // metadata.json for the entries that have one; the review was saved under its title
+        let doi = |id: &str| match id {
+            "doi_10.5555_attention" => Some("10.5555/attention"),
+            "doi_10.1038_nature14539" | "deep-learning-review" => Some("https://doi.org/10.1038/NATURE14539"),
+            _ => None,
+        };
+        entries.into_iter().map(|entry| {
+            let metadata = doi(&entry.id).map(|doi| PaperMetadata {
+                title: entry.title.clone(),
+                doi: Some(doi.to_string()),
+                authors: vec![entry.first_author.clone()],
+                ..Default::default()
+            });
+            LibraryPaper { entry, metadata }
+        }).collect()
+    }
+
+    fn ids(groups: &[Vec<usize>], papers: &[LibraryPaper]) -> Vec<Vec<String>> {
+        groups.iter().map(|g| g.iter().map(|&i| papers[i].entry.id.clone()).collect()).collect()
+    }
+
+    #[test]
+    fn test_duplicate_heavy_library() {
+        let papers = library();
+        let groups = find_duplicates(&papers);
+        assert_eq!(ids(&groups, &papers), [
+            // Preprint and journal version: close titles, same first author
+            vec!["arxiv_1706.03762", "doi_10.5555_attention", "attention-revisited-copy"],
+            // The same DOI under two directory names, whatever their titles
+            vec!["doi_10.1038_nature14539", "deep-learning-review"],
+        ]);
+    }
+
+    #[test]
+    fn test_similar_titles_need_a_shared_author() {
+        let papers = library();
+        let ids: Vec<&str> = find_duplicates(&papers).concat().into_iter().map(|i| papers[i].entry.id.as_str()).collect();
+        // Same title as a grouped paper, by someone else
+        assert!(!ids.contains(&"attention-other-authors"));
+        assert!(!ids.contains(&"graph-networks"));
+    }
+
+    #[test]
+    fn test_render() {
+        let papers = library();
+        let groups = find_duplicates(&papers);
+        let render = |groups: &[Vec<usize>], size: &dyn Fn(&ManifestEntry) -> Option<u64>| {
+            formatter(OutputFormat::Plain).duplicates(&DedupeView::new(groups, &papers, size))
+        };
+        let out = render(&groups[1..], &|e| (e.id != "deep-learning-review").then_some(2_500_000));
+        assert_eq!(out, "1 group of suspected duplicates among 7 papers:\n\n[1]\n\
+            \x20 Deep learning (2015)\n    doi_10.1038_nature14539/paper.pdf  2.5 MB\n\
+            \x20 Deep Learning: A Review (2015)\n    deep-learning-review/paper.pdf  missing\n");
+        assert_eq!(render(&[], &|_| None), "No suspected duplicates among 7 papers.\n");
+    }
+}
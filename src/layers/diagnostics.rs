@@ -0,0 +1,170 @@
+// Collects warnings during a run so the end-of-run summary can show each kind once
+// with a count, instead of the same line repeated for every paper.
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+// How many example subjects (titles, DOIs, ...) to keep per group
+const MAX_EXAMPLES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, schemars::JsonSchema)]
+pub enum Source {
+    SemanticScholar,
+    Arxiv,
+    OpenAlex,
+    Core,
+    Dblp,
+    EuropePmc,
+    Crossref,
+    Unpaywall,
+    Download,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::SemanticScholar => "Semantic Scholar",
+            Source::Arxiv => "arXiv",
+            Source::OpenAlex => "OpenAlex",
+            Source::Core => "CORE",
+            Source::Dblp => "DBLP",
+            Source::EuropePmc => "Europe PMC",
+            Source::Crossref => "Crossref",
+            Source::Unpaywall => "Unpaywall",
+            Source::Download => "download",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, schemars::JsonSchema)]
+pub enum WarningKind {
+    SourceFailed,
+    FiltersRejected,
+    XmlParse,
+    EnrichmentFailed,
+    NotOpenAccess,
+    NoPdfUrl,
+    DownloadFailed,
+    TitleMismatch,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WarningKind::SourceFailed => "search failed",
+            WarningKind::FiltersRejected => "filters rejected, filtered locally",
+            WarningKind::XmlParse => "XML parse error",
+            WarningKind::EnrichmentFailed => "enrichment failed",
+            WarningKind::NotOpenAccess => "skipped, not open access",
+            WarningKind::NoPdfUrl => "skipped, no PDF URL",
+            WarningKind::DownloadFailed => "download failed",
+            WarningKind::TitleMismatch => "PDF title mismatch",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct WarningGroup {
+    pub kind: WarningKind,
+    pub source: Source,
+    pub count: usize,
+    pub examples: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Diagnostics {
+    groups: Mutex<BTreeMap<(WarningKind, Source), WarningGroup>>,
+}
+
+impl Diagnostics {
+    /// Records one occurrence. `subject` is what it happened to (a title, DOI, query...)
+    /// and is kept as an example for the first few occurrences.
+    pub fn warn(&self, kind: WarningKind, source: Source, subject: impl Into<String>) {
+        let mut groups = self.groups.lock().unwrap();
+        let group = groups.entry((kind, source)).or_insert_with(|| WarningGroup {
+            kind,
+            source,
+            count: 0,
+            examples: Vec::new(),
+        });
+        group.count += 1;
+        let subject = subject.into();
+        if group.examples.len() < MAX_EXAMPLES && !group.examples.contains(&subject) {
+            group.examples.push(subject);
+        }
+    }
+
+    /// Groups ordered by kind, then source.
+    pub fn summary(&self) -> Vec<WarningGroup> {
+        self.groups.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Human-readable summary, or an empty string when nothing was recorded.
+    pub fn render(&self) -> String {
+        let groups = self.summary();
+        if groups.is_empty() {
+            return String::new();
+        }
+        let total: usize = groups.iter().map(|g| g.count).sum();
+        let mut out = format!("\n--- {} warning(s) ---\n", total);
+        for group in &groups {
+            out.push_str(&format!("{}: {} (x{})\n", group.source, group.kind, group.count));
+            if !group.examples.is_empty() {
+                let more = if group.count > group.examples.len() { ", ..." } else { "" };
+                out.push_str(&format!("    e.g. {}{}\n", group.examples.join("; "), more));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_by_kind_and_source() {
+        let diagnostics = Diagnostics::default();
+        diagnostics.warn(WarningKind::NotOpenAccess, Source::Download, "A");
+        diagnostics.warn(WarningKind::NotOpenAccess, Source::Download, "B");
+        diagnostics.warn(WarningKind::SourceFailed, Source::Arxiv, "timeout");
+        diagnostics.warn(WarningKind::SourceFailed, Source::OpenAlex, "timeout");
+
+        let summary = diagnostics.summary();
+        assert_eq!(summary.len(), 3);
+        assert_eq!(summary[0].kind, WarningKind::SourceFailed);
+        assert_eq!(summary[0].source, Source::Arxiv);
+        assert_eq!(summary[1].source, Source::OpenAlex);
+        assert_eq!(summary[2].count, 2);
+        assert_eq!(summary[2].examples, vec!["A", "B"]);
+    }
+
+    #[test]
+    fn test_examples_are_capped_and_distinct() {
+        let diagnostics = Diagnostics::default();
+        for subject in ["A", "A", "B", "C", "D", "E"] {
+            diagnostics.warn(WarningKind::NoPdfUrl, Source::Download, subject);
+        }
+        let summary = diagnostics.summary();
+        assert_eq!(summary[0].count, 6);
+        assert_eq!(summary[0].examples, vec!["A", "B", "C"]);
+    }
+
+    #[test]
+    fn test_render() {
+        let diagnostics = Diagnostics::default();
+        assert_eq!(diagnostics.render(), "");
+
+        for title in ["One", "Two", "Three", "Four"] {
+            diagnostics.warn(WarningKind::NotOpenAccess, Source::Download, title);
+        }
+        diagnostics.warn(WarningKind::XmlParse, Source::Arxiv, "position 120");
+        assert_eq!(
+            diagnostics.render(),
+            "\n--- 5 warning(s) ---\n\
+             arXiv: XML parse error (x1)\n    e.g. position 120\n\
+             download: skipped, not open access (x4)\n    e.g. One; Two; Three, ...\n"
+        );
+    }
+}
@@ -1,45 +1,126 @@
 use anyhow::{Result, anyhow};
 use reqwest::Client;
 use serde::Deserialize;
-use crate::layers::{PaperMetadata, DiscoveryQuery};
+use crate::layers::{PaperMetadata, DiscoveryQuery, DiscoverySource, PublicationFilter, Cancelled, S2_FIELD_PREFIX};
+use tokio_util::sync::CancellationToken;
+use crate::layers::legality::{arxiv_pdf_url, classify_version, PaywallHeuristic};
+use crate::layers::arxiv_id::{self, ArxivId};
+use crate::layers::diagnostics::{Diagnostics, Source, WarningKind};
+use crate::layers::merge::MergeIndex;
+use crate::layers::enrichment::{normalize_doi, EnrichmentQueue, Provider};
+use crate::layers::year;
+use crate::layers::encoding;
+use crate::layers::http;
+use futures_util::StreamExt;
+use futures_util::future::LocalBoxFuture;
+use futures_util::stream::FuturesOrdered;
+use tokio::sync::mpsc;
+use std::collections::HashMap;
+use url::Url;
 
+// Semantic Scholar's schema drifts: fields come and go, and a search with no hits has
+// returned `"data": null`. Everything below the page is optional, and each paper is
+// decoded on its own so one odd record doesn't cost the whole page.
 #[derive(Deserialize)]
 struct SSResult {
-    data: Vec<SSPaper>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    data: Vec<serde_json::Value>,
+    // Offset of the next page; absent on the last one
+    #[serde(default)]
+    next: Option<usize>,
+}
+
+struct SSPage {
+    papers: Vec<PaperMetadata>,
+    // Results S2 sent, before local filtering or skipping unreadable ones; 0 means no more
+    served: usize,
+    next: Option<usize>,
+}
+
+// /paper/search/bulk: the same papers, but continued with an opaque token instead of an
+// offset, and `total` counts every match rather than the ones relevance search would serve
+#[derive(Deserialize)]
+struct SSBulkResult {
+    #[serde(default)]
+    total: Option<u64>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    data: Vec<serde_json::Value>,
+    // Absent or null once there are no more batches
+    #[serde(default)]
+    token: Option<String>,
+}
+
+struct SSBulkPage {
+    papers: Vec<PaperMetadata>,
+    total: Option<u64>,
+    token: Option<String>,
+}
+
+fn null_as_default<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SSPaper {
-    paper_id: String,
-    title: String,
-    year: Option<u32>,
+    #[serde(default)]
+    paper_id: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    year: Option<i64>,
+    #[serde(default)]
     venue: Option<String>,
     #[serde(default)]
     abstract_text: Option<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "null_as_default")]
     authors: Vec<SSAuthor>,
+    #[serde(default)]
     external_ids: Option<SSExternalIds>,
+    #[serde(default)]
     is_open_access: Option<bool>,
+    #[serde(default)]
     open_access_pdf: Option<SSOpenAccessPdf>,
+    // Legacy field: a plain list of names, often null
+    #[serde(default)]
+    fields_of_study: Option<Vec<String>>,
+    #[serde(default)]
+    s2_fields_of_study: Option<Vec<SSFieldOfStudy>>,
+    #[serde(default)]
+    citation_count: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SSFieldOfStudy {
+    #[serde(default)]
+    category: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct SSAuthor {
-    name: String,
+    #[serde(default)]
+    name: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct SSExternalIds {
-    #[serde(rename = "DOI")]
+    #[serde(rename = "DOI", default)]
     doi: Option<String>,
-    #[serde(rename = "ArXiv")]
+    #[serde(rename = "ArXiv", default)]
     arxiv: Option<String>,
+    // PubMed, DBLP, CorpusId, ... kept as they come
+    #[serde(flatten)]
+    other: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Deserialize)]
 struct SSOpenAccessPdf {
-    url: String,
+    #[serde(default)]
+    url: Option<String>,
 }
 
 use governor::{Quota, RateLimiter};
@@ -47,31 +128,124 @@ use governor::clock::DefaultClock;
 use governor::state::{InMemoryState, direct::NotKeyed};
 use nonzero_ext::nonzero;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// How a client retries a request the server turned away with 429 or a 5xx.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    // Including the first try
+    pub max_attempts: u32,
+    // Doubled after each retry when the server doesn't send Retry-After
+    pub initial_backoff: Duration,
+    // Longest wait honoured from Retry-After
+    pub max_wait: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 4, initial_backoff: Duration::from_secs(1), max_wait: Duration::from_secs(60) }
+    }
+}
+
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Retry-After as a wait: either delta-seconds ("120") or an HTTP date.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+impl RetryPolicy {
+    /// Sends the request built by `send` until it gets something other than 429 or 5xx, or
+    /// runs out of attempts; the last response is returned either way. `send` is called
+    /// once per attempt, so a client's rate limiter sees every retry.
+    pub async fn send<F, Fut>(&self, source: &str, mut send: F) -> Result<reqwest::Response>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<reqwest::Response>>,
+    {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let resp = send().await?;
+            if !is_retryable(resp.status()) || attempt >= self.max_attempts {
+                return Ok(resp);
+            }
+            let wait = retry_after(resp.headers()).map(|w| w.min(self.max_wait)).unwrap_or(backoff);
+            tracing::warn!("{} returned {} (attempt {} of {}); retrying in {:?}",
+                source, resp.status(), attempt, self.max_attempts, wait);
+            tokio::time::sleep(wait).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+pub const SEMANTIC_SCHOLAR_BASE_URL: &str = "https://api.semanticscholar.org/graph/v1";
+// Results per request; larger limits are fetched a page at a time
+pub const SS_PAGE_SIZE: usize = 100;
+// Relevance search serves at most this many results (offset + limit) per query
+pub const SS_MAX_RESULTS: usize = 1000;
+// Papers per bulk search batch
+pub const SS_BULK_BATCH_SIZE: usize = 1000;
+const SS_FIELDS: &str = "title,authors,year,venue,abstract,externalIds,isOpenAccess,openAccessPdf,fieldsOfStudy,s2FieldsOfStudy,citationCount";
 
 pub struct SemanticScholarClient {
     client: Client,
     api_key: Option<String>,
     limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    retry: RetryPolicy,
+    base_url: String,
+    diagnostics: Arc<Diagnostics>,
 }
 
 impl SemanticScholarClient {
+    /// Time between requests. A key is good for 1 request per second. Without one, requests
+    /// share a pool of roughly 100 per 5 minutes across all anonymous users, so space them out further.
+    pub fn request_interval(has_api_key: bool) -> Duration {
+        Duration::from_secs(if has_api_key { 1 } else { 3 })
+    }
+
     pub fn new(api_key: Option<String>) -> Self {
-        // Set quota: 10 requests per second (safe default)
-        // If the user asked for 500/s, but 429 happens at much lower, we start safe.
-        // Let's implement what was asked: < 500. Let's go with 400.
-        let quota = Quota::per_second(nonzero!(400u32));
+        let quota = Quota::with_period(Self::request_interval(api_key.is_some())).expect("non-zero period");
         
         Self {
-            client: Client::new(),
+            client: http::api_client(),
             api_key,
             limiter: Arc::new(RateLimiter::direct(quota)),
+            retry: RetryPolicy::default(),
+            base_url: SEMANTIC_SCHOLAR_BASE_URL.to_string(),
+            diagnostics: Arc::default(),
         }
     }
 
-    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
-        // Wait for permission
-        self.limiter.until_ready().await;
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        // Mock servers answer at once; don't make tests wait on the real quota
+        self.limiter = Arc::new(RateLimiter::direct(Quota::per_second(nonzero!(1000u32))));
+        self
+    }
+
+    #[cfg(test)]
+    fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Whether a search sends server-side filters, which S2 may reject once with a 400.
+    pub fn has_filters(query_params: &DiscoveryQuery) -> bool {
+        query_params.year_from.is_some() || query_params.year_to.is_some()
+            || query_params.publication_filter.is_some() || query_params.oa_only
+    }
 
+    fn query_text(query_params: &DiscoveryQuery) -> String {
         let mut query = String::new();
         if let Some(title) = &query_params.title {
             query.push_str(title);
@@ -89,54 +263,310 @@ impl SemanticScholarClient {
             query.push_str(cat);
             query.push(' ');
         }
-        
-        let url = format!("https://api.semanticscholar.org/graph/v1/paper/search?query={}&fields=title,authors,year,venue,abstract,externalIds,isOpenAccess,openAccessPdf&limit={}", urlencoding::encode(query.trim()), query_params.limit);
-        
-        let mut request = self.client.get(&url);
-        if let Some(key) = &self.api_key {
-            request = request.header("x-api-key", key);
+        query.trim().to_string()
+    }
+
+    fn build_url(&self, query_params: &DiscoveryQuery, with_filters: bool, offset: usize) -> String {
+        let per_page = query_params.limit_for(DiscoverySource::SemanticScholar).min(SS_PAGE_SIZE);
+        let mut url = format!("{}/paper/search?query={}&fields={}&limit={}", self.base_url, urlencoding::encode(&Self::query_text(query_params)), SS_FIELDS, per_page);
+        if offset > 0 {
+            url.push_str(&format!("&offset={}", offset));
+        }
+        if with_filters {
+            Self::push_filters(&mut url, query_params);
+        }
+        url
+    }
+
+    // Bulk search takes the same filters as relevance search
+    fn bulk_url(&self, query_params: &DiscoveryQuery, with_filters: bool, token: Option<&str>) -> String {
+        let mut url = format!("{}/paper/search/bulk?query={}&fields={}", self.base_url, urlencoding::encode(&Self::query_text(query_params)), SS_FIELDS);
+        if let Some(token) = token {
+            url.push_str(&format!("&token={}", urlencoding::encode(token)));
+        }
+        if with_filters {
+            Self::push_filters(&mut url, query_params);
+        }
+        url
+    }
+
+    fn push_filters(url: &mut String, query_params: &DiscoveryQuery) {
+        // publicationDateOrYear takes an inclusive range with either end open: "2018:", ":2020"
+        if query_params.year_from.is_some() || query_params.year_to.is_some() {
+            let from = query_params.year_from.map(|y| y.to_string()).unwrap_or_default();
+            let to = query_params.year_to.map(|y| y.to_string()).unwrap_or_default();
+            url.push_str(&format!("&publicationDateOrYear={}:{}", from, to));
+        }
+        if let Some(PublicationFilter::Published) = query_params.publication_filter {
+            url.push_str("&publicationTypes=JournalArticle,Conference");
+        }
+        // Valueless filter: only papers with a downloadable PDF. The field itself is already in SS_FIELDS.
+        if query_params.oa_only {
+            url.push_str("&openAccessPdf");
+        }
+    }
+
+    // Client-side equivalent of the server filters, used when S2 rejects the combination
+    fn passes_filters(paper: &PaperMetadata, query_params: &DiscoveryQuery) -> bool {
+        let year_ok = match paper.year {
+            Some(y) => query_params.year_from.is_none_or(|from| y >= from) && query_params.year_to.is_none_or(|to| y <= to),
+            None => query_params.year_from.is_none() && query_params.year_to.is_none(),
+        };
+        let oa_ok = !query_params.oa_only || paper.pdf_url.is_some();
+        year_ok && oa_ok
+    }
+
+    async fn send(&self, url: &str) -> Result<reqwest::Response> {
+        self.retry.send("Semantic Scholar", || async {
+            // Wait for permission
+            self.limiter.until_ready().await;
+
+            let mut request = self.client.get(url);
+            if let Some(key) = &self.api_key {
+                request = request.header("x-api-key", key);
+            }
+
+            tracing::info!("Querying Semantic Scholar: {}", url);
+            request.send().await.map_err(|e| anyhow!("Request failed: {}", e))
+        }).await
+    }
+
+    /// Pages through the results until `limit` papers are collected or Semantic Scholar has no
+    /// more. If a later page fails, the papers from earlier pages are returned.
+    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+        let limit = query_params.limit_for(DiscoverySource::SemanticScholar);
+        let mut with_filters = true;
+        let mut papers = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = match self.fetch_page(query_params, &mut with_filters, offset).await {
+                Ok(page) => page,
+                Err(e) if offset > 0 => {
+                    tracing::warn!("Semantic Scholar page at offset {} failed, keeping {} results: {}", offset, papers.len(), e);
+                    self.diagnostics.warn(WarningKind::SourceFailed, Source::SemanticScholar, format!("page at offset {}: {}", offset, e));
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            // A page filtered locally down to nothing isn't the end of the results
+            let exhausted = page.served == 0;
+            papers.extend(page.papers);
+            match page.next {
+                Some(next) if !exhausted && papers.len() < limit && next > offset && next < SS_MAX_RESULTS => offset = next,
+                _ => break,
+            }
         }
+        papers.truncate(limit);
+        Ok(papers)
+    }
 
-        tracing::info!("Querying Semantic Scholar: {}", url);
-        match request.send().await {
-            Ok(resp) => {
-                if !resp.status().is_success() {
-                    return Err(anyhow!("Semantic Scholar API error: {}", resp.status()));
+    /// Sweeps the bulk search endpoint, following its continuation token until `limit` papers
+    /// are collected. Bulk search doesn't rank by relevance, but serves far more results in
+    /// far fewer requests, which suits author and university sweeps. As with `search`, a failed
+    /// later batch keeps the papers already collected.
+    pub async fn search_bulk(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+        let limit = query_params.limit_for(DiscoverySource::SemanticScholar);
+        let mut with_filters = true;
+        let mut papers = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let text = match self.fetch(query_params, &mut with_filters, |filters| self.bulk_url(query_params, filters, token.as_deref())).await {
+                Ok(text) => text,
+                Err(e) if token.is_some() => {
+                    tracing::warn!("Semantic Scholar bulk batch failed, keeping {} results: {}", papers.len(), e);
+                    self.diagnostics.warn(WarningKind::SourceFailed, Source::SemanticScholar, format!("bulk batch after {} results: {}", papers.len(), e));
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            let mut page = Self::parse_bulk_page(&text)?;
+            if token.is_none() {
+                if let Some(total) = page.total {
+                    tracing::info!("Semantic Scholar bulk search matched {} papers", total);
                 }
-                let results: SSResult = resp.json().await?;
-                Ok(results.data.into_iter().map(|p| PaperMetadata {
-                    title: p.title,
-                    authors: p.authors.into_iter().map(|a| a.name).collect(),
-                    year: p.year,
-                    doi: p.external_ids.as_ref().and_then(|ids| ids.doi.clone()),
-                    arxiv_id: p.external_ids.as_ref().and_then(|ids| ids.arxiv.clone()),
-                    semantic_scholar_id: Some(p.paper_id),
-                    open_alex_id: None,
-                    venue: p.venue,
-                    abstract_text: p.abstract_text,
-                    pdf_url: p.open_access_pdf.map(|pdf| pdf.url),
-                    is_oa: p.is_open_access.unwrap_or(false),
-                    categories: Vec::new(),
-                }).collect())
             }
-            Err(e) => Err(anyhow!("Request failed: {}", e)),
+            if !with_filters {
+                page.papers.retain(|p| Self::passes_filters(p, query_params));
+            }
+            papers.extend(page.papers);
+            // Only the token says whether more batches follow, however few papers passed
+            match page.token.filter(|t| !t.is_empty()) {
+                Some(next) if papers.len() < limit && token.as_deref() != Some(next.as_str()) => token = Some(next),
+                _ => break,
+            }
+        }
+        papers.truncate(limit);
+        Ok(papers)
+    }
+
+    // One page; the first rejected filter combination switches this and later pages to local filtering
+    async fn fetch_page(&self, query_params: &DiscoveryQuery, with_filters: &mut bool, offset: usize) -> Result<SSPage> {
+        let text = self.fetch(query_params, with_filters, |filters| self.build_url(query_params, filters, offset)).await?;
+        let mut page = Self::parse_page(&text)?;
+        if !*with_filters {
+            page.papers.retain(|p| Self::passes_filters(p, query_params));
+        }
+        Ok(page)
+    }
+
+    // The body at `url(with_filters)`, retried once without filters if S2 rejects them
+    async fn fetch(&self, query_params: &DiscoveryQuery, with_filters: &mut bool, url: impl Fn(bool) -> String) -> Result<String> {
+        let mut resp = self.send(&url(*with_filters)).await?;
+
+        if resp.status() == reqwest::StatusCode::BAD_REQUEST && *with_filters && Self::has_filters(query_params) {
+            tracing::warn!("Semantic Scholar rejected the filter combination; retrying unfiltered and filtering locally.");
+            self.diagnostics.warn(WarningKind::FiltersRejected, Source::SemanticScholar, "publication filters");
+            *with_filters = false;
+            resp = self.send(&url(false)).await?;
+        }
+
+        if !resp.status().is_success() {
+            return Err(anyhow!("Semantic Scholar API error: {}", resp.status()));
+        }
+        encoding::response_text(resp, "Semantic Scholar").await
+    }
+
+    // Any arXiv category, then both field lists (legacy first) as s2:-prefixed categories
+    // without case-insensitive repeats
+    fn categories(paper: &SSPaper) -> Vec<String> {
+        let legacy = paper.fields_of_study.iter().flatten().map(|f| f.as_str());
+        let newer = paper.s2_fields_of_study.iter().flatten().filter_map(|f| f.category.as_deref());
+        // Old-style arXiv IDs (cs.DS/0301001) carry their category
+        let mut categories: Vec<String> = paper.external_ids.as_ref()
+            .and_then(|ids| ids.arxiv.as_deref())
+            .and_then(ArxivId::parse)
+            .and_then(|a| a.category())
+            .into_iter()
+            .collect();
+        for field in legacy.chain(newer) {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+            let category = format!("{}{}", S2_FIELD_PREFIX, field);
+            if !categories.iter().any(|c| c.eq_ignore_ascii_case(&category)) {
+                categories.push(category);
+            }
         }
+        categories
+    }
+
+    #[cfg(test)]
+    fn parse_response(json: &str) -> Result<Vec<PaperMetadata>> {
+        Ok(Self::parse_page(json)?.papers)
+    }
+
+    fn parse_page(json: &str) -> Result<SSPage> {
+        let results: SSResult = serde_json::from_str(json)?;
+        Ok(SSPage { served: results.data.len(), papers: Self::decode_papers(results.data), next: results.next })
+    }
+
+    fn parse_bulk_page(json: &str) -> Result<SSBulkPage> {
+        let results: SSBulkResult = serde_json::from_str(json)?;
+        Ok(SSBulkPage { papers: Self::decode_papers(results.data), total: results.total, token: results.token })
+    }
+
+    fn decode_papers(data: Vec<serde_json::Value>) -> Vec<PaperMetadata> {
+        data.into_iter().filter_map(|raw| {
+            match serde_json::from_value::<SSPaper>(raw).map_err(anyhow::Error::from).and_then(Self::map_paper) {
+                Ok(paper) => Some(paper),
+                Err(e) => {
+                    tracing::warn!("Skipping a Semantic Scholar result that could not be read: {}", e);
+                    None
+                }
+            }
+        }).collect()
+    }
+
+    fn map_paper(p: SSPaper) -> Result<PaperMetadata> {
+        let categories = Self::categories(&p);
+        let title = p.title.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+            .ok_or_else(|| anyhow!("paper {} has no title", p.paper_id.as_deref().unwrap_or("without an ID")))?;
+        let (year, rejected_years) = year::check(p.year);
+        let (doi, arxiv_id, external_ids) = match p.external_ids {
+            Some(ids) => {
+                let other = ids.other.into_iter().filter_map(|(key, value)| match value {
+                    serde_json::Value::String(s) => Some((key, s)),
+                    serde_json::Value::Number(n) => Some((key, n.to_string())),
+                    _ => None,
+                }).collect();
+                (ids.doi, ids.arxiv, other)
+            }
+            None => (None, None, HashMap::new()),
+        };
+        Ok(PaperMetadata {
+            categories,
+            title,
+            authors: p.authors.into_iter().filter_map(|a| a.name).collect(),
+            year,
+            rejected_years,
+            doi,
+            arxiv_id,
+            external_ids,
+            semantic_scholar_id: p.paper_id,
+            open_alex_id: None,
+            venue: p.venue,
+            abstract_text: p.abstract_text,
+            citation_count: p.citation_count,
+            pdf_url: p.open_access_pdf.and_then(|pdf| pdf.url).filter(|u| !u.is_empty()),
+            is_oa: p.is_open_access.unwrap_or(false),
+            ..Default::default()
+        })
     }
 }
 
 use quick_xml::events::Event;
 use quick_xml::reader::Reader;
 
+pub const ARXIV_BASE_URL: &str = "http://export.arxiv.org/api/query";
+// arXiv's API terms ask for no more than one request every three seconds
+pub const ARXIV_REQUEST_INTERVAL: Duration = Duration::from_secs(3);
+// Entries per request; arXiv serves up to 2000 but recommends smaller slices
+pub const ARXIV_PAGE_SIZE: usize = 100;
+
+/// Spaces requests to arXiv; one is shared by the search and the e-print downloads of a run.
+pub type ArxivLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>;
+
+pub fn arxiv_limiter() -> ArxivLimiter {
+    Arc::new(RateLimiter::direct(Quota::with_period(ARXIV_REQUEST_INTERVAL).expect("non-zero period")))
+}
+
 pub struct ArxivClient {
     client: Client,
+    // arXiv's terms ask for at most one request every three seconds; shared by every call
+    limiter: ArxivLimiter,
+    // A 503 is arXiv's throttle response; one retry after a long pause
+    retry: RetryPolicy,
+    base_url: String,
+    diagnostics: Arc<Diagnostics>,
 }
 
 impl ArxivClient {
     pub fn new() -> Self {
-        Self { client: Client::new() }
+        Self {
+            client: http::api_client(),
+            limiter: arxiv_limiter(),
+            retry: RetryPolicy { max_attempts: 2, initial_backoff: Duration::from_secs(10), ..Default::default() },
+            base_url: ARXIV_BASE_URL.to_string(),
+            diagnostics: Arc::default(),
+        }
     }
 
-    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self.retry.initial_backoff = Duration::from_millis(1);
+        self
+    }
+
+    #[cfg(test)]
+    fn with_quota(mut self, quota: Quota) -> Self {
+        self.limiter = Arc::new(RateLimiter::direct(quota));
+        self
+    }
+
+    /// The URL of the page starting at entry `start`.
+    fn build_url(&self, query_params: &DiscoveryQuery, start: usize) -> String {
         let mut query = String::new();
         if let Some(title) = &query_params.title {
             query.push_str(&format!("ti:\"{}\"", title));
@@ -156,170 +586,262 @@ impl ArxivClient {
             query.push_str(&format!("all:\"{}\"", uni));
         }
 
-        let url = format!("http://export.arxiv.org/api/query?search_query={}&start=0&max_results={}", urlencoding::encode(&query), query_params.limit);
-        tracing::info!("Querying arXiv: {}", url);
-        
-        match self.client.get(&url).send().await {
-            Ok(resp) => {
-                if !resp.status().is_success() {
-                    return Err(anyhow!("arXiv API error: {}", resp.status()));
+        let per_page = query_params.limit_for(DiscoverySource::Arxiv).saturating_sub(start).clamp(1, ARXIV_PAGE_SIZE);
+        let mut url = format!("{}?search_query={}&start={}&max_results={}", self.base_url, urlencoding::encode(&query), start, per_page);
+        // Relevance is arXiv's default order, so it needs no parameters
+        if let Some((sort_by, sort_order)) = query_params.arxiv_sort.params() {
+            url.push_str(&format!("&sortBy={}&sortOrder={}", sort_by, sort_order));
+        }
+        url
+    }
+
+    async fn send(&self, url: &str) -> Result<reqwest::Response> {
+        self.retry.send("arXiv", || async {
+            self.limiter.until_ready().await;
+            tracing::info!("Querying arXiv: {}", url);
+            self.client.get(url).send().await.map_err(|e| anyhow!("Request failed: {}", e))
+        }).await
+    }
+
+    /// Pages through the feed until `limit` entries are parsed or a page comes back short.
+    /// Every page waits for the rate limiter; if a later page fails, the earlier ones are kept.
+    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+        let limit = query_params.limit_for(DiscoverySource::Arxiv);
+        let mut papers = Vec::new();
+        loop {
+            let start = papers.len();
+            let requested = (limit - start).min(ARXIV_PAGE_SIZE);
+            let page = match self.fetch_page(&self.build_url(query_params, start)).await {
+                Ok(page) => page,
+                Err(e) if start > 0 => {
+                    tracing::warn!("arXiv page at start={} failed, keeping {} results: {}", start, papers.len(), e);
+                    self.diagnostics.warn(WarningKind::SourceFailed, Source::Arxiv, format!("page at start={}: {}", start, e));
+                    break;
                 }
-                let text = resp.text().await?;
-                
-                // Manual XML Parsing with detailed extraction
-                let mut reader = Reader::from_str(&text);
-                reader.config_mut().trim_text(true);
-
-                let mut papers = Vec::new();
-                let mut buf = Vec::new();
-
-                // Temp vars for current entry
-                let mut in_entry = false;
-                let mut title = String::new();
-                let mut summary = String::new();
-                let mut year = None;
-                let mut authors = Vec::new();
-                let mut links = Vec::new(); // (href, title, type)
-                let mut id = String::new();
-
-                // Parsing State
-                #[derive(PartialEq)]
-                enum TagState { None, Title, Summary, Published, AuthorName, Id }
-                let mut state = TagState::None;
-
-                loop {
-                    match reader.read_event_into(&mut buf) {
-                        Ok(Event::Start(e)) => {
-                            match e.name().as_ref() {
-                                b"entry" => {
-                                    in_entry = true;
-                                    title.clear(); summary.clear(); year = None; authors.clear(); links.clear(); id.clear();
-                                },
-                                b"title" if in_entry => state = TagState::Title,
-                                b"summary" if in_entry => state = TagState::Summary,
-                                b"published" if in_entry => state = TagState::Published,
-                                b"name" if in_entry => state = TagState::AuthorName,
-                                b"id" if in_entry => state = TagState::Id,
-                                // 'link' with content (rare for Atom)
-                                b"link" if in_entry => {
-                                    let mut href = String::new();
-                                    let mut title_attr = String::new();
-                                    let mut type_attr = String::new();
-                                    for attr in e.attributes().flatten() {
-                                        match attr.key.as_ref() {
-                                            b"href" => href = String::from_utf8_lossy(&attr.value).to_string(),
-                                            b"title" => title_attr = String::from_utf8_lossy(&attr.value).to_string(),
-                                            b"type" => type_attr = String::from_utf8_lossy(&attr.value).to_string(),
-                                            _ => {}
-                                        }
-                                    }
-                                    links.push((href, title_attr, type_attr));
-                                }
-                                _ => state = TagState::None,
-                            }
-                        }
-                        Ok(Event::Empty(e)) => {
-                            match e.name().as_ref() {
-                                b"link" if in_entry => {
-                                    let mut href = String::new();
-                                    let mut title_attr = String::new();
-                                    let mut type_attr = String::new();
-                                    for attr in e.attributes().flatten() {
-                                        match attr.key.as_ref() {
-                                            b"href" => href = String::from_utf8_lossy(&attr.value).to_string(),
-                                            b"title" => title_attr = String::from_utf8_lossy(&attr.value).to_string(),
-                                            b"type" => type_attr = String::from_utf8_lossy(&attr.value).to_string(),
-                                            _ => {}
-                                        }
-                                    }
-                                    tracing::debug!("Found (empty) link in entry {}: href={}, title={}, type={}", id.clone(), href, title_attr, type_attr);
-                                    links.push((href, title_attr, type_attr));
+                Err(e) => return Err(e),
+            };
+            let short = page.len() < requested;
+            papers.extend(page);
+            if short || papers.len() >= limit {
+                break;
+            }
+        }
+        papers.truncate(limit);
+        Ok(papers)
+    }
+
+    async fn fetch_page(&self, url: &str) -> Result<Vec<PaperMetadata>> {
+        let resp = self.send(url).await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("arXiv API error: {}", resp.status()));
+        }
+        let text = encoding::response_text(resp, "arXiv").await?;
+        Ok(self.parse_feed(&text))
+    }
+
+    // Manual XML Parsing with detailed extraction
+    fn parse_feed(&self, text: &str) -> Vec<PaperMetadata> {
+        let mut reader = Reader::from_str(text);
+        reader.config_mut().trim_text(true);
+
+        let mut papers = Vec::new();
+        let mut buf = Vec::new();
+
+        // Temp vars for current entry
+        let mut in_entry = false;
+        let mut title = String::new();
+        let mut summary = String::new();
+        let mut published = String::new();
+        let mut authors = Vec::new();
+        let mut links = Vec::new(); // (href, title, type)
+        let mut id = String::new();
+        let mut primary_category = None;
+        let mut categories = Vec::new();
+        let mut doi = None;
+
+        // Parsing State
+        #[derive(PartialEq)]
+        enum TagState { None, Title, Summary, Published, AuthorName, Id, Doi }
+        let mut state = TagState::None;
+
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) => {
+                    match e.name().as_ref() {
+                        b"entry" => {
+                            in_entry = true;
+                            title.clear(); summary.clear(); published.clear(); authors.clear(); links.clear(); id.clear();
+                            primary_category = None; categories.clear(); doi = None;
+                        },
+                        b"title" if in_entry => state = TagState::Title,
+                        b"summary" if in_entry => state = TagState::Summary,
+                        b"published" if in_entry => state = TagState::Published,
+                        b"name" if in_entry => state = TagState::AuthorName,
+                        b"id" if in_entry => state = TagState::Id,
+                        // Set by the authors once the paper is published
+                        b"arxiv:doi" if in_entry => state = TagState::Doi,
+                        // 'link' with content (rare for Atom)
+                        b"link" if in_entry => {
+                            let mut href = String::new();
+                            let mut title_attr = String::new();
+                            let mut type_attr = String::new();
+                            for attr in e.attributes().flatten() {
+                                match attr.key.as_ref() {
+                                    b"href" => href = String::from_utf8_lossy(&attr.value).to_string(),
+                                    b"title" => title_attr = String::from_utf8_lossy(&attr.value).to_string(),
+                                    b"type" => type_attr = String::from_utf8_lossy(&attr.value).to_string(),
+                                    _ => {}
                                 }
-                                _ => {}
                             }
+                            links.push((href, title_attr, type_attr));
                         }
-                        Ok(Event::Text(e)) => {
-                            if in_entry {
-                                let txt = String::from_utf8_lossy(&e.into_inner()).into_owned();
-                                match state {
-                                    TagState::Title => title = txt,
-                                    TagState::Summary => summary = txt,
-                                    TagState::Published => {
-                                        if let Some(y_str) = txt.split('-').next() {
-                                            year = y_str.parse().ok();
-                                        }
-                                    }
-                                    TagState::AuthorName => authors.push(txt),
-                                    TagState::Id => id = txt,
+                        _ => state = TagState::None,
+                    }
+                }
+                Ok(Event::Empty(e)) => {
+                    match e.name().as_ref() {
+                        b"link" if in_entry => {
+                            let mut href = String::new();
+                            let mut title_attr = String::new();
+                            let mut type_attr = String::new();
+                            for attr in e.attributes().flatten() {
+                                match attr.key.as_ref() {
+                                    b"href" => href = String::from_utf8_lossy(&attr.value).to_string(),
+                                    b"title" => title_attr = String::from_utf8_lossy(&attr.value).to_string(),
+                                    b"type" => type_attr = String::from_utf8_lossy(&attr.value).to_string(),
                                     _ => {}
                                 }
                             }
+                            tracing::debug!("Found (empty) link in entry {}: href={}, title={}, type={}", id.clone(), href, title_attr, type_attr);
+                            links.push((href, title_attr, type_attr));
                         }
-                        Ok(Event::End(e)) => {
-                            match e.name().as_ref() {
-                                b"entry" => {
-                                    in_entry = false;
-                                    tracing::info!("Parsed Arxiv Entry: Title='{}', ID='{}', Links={}", title, id, links.len());
-                                    
-                                    let pdf_url = links.iter()
-                                        .find(|(_, t, ty)| t == "pdf" || ty == "application/pdf")
-                                        .map(|(h, _, _)| h.clone());
-
-                                    papers.push(PaperMetadata {
-                                        title: title.replace('\n', " ").trim().to_string(),
-                                        authors: authors.clone(),
-                                        year,
-                                        doi: None,
-                                        arxiv_id: Some(id.clone()),
-                                        semantic_scholar_id: None,
-                                        open_alex_id: None,
-                                        venue: Some("arXiv".to_string()),
-                                        abstract_text: Some(summary.trim().to_string()),
-                                        pdf_url: pdf_url.clone().map(|u| {
-                                             tracing::info!("Found arXiv PDF link: {}", u);
-                                             u
-                                        }),
-                                        is_oa: true,
-                                        categories: Vec::new(),
-                                    });
-                                },
-                                _ => state = TagState::None,
+                        b"category" | b"arxiv:primary_category" if in_entry => {
+                            let term = e.attributes().flatten()
+                                .find(|attr| attr.key.as_ref() == b"term")
+                                .map(|attr| String::from_utf8_lossy(&attr.value).trim().to_string())
+                                .filter(|term| !term.is_empty());
+                            if e.name().as_ref() == b"category" {
+                                categories.extend(term);
+                            } else {
+                                primary_category = term;
                             }
                         }
-                        Ok(Event::Eof) => break,
-                        Err(e) => {
-                            tracing::warn!("XML parsing error at position {}: {:?}", reader.buffer_position(), e);
-                            break;
-                        }
                         _ => {}
                     }
-                    buf.clear();
                 }
+                Ok(Event::Text(e)) if in_entry => {
+                    let txt = String::from_utf8_lossy(&e.into_inner()).into_owned();
+                    match state {
+                        TagState::Title => title = txt,
+                        TagState::Summary => summary = txt,
+                        TagState::Published => published = txt,
+                        TagState::AuthorName => authors.push(txt),
+                        TagState::Id => id = txt,
+                        TagState::Doi => doi = Some(txt.trim().to_string()).filter(|d| !d.is_empty()),
+                        _ => {}
+                    }
+                }
+                Ok(Event::End(e)) => {
+                    match e.name().as_ref() {
+                        b"entry" => {
+                            in_entry = false;
+                            tracing::info!("Parsed Arxiv Entry: Title='{}', ID='{}', Links={}", title, id, links.len());
+                            
+                            // <id> is the versioned abs URL; keep the bare ID, and the category old-style IDs carry
+                            let parsed_id = ArxivId::parse(&id);
+                            // Some entries link only the abstract page; the PDF of the same version is there regardless
+                            let pdf_url = links.iter()
+                                .find(|(_, t, ty)| t == "pdf" || ty == "application/pdf")
+                                .map(|(h, _, _)| h.clone())
+                                .or_else(|| parsed_id.as_ref().map(ArxivId::pdf_url));
+                            let arxiv_id = arxiv_id::normalize(&id);
+                            // Primary category first, then the cross-lists in feed order
+                            let mut entry_categories: Vec<String> = Vec::new();
+                            for category in primary_category.take().into_iter().chain(categories.drain(..)) {
+                                if !entry_categories.contains(&category) {
+                                    entry_categories.push(category);
+                                }
+                            }
+                            // An old-style ID's archive ("math") adds nothing next to a listed "math.GT"
+                            if let Some(category) = parsed_id.and_then(|a| a.category()) {
+                                let prefix = format!("{}.", category);
+                                if !entry_categories.iter().any(|c| *c == category || c.starts_with(&prefix)) {
+                                    entry_categories.push(category);
+                                }
+                            }
 
-                Ok(papers)
+                            let (year, rejected_years) = year::check_text(Some(&published));
+                            papers.push(PaperMetadata {
+                                title: title.replace('\n', " ").trim().to_string(),
+                                authors: authors.clone(),
+                                year,
+                                rejected_years,
+                                doi: doi.take(),
+                                arxiv_id: Some(arxiv_id),
+                                semantic_scholar_id: None,
+                                open_alex_id: None,
+                                venue: Some("arXiv".to_string()),
+                                abstract_text: Some(summary.trim().to_string()),
+                                pdf_url: pdf_url.clone().map(|u| {
+                                     tracing::info!("Found arXiv PDF link: {}", u);
+                                     u
+                                }),
+                                is_oa: true,
+                                categories: entry_categories,
+                                ..Default::default()
+                            });
+                        },
+                        _ => state = TagState::None,
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => {
+                    tracing::warn!("XML parsing error at position {}: {:?}", reader.buffer_position(), e);
+                    self.diagnostics.warn(WarningKind::XmlParse, Source::Arxiv, format!("position {}", reader.buffer_position()));
+                    break;
+                }
+                _ => {}
             }
-            Err(e) => Err(anyhow!("Request failed: {}", e)),
+            buf.clear();
         }
+
+        papers
     }
 }
 
 // OpenAlex Data Structures
 #[derive(Deserialize)]
 struct OAResponse {
+    meta: Option<OAMeta>,
     results: Vec<OAWork>,
 }
 
+#[derive(Deserialize)]
+struct OAMeta {
+    count: Option<u64>,
+    // Null on the last page
+    next_cursor: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct OAWork {
     id: String,
     title: Option<String>,
-    publication_year: Option<u32>,
+    publication_year: Option<i64>,
     ids: Option<OAIds>,
     authorships: Vec<OAAuthorship>,
+    primary_location: Option<OALocation>,
     best_oa_location: Option<OALocation>,
     #[serde(default)]
-    #[allow(dead_code)]
-    abstract_inverted_index: Option<serde_json::Value>, // We won't reconstruct abstract for now, complex
+    locations: Vec<OALocation>,
+    open_access: Option<OAOpenAccess>,
+    // "article", "preprint", "book-chapter", ...
+    #[serde(rename = "type")]
+    work_type: Option<String>,
+    #[serde(default)]
+    abstract_inverted_index: Option<HashMap<String, Vec<usize>>>,
+    #[serde(default)]
+    cited_by_count: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -342,154 +864,2114 @@ struct OAAuthor {
 
 #[derive(Deserialize)]
 struct OALocation {
+    landing_page_url: Option<String>,
     pdf_url: Option<String>,
     is_oa: bool,
+    version: Option<String>,
+    source: Option<OASource>,
+}
+
+impl OALocation {
+    // The arXiv ID, when this location is arXiv's abs page or PDF
+    fn arxiv_id(&self) -> Option<ArxivId> {
+        [&self.landing_page_url, &self.pdf_url].into_iter().flatten()
+            .filter(|url| url.contains("arxiv.org"))
+            .find_map(|url| ArxivId::parse(url))
+    }
+}
+
+#[derive(Deserialize)]
+struct OASource {
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OAOpenAccess {
+    oa_status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OAInstitutionResponse {
+    results: Vec<OAInstitution>,
+}
+
+#[derive(Deserialize)]
+struct OAInstitution {
+    // "https://openalex.org/I63966007"
+    id: String,
+    display_name: String,
 }
 
+pub const OPENALEX_BASE_URL: &str = "https://api.openalex.org";
+// Institution matches fetched when resolving --university; the first is used, the rest are logged
+const INSTITUTION_CANDIDATES: usize = 5;
+// The most works OpenAlex returns per page; larger limits are fetched a page at a time
+pub const OPENALEX_PAGE_SIZE: usize = 200;
+
 pub struct OpenAlexClient {
     client: Client,
     email: Option<String>,
+    retry: RetryPolicy,
+    base_url: String,
+    diagnostics: Arc<Diagnostics>,
 }
 
 impl OpenAlexClient {
     pub fn new(email: Option<String>) -> Self {
         Self {
-            client: Client::new(),
+            client: http::api_client(),
             email,
+            retry: RetryPolicy::default(),
+            base_url: OPENALEX_BASE_URL.to_string(),
+            diagnostics: Arc::default(),
         }
     }
 
-    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
-        // Use 'filter' for institution if provided, otherwise 'search'
-        let mut url = "https://api.openalex.org/works?".to_string();
-        
-        let mut filters = Vec::new();
-        if let Some(uni) = &query_params.university {
-            // Encode value but keep key and colon raw if possible, or handle carefully.
-            // OpenAlex expects filter=key:value. 
-            // We shouldn't encode the colon if possible, but we must encode the value.
-            filters.push(format!("raw_affiliation_strings.search:{}", uni));
-        }
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
 
-        let mut search_parts = Vec::new();
-        if let Some(title) = &query_params.title {
-             search_parts.push(title.clone());
+    #[cfg(test)]
+    fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    // `path` under the base URL with the query pairs encoded, and the polite pool's address
+    // last, since OpenAlex needs it on every request
+    fn url(&self, path: &str, pairs: &[(&str, &str)]) -> String {
+        let mut url = Url::parse(&format!("{}{}", self.base_url, path)).expect("the OpenAlex base URL is valid");
+        {
+            let mut query = url.query_pairs_mut();
+            query.extend_pairs(pairs);
+            if let Some(email) = &self.email {
+                query.append_pair("mailto", email);
+            }
         }
-        if let Some(author) = &query_params.author {
-             search_parts.push(author.clone());
+        url.into()
+    }
+
+    /// The URL of one page; `cursor` is "*" for the first and `meta.next_cursor` after that.
+    /// `institution` is the OpenAlex ID `--university` resolved to; without one the name is
+    /// matched against the raw affiliation strings.
+    fn build_url(&self, query_params: &DiscoveryQuery, cursor: &str, institution: Option<&str>) -> String {
+        let filter = match (institution, &query_params.university) {
+            (Some(id), _) => Some(format!("institutions.id:{}", id)),
+            (None, Some(uni)) => Some(format!("raw_affiliation_strings.search:{}", uni)),
+            (None, None) => None,
+        };
+        let search: Vec<&str> = [&query_params.title, &query_params.author, &query_params.category]
+            .into_iter().flatten().map(String::as_str).collect();
+        let search = search.join(" ");
+        let per_page = query_params.limit_for(DiscoverySource::OpenAlex).min(OPENALEX_PAGE_SIZE).to_string();
+
+        let mut pairs = Vec::new();
+        if let Some(filter) = &filter {
+            pairs.push(("filter", filter.as_str()));
         }
-        if let Some(cat) = &query_params.category {
-             search_parts.push(cat.clone());
-        }
-
-        if !filters.is_empty() {
-             url.push_str("filter=");
-             // Manually build the filter string: key:value,key2:value2
-             // We need to encode the VALUES.
-             let encoded_filters: Vec<String> = filters.iter().map(|f| {
-                 let parts: Vec<&str> = f.splitn(2, ':').collect();
-                 if parts.len() == 2 {
-                     format!("{}:{}", parts[0], urlencoding::encode(parts[1]))
-                 } else {
-                     f.clone() // fallback
-                 }
-             }).collect();
-             url.push_str(&encoded_filters.join(","));
-             
-             if !search_parts.is_empty() {
-                 url.push_str("&");
-             }
-        }
-
-        if !search_parts.is_empty() {
-            url.push_str("search=");
-            url.push_str(&urlencoding::encode(&search_parts.join(" ")));
-        }
-
-        // Add limit
-        if url.contains('?') {
-            url.push_str("&");
-        } else {
-            url.push_str("?");
+        if !search.is_empty() {
+            pairs.push(("search", search.as_str()));
         }
-        url.push_str(&format!("per_page={}", query_params.limit));
-        
-        if let Some(email) = &self.email {
-            url.push_str(&format!("&mailto={}", email));
+        pairs.push(("per_page", &per_page));
+        pairs.push(("cursor", cursor));
+        self.url("/works", &pairs)
+    }
+
+    async fn get(&self, url: &str) -> Result<String> {
+        let resp = self.retry.send("OpenAlex", || async {
+            self.client.get(url).send().await.map_err(|e| anyhow!("Request failed: {}", e))
+        }).await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("OpenAlex API error: {}", resp.status()));
         }
+        encoding::response_text(resp, "OpenAlex").await
+    }
 
-        tracing::info!("Querying OpenAlex: {}", url);
-        match self.client.get(&url).send().await {
-            Ok(resp) => {
-                if !resp.status().is_success() {
-                    return Err(anyhow!("OpenAlex API error: {}", resp.status()));
+    /// Follows the result cursor until `limit` works are collected or the results run out.
+    /// If a later page fails, the works from earlier pages are returned.
+    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+        let limit = query_params.limit_for(DiscoverySource::OpenAlex);
+        let institution = match &query_params.university {
+            Some(name) => self.resolve_institution(name).await,
+            None => None,
+        };
+        let mut papers = Vec::new();
+        let mut cursor = "*".to_string();
+        loop {
+            let url = self.build_url(query_params, &cursor, institution.as_deref());
+            tracing::info!("Querying OpenAlex: {}", url);
+            let (page, meta) = match self.get(&url).await.and_then(|text| Self::parse_page(&text)) {
+                Ok(page) => page,
+                Err(e) if cursor != "*" => {
+                    tracing::warn!("OpenAlex page after {} results failed, keeping them: {}", papers.len(), e);
+                    self.diagnostics.warn(WarningKind::SourceFailed, Source::OpenAlex, format!("page after {} results: {}", papers.len(), e));
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            if cursor == "*" {
+                if let Some(count) = meta.as_ref().and_then(|m| m.count) {
+                    tracing::info!("OpenAlex: {} matching works, fetching up to {}", count, limit);
                 }
-                let oa_resp: OAResponse = resp.json().await?;
-                
-                Ok(oa_resp.results.into_iter().map(|work| {
-                    let authors = work.authorships.into_iter().map(|a| a.author.display_name).collect();
-                    PaperMetadata {
-                        title: work.title.unwrap_or_else(|| "Untitled".to_string()),
-                        authors,
-                        year: work.publication_year,
-                        doi: work.ids.as_ref().and_then(|ids| ids.doi.clone()),
-                        // OpenAlex doesn't always give Arxiv ID easily in top level IDs, 
-                        // sometimes it's in detailed location. Skipping for now.
-                        arxiv_id: None, 
-                        semantic_scholar_id: None,
-                        open_alex_id: Some(work.id),
-                        venue: None, // Could parse, but skipping for brevity
-                        abstract_text: None, // Requires reconstructing from inverted index
-                        pdf_url: work.best_oa_location.as_ref().and_then(|loc| loc.pdf_url.clone()),
-                        is_oa: work.best_oa_location.map(|loc| loc.is_oa).unwrap_or(false),
-                        categories: Vec::new(),
-                    }
-                }).collect())
             }
-            Err(e) => Err(anyhow!("Request failed: {}", e)),
+            let exhausted = page.is_empty();
+            papers.extend(page);
+            match meta.and_then(|m| m.next_cursor) {
+                Some(next) if !exhausted && papers.len() < limit => cursor = next,
+                _ => break,
+            }
         }
+        papers.truncate(limit);
+        Ok(papers)
     }
-}
 
-pub struct DiscoveryOrchestrator {
-    ss_client: SemanticScholarClient,
-    arxiv_client: ArxivClient,
-    open_alex_client: OpenAlexClient,
-}
+    fn institutions_url(&self, name: &str) -> String {
+        self.url("/institutions", &[("search", name), ("per_page", &INSTITUTION_CANDIDATES.to_string())])
+    }
 
-impl DiscoveryOrchestrator {
-    pub fn new(ss_api_key: Option<String>, open_alex_email: Option<String>) -> Self {
-        Self {
-            ss_client: SemanticScholarClient::new(ss_api_key),
-            arxiv_client: ArxivClient::new(),
-            open_alex_client: OpenAlexClient::new(open_alex_email),
+    /// The ID of the institution OpenAlex ranks first for `name` ("MIT" -> "I63966007"), or None
+    /// when nothing matches or the lookup fails, in which case the affiliation search is used.
+    async fn resolve_institution(&self, name: &str) -> Option<String> {
+        let url = self.institutions_url(name);
+        tracing::info!("Resolving institution on OpenAlex: {}", url);
+        let institutions = match self.get(&url).await.and_then(|text| Self::parse_institutions(&text)) {
+            Ok(institutions) => institutions,
+            Err(e) => {
+                tracing::warn!("Institution lookup for '{}' failed, searching affiliations instead: {}", name, e);
+                return None;
+            }
+        };
+        let Some(chosen) = institutions.first() else {
+            tracing::info!("No OpenAlex institution matches '{}'; searching affiliations instead", name);
+            return None;
+        };
+        let id = chosen.id.rsplit('/').next().unwrap_or(&chosen.id).to_string();
+        if institutions.len() > 1 {
+            let others: Vec<&str> = institutions[1..].iter().map(|i| i.display_name.as_str()).collect();
+            tracing::info!("'{}' resolved to {} ({}); other matches: {}", name, chosen.display_name, id, others.join("; "));
+        } else {
+            tracing::info!("'{}' resolved to {} ({})", name, chosen.display_name, id);
         }
+        Some(id)
     }
 
-    pub async fn search_all(&self, query: &DiscoveryQuery) -> Vec<PaperMetadata> {
-        let ss_fut = self.ss_client.search(query);
-        let arxiv_fut = self.arxiv_client.search(query);
-        let oa_fut = self.open_alex_client.search(query);
+    fn parse_institutions(json: &str) -> Result<Vec<OAInstitution>> {
+        let resp: OAInstitutionResponse = serde_json::from_str(json)?;
+        Ok(resp.results)
+    }
 
-        let (ss_res, arxiv_res, oa_res) = tokio::join!(ss_fut, arxiv_fut, oa_fut);
+    fn parse_page(json: &str) -> Result<(Vec<PaperMetadata>, Option<OAMeta>)> {
+        let oa_resp: OAResponse = serde_json::from_str(json)?;
+        Ok((oa_resp.results.into_iter().map(Self::map_work).collect(), oa_resp.meta))
+    }
 
-        let mut all_results = Vec::new();
+    #[cfg(test)]
+    fn parse_response(json: &str) -> Result<Vec<PaperMetadata>> {
+        Ok(Self::parse_page(json)?.0)
+    }
 
-        match ss_res {
-            Ok(results) => all_results.extend(results),
-            Err(e) => tracing::warn!("Semantic Scholar discovery failed: {}", e),
+    fn map_work(work: OAWork) -> PaperMetadata {
+        let authors = work.authorships.into_iter().map(|a| a.author.display_name).collect();
+        let version_hint = classify_version(
+            work.open_access.as_ref().and_then(|oa| oa.oa_status.as_deref()),
+            work.best_oa_location.as_ref().and_then(|loc| loc.version.as_deref()),
+            work.best_oa_location.as_ref().and_then(|loc| loc.pdf_url.as_deref()),
+        );
+        let (year, rejected_years) = year::check(work.publication_year);
+        let arxiv_id = work.primary_location.iter().chain(&work.best_oa_location).chain(&work.locations)
+            .find_map(OALocation::arxiv_id);
+        // arXiv's source is "arXiv (Cornell University)"; the plain name lets a publisher venue win the merge
+        let venue = work.primary_location.as_ref()
+            .and_then(|loc| loc.source.as_ref()).and_then(|source| source.display_name.clone())
+            .map(|name| if name.starts_with("arXiv") { "arXiv".to_string() } else { name });
+        PaperMetadata {
+            title: work.title.unwrap_or_else(|| "Untitled".to_string()),
+            authors,
+            year,
+            rejected_years,
+            doi: work.ids.as_ref().and_then(|ids| ids.doi.clone()),
+            arxiv_id: arxiv_id.map(|id| id.to_string()),
+            semantic_scholar_id: None,
+            open_alex_id: Some(work.id),
+            venue,
+            publication_type: work.work_type,
+            abstract_text: work.abstract_inverted_index.as_ref().and_then(reconstruct_abstract),
+            citation_count: work.cited_by_count,
+            pdf_url: work.best_oa_location.as_ref().and_then(|loc| loc.pdf_url.clone()),
+            is_oa: work.best_oa_location.map(|loc| loc.is_oa).unwrap_or(false),
+            categories: Vec::new(),
+            version_hint,
+            ..Default::default()
         }
+    }
+}
 
-        match arxiv_res {
-            Ok(results) => all_results.extend(results),
-            Err(e) => tracing::warn!("arXiv discovery failed: {}", e),
-        }
+/// OpenAlex ships abstracts as word -> positions; lay the words back out in position order.
+fn reconstruct_abstract(index: &HashMap<String, Vec<usize>>) -> Option<String> {
+    let mut positioned: Vec<(usize, &str)> = index.iter()
+        .flat_map(|(word, positions)| positions.iter().map(move |&p| (p, word.as_str())))
+        .collect();
+    if positioned.is_empty() {
+        return None;
+    }
+    positioned.sort_by_key(|(p, _)| *p);
+    Some(positioned.into_iter().map(|(_, w)| w).collect::<Vec<_>>().join(" "))
+}
 
-        match oa_res {
-            Ok(results) => all_results.extend(results),
-            Err(e) => tracing::warn!("OpenAlex discovery failed: {}", e),
-        }
+// CORE Data Structures
+#[derive(Deserialize)]
+struct CoreResponse {
+    #[serde(default)]
+    results: Vec<CoreWork>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoreWork {
+    id: serde_json::Value,
+    title: Option<String>,
+    #[serde(default)]
+    authors: Vec<CoreAuthor>,
+    #[serde(rename = "abstract")]
+    abstract_text: Option<String>,
+    doi: Option<String>,
+    arxiv_id: Option<String>,
+    year_published: Option<i64>,
+    publisher: Option<String>,
+    download_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CoreAuthor {
+    name: String,
+}
+
+pub const CORE_BASE_URL: &str = "https://api.core.ac.uk/v3";
+
+pub struct CoreClient {
+    client: Client,
+    api_key: String,
+    base_url: String,
+}
+
+impl CoreClient {
+    pub fn new(api_key: String) -> Self {
+        Self { client: http::api_client(), api_key, base_url: CORE_BASE_URL.to_string() }
+    }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    fn build_url(&self, query_params: &DiscoveryQuery) -> String {
+        let mut terms = Vec::new();
+        if let Some(title) = &query_params.title {
+            terms.push(format!("title:\"{}\"", title));
+        }
+        if let Some(author) = &query_params.author {
+            terms.push(format!("authors:\"{}\"", author));
+        }
+        // CORE has no affiliation field; a phrase search over the record is the closest match
+        if let Some(uni) = &query_params.university {
+            terms.push(format!("\"{}\"", uni));
+        }
+        if let Some(cat) = &query_params.category {
+            terms.push(format!("\"{}\"", cat));
+        }
+        format!("{}/search/works?q={}&limit={}", self.base_url, urlencoding::encode(&terms.join(" AND ")), query_params.limit_for(DiscoverySource::Core))
+    }
+
+    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+        let url = self.build_url(query_params);
+        tracing::info!("Querying CORE: {}", url);
+        let resp = self.client.get(&url).bearer_auth(&self.api_key).send().await
+            .map_err(|e| anyhow!("Request failed: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("CORE API error: {}", resp.status()));
+        }
+        Self::parse_response(&encoding::response_text(resp, "CORE").await?)
+    }
+
+    fn parse_response(json: &str) -> Result<Vec<PaperMetadata>> {
+        let resp: CoreResponse = serde_json::from_str(json)?;
+        Ok(resp.results.into_iter().map(Self::map_work).collect())
+    }
+
+    fn map_work(work: CoreWork) -> PaperMetadata {
+        // CORE only aggregates open repositories, so a download URL is an open copy
+        let pdf_url = work.download_url.filter(|u| !u.trim().is_empty());
+        let (year, rejected_years) = year::check(work.year_published);
+        PaperMetadata {
+            title: work.title.map(|t| t.trim().to_string()).unwrap_or_else(|| "Untitled".to_string()),
+            authors: work.authors.into_iter().map(|a| a.name).collect(),
+            year,
+            rejected_years,
+            doi: work.doi.filter(|d| !d.is_empty()),
+            arxiv_id: work.arxiv_id.filter(|a| !a.is_empty()),
+            core_id: Some(match work.id {
+                serde_json::Value::String(id) => id,
+                other => other.to_string(),
+            }),
+            abstract_text: work.abstract_text.map(|a| a.trim().to_string()).filter(|a| !a.is_empty()),
+            is_oa: pdf_url.is_some(),
+            pdf_url,
+            publisher: work.publisher.filter(|p| !p.is_empty()),
+            ..Default::default()
+        }
+    }
+}
+
+// Europe PMC Data Structures
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpmcResponse {
+    result_list: EpmcResultList,
+}
+
+#[derive(Deserialize)]
+struct EpmcResultList {
+    #[serde(default)]
+    result: Vec<EpmcResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpmcResult {
+    id: String,
+    source: String,
+    title: Option<String>,
+    doi: Option<String>,
+    author_string: Option<String>,
+    author_list: Option<EpmcAuthorList>,
+    pub_year: Option<String>,
+    journal_info: Option<EpmcJournalInfo>,
+    abstract_text: Option<String>,
+    // "Y" or "N"
+    is_open_access: Option<String>,
+    full_text_url_list: Option<EpmcFullTextUrlList>,
+}
+
+#[derive(Deserialize)]
+struct EpmcAuthorList {
+    #[serde(default)]
+    author: Vec<EpmcAuthor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpmcAuthor {
+    full_name: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    collective_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EpmcJournalInfo {
+    journal: Option<EpmcJournal>,
+}
+
+#[derive(Deserialize)]
+struct EpmcJournal {
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpmcFullTextUrlList {
+    #[serde(default)]
+    full_text_url: Vec<EpmcFullTextUrl>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpmcFullTextUrl {
+    // "OA" (open access), "F" (free to read), "S" (subscription) ...
+    availability_code: Option<String>,
+    document_style: Option<String>,
+    url: String,
+}
+
+pub const EUROPE_PMC_BASE_URL: &str = "https://www.ebi.ac.uk/europepmc/webservices/rest";
+
+pub struct EuropePmcClient {
+    client: Client,
+    base_url: String,
+}
+
+impl EuropePmcClient {
+    pub fn new() -> Self {
+        Self { client: http::api_client(), base_url: EUROPE_PMC_BASE_URL.to_string() }
+    }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// The Europe PMC query for a search, or None when it has nothing Europe PMC can search on.
+    pub fn search_terms(query_params: &DiscoveryQuery) -> Option<String> {
+        let mut terms = Vec::new();
+        if let Some(title) = &query_params.title {
+            terms.push(format!("TITLE:\"{}\"", title));
+        }
+        if let Some(author) = &query_params.author {
+            terms.push(format!("AUTH:\"{}\"", author));
+        }
+        if let Some(uni) = &query_params.university {
+            terms.push(format!("AFF:\"{}\"", uni));
+        }
+        if terms.is_empty() {
+            return None;
+        }
+        Some(terms.join(" AND "))
+    }
+
+    fn build_url(&self, query_params: &DiscoveryQuery) -> Option<String> {
+        let terms = Self::search_terms(query_params)?;
+        // The "core" result type is the one that carries full-text links and abstracts
+        Some(format!("{}/search?query={}&format=json&resultType=core&pageSize={}",
+            self.base_url, urlencoding::encode(&terms), query_params.limit_for(DiscoverySource::EuropePmc)))
+    }
+
+    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+        let Some(url) = self.build_url(query_params) else {
+            tracing::info!("Skipping Europe PMC: no title, author or university to search");
+            return Ok(Vec::new());
+        };
+        tracing::info!("Querying Europe PMC: {}", url);
+        let resp = self.client.get(&url).send().await
+            .map_err(|e| anyhow!("Request failed: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Europe PMC API error: {}", resp.status()));
+        }
+        Self::parse_response(&encoding::response_text(resp, "Europe PMC").await?)
+    }
+
+    fn parse_response(json: &str) -> Result<Vec<PaperMetadata>> {
+        let resp: EpmcResponse = serde_json::from_str(json)?;
+        Ok(resp.result_list.result.into_iter().map(Self::map_result).collect())
+    }
+
+    fn map_result(result: EpmcResult) -> PaperMetadata {
+        // A PDF behind a subscription is no use to us; open and free-to-read ones are
+        let pdf = result.full_text_url_list.map(|l| l.full_text_url).unwrap_or_default().into_iter()
+            .filter(|u| u.document_style.as_deref() == Some("pdf"))
+            .find(|u| matches!(u.availability_code.as_deref(), Some("OA") | Some("F")));
+        let authors = match result.author_list {
+            Some(list) if !list.author.is_empty() => list.author.into_iter().filter_map(epmc_author_name).collect(),
+            // Older records only have "Jumper J, Evans R."
+            _ => result.author_string.map(|a| a.trim_end_matches('.').split(", ").map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect())
+                .unwrap_or_default(),
+        };
+        let (year, rejected_years) = year::check_text(result.pub_year.as_deref());
+        PaperMetadata {
+            // MEDLINE titles end with a period
+            title: result.title.map(|t| t.trim().trim_end_matches('.').to_string()).unwrap_or_else(|| "Untitled".to_string()),
+            authors,
+            year,
+            rejected_years,
+            doi: result.doi.filter(|d| !d.is_empty()),
+            europe_pmc_id: Some(format!("{}:{}", result.source, result.id)),
+            venue: result.journal_info.and_then(|j| j.journal).and_then(|j| j.title),
+            abstract_text: result.abstract_text.map(|a| a.trim().to_string()).filter(|a| !a.is_empty()),
+            // Preprint servers are free to read without being flagged open access
+            is_oa: result.is_open_access.as_deref() == Some("Y") || pdf.is_some(),
+            pdf_url: pdf.map(|u| u.url),
+            ..Default::default()
+        }
+    }
+}
+
+// "John Jumper" when the parts are given, else "Jumper J" or a consortium name
+fn epmc_author_name(author: EpmcAuthor) -> Option<String> {
+    match (author.first_name, author.last_name) {
+        (Some(first), Some(last)) => Some(format!("{} {}", first, last)),
+        _ => author.full_name.or(author.collective_name),
+    }
+}
+
+// DBLP Data Structures
+#[derive(Deserialize)]
+struct DblpResponse {
+    result: DblpResult,
+}
+
+#[derive(Deserialize)]
+struct DblpResult {
+    hits: DblpHits,
+}
+
+#[derive(Deserialize)]
+struct DblpHits {
+    // Absent when nothing matched
+    #[serde(default)]
+    hit: Vec<DblpHit>,
+}
+
+#[derive(Deserialize)]
+struct DblpHit {
+    info: DblpInfo,
+}
+
+#[derive(Deserialize)]
+struct DblpInfo {
+    title: Option<String>,
+    authors: Option<DblpAuthors>,
+    venue: Option<OneOrMany<String>>,
+    year: Option<String>,
+    doi: Option<String>,
+    ee: Option<OneOrMany<String>>,
+    key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DblpAuthors {
+    author: OneOrMany<DblpAuthor>,
+}
+
+#[derive(Deserialize)]
+struct DblpAuthor {
+    text: String,
+}
+
+// DBLP's JSON is converted from XML, so a list of one comes back as a bare value
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::One(item) => vec![item],
+            OneOrMany::Many(items) => items,
+        }
+    }
+}
+
+pub const DBLP_BASE_URL: &str = "https://dblp.org/search/publ/api";
+
+/// DBLP has no PDFs, but its author, venue and year data for computer science is the
+/// cleanest there is; its hits are worth having only once merged with a PDF-bearing source.
+pub struct DblpClient {
+    client: Client,
+    base_url: String,
+}
+
+impl DblpClient {
+    pub fn new() -> Self {
+        Self { client: http::api_client(), base_url: DBLP_BASE_URL.to_string() }
+    }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// The DBLP query for a search, or None without a title or author. DBLP has no affiliation
+    /// or subject index, so only those two are searched.
+    pub fn search_terms(query_params: &DiscoveryQuery) -> Option<String> {
+        let terms: Vec<&str> = [&query_params.title, &query_params.author].into_iter().flatten().map(String::as_str).collect();
+        if terms.is_empty() {
+            return None;
+        }
+        Some(terms.join(" "))
+    }
+
+    fn build_url(&self, query_params: &DiscoveryQuery) -> Option<String> {
+        let terms = Self::search_terms(query_params)?;
+        Some(format!("{}?q={}&format=json&h={}", self.base_url, urlencoding::encode(&terms), query_params.limit_for(DiscoverySource::Dblp)))
+    }
+
+    pub async fn search(&self, query_params: &DiscoveryQuery) -> Result<Vec<PaperMetadata>> {
+        let Some(url) = self.build_url(query_params) else {
+            tracing::info!("Skipping DBLP: it can only search titles and authors");
+            return Ok(Vec::new());
+        };
+        tracing::info!("Querying DBLP: {}", url);
+        let resp = self.client.get(&url).send().await
+            .map_err(|e| anyhow!("Request failed: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("DBLP API error: {}", resp.status()));
+        }
+        Self::parse_response(&encoding::response_text(resp, "DBLP").await?)
+    }
+
+    fn parse_response(json: &str) -> Result<Vec<PaperMetadata>> {
+        let resp: DblpResponse = serde_json::from_str(json)?;
+        Ok(resp.result.hits.hit.into_iter().map(|hit| Self::map_info(hit.info)).collect())
+    }
+
+    fn map_info(info: DblpInfo) -> PaperMetadata {
+        let links = info.ee.map(OneOrMany::into_vec).unwrap_or_default();
+        // CoRR records link the preprint, which lets them merge with arXiv's own hit
+        let arxiv_id = links.iter()
+            .filter_map(|link| link.split_once("arxiv.org/abs/").map(|(_, id)| id.trim_end_matches('/')))
+            .find(|id| ArxivId::parse(id).is_some())
+            .map(str::to_string);
+        let (year, rejected_years) = year::check_text(info.year.as_deref());
+        PaperMetadata {
+            // Every DBLP title ends with a period
+            title: info.title.map(|t| t.trim().trim_end_matches('.').to_string()).unwrap_or_else(|| "Untitled".to_string()),
+            authors: info.authors.map(|a| a.author.into_vec()).unwrap_or_default()
+                .into_iter().map(|a| dblp_author_name(&a.text)).collect(),
+            year,
+            rejected_years,
+            doi: info.doi.filter(|d| !d.is_empty()),
+            arxiv_id,
+            dblp_key: info.key,
+            venue: info.venue.and_then(|v| v.into_vec().into_iter().next()),
+            pdf_url: None,
+            is_oa: false,
+            ..Default::default()
+        }
+    }
+}
+
+// "Wei Wang 0012" -> "Wei Wang": DBLP numbers homonymous authors
+fn dblp_author_name(name: &str) -> String {
+    match name.rsplit_once(' ') {
+        Some((rest, suffix)) if suffix.len() == 4 && suffix.chars().all(|c| c.is_ascii_digit()) => rest.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+// Unpaywall Data Structures
+#[derive(Deserialize)]
+struct UPResponse {
+    #[serde(default)]
+    is_oa: bool,
+    best_oa_location: Option<UPLocation>,
+    #[serde(default)]
+    oa_locations: Vec<UPLocation>,
+}
+
+#[derive(Deserialize)]
+struct UPLocation {
+    url_for_pdf: Option<String>,
+    license: Option<String>,
+    version: Option<String>,
+}
+
+/// A legal open copy Unpaywall knows about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OaCopy {
+    pub pdf_url: String,
+    pub license: Option<String>,
+    pub version: Option<String>,
+}
+
+impl OaCopy {
+    pub fn apply_to(&self, paper: &mut PaperMetadata) {
+        paper.pdf_url = Some(self.pdf_url.clone());
+        paper.is_oa = true;
+        paper.license = self.license.clone();
+        paper.version_hint = classify_version(None, self.version.as_deref(), Some(&self.pdf_url));
+    }
+}
+
+pub const UNPAYWALL_BASE_URL: &str = "https://api.unpaywall.org/v2";
+
+pub struct UnpaywallClient {
+    client: Client,
+    email: String,
+    limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    base_url: String,
+    // OA papers whose PDF is on one of these hosts are looked up too, for a copy without a login wall
+    paywall: PaywallHeuristic,
+}
+
+impl UnpaywallClient {
+    pub fn new(email: String) -> Self {
+        // Unpaywall asks for at most 100k calls a day: one every 864ms, with a burst for a result page
+        let quota = Quota::with_period(std::time::Duration::from_millis(864))
+            .expect("non-zero period")
+            .allow_burst(nonzero!(50u32));
+        Self {
+            client: http::api_client(),
+            email,
+            limiter: Arc::new(RateLimiter::direct(quota)),
+            base_url: UNPAYWALL_BASE_URL.to_string(),
+            paywall: PaywallHeuristic::default(),
+        }
+    }
+
+    pub fn with_paywall(mut self, paywall: PaywallHeuristic) -> Self {
+        self.paywall = paywall;
+        self
+    }
+
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// The best OA PDF for a DOI, or None when Unpaywall has no open copy (or doesn't know the DOI).
+    pub async fn lookup(&self, doi: &str) -> Result<Option<OaCopy>> {
+        self.limiter.until_ready().await;
+        let url = format!("{}/{}?email={}", self.base_url, normalize_doi(doi), urlencoding::encode(&self.email));
+        tracing::info!("Querying Unpaywall: {}", url);
+        let resp = self.client.get(&url).send().await.map_err(|e| anyhow!("Request failed: {}", e))?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow!("Unpaywall API error: {}", resp.status()));
+        }
+        Self::parse_response(&encoding::response_text(resp, "Unpaywall").await?)
+    }
+
+    fn parse_response(json: &str) -> Result<Option<OaCopy>> {
+        let resp: UPResponse = serde_json::from_str(json)?;
+        if !resp.is_oa {
+            return Ok(None);
+        }
+        // The best location sometimes only has a landing page; any other location with a PDF will do
+        Ok(resp.best_oa_location.into_iter()
+            .chain(resp.oa_locations)
+            .find_map(|loc| loc.url_for_pdf.map(|pdf_url| OaCopy { pdf_url, license: loc.license, version: loc.version })))
+    }
+}
+
+impl Provider for UnpaywallClient {
+    type Found = OaCopy;
+    const SOURCE: Source = Source::Unpaywall;
+
+    // Papers that already have a PDF don't need a repository copy, unless it's likely behind a login
+    fn wants(&self, paper: &PaperMetadata) -> Option<String> {
+        let needs_copy = paper.pdf_url.is_none() || self.paywall.is_suspicious(paper);
+        needs_copy.then_some(paper.doi.as_deref()).flatten().map(normalize_doi)
+    }
+
+    async fn fetch(&self, doi: &str) -> Result<Option<OaCopy>> {
+        self.lookup(doi).await
+    }
+
+    fn apply(&self, copy: &OaCopy, paper: &mut PaperMetadata) {
+        // Unpaywall's best copy of a paywall-prone paper is often the same publisher page
+        if paper.pdf_url.is_some() && self.paywall.is_paywalled_host(&copy.pdf_url) {
+            return;
+        }
+        tracing::info!("Unpaywall found an open copy of '{}': {}", paper.title, copy.pdf_url);
+        copy.apply_to(paper);
+    }
+}
+
+// Candidates in flight between the sources and the merge; a full channel makes the sources
+// wait. Each source still holds its own results until its last page is in.
+pub const CANDIDATE_CHANNEL_CAPACITY: usize = 256;
+
+// Sends `papers` one by one; false once the receiver has gone away
+async fn forward(papers: Vec<PaperMetadata>, tx: &mpsc::Sender<PaperMetadata>, cancel: &CancellationToken) -> Result<bool> {
+    for paper in papers {
+        tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err(Cancelled.into()),
+            sent = tx.send(paper) => if sent.is_err() { return Ok(false) },
+        }
+    }
+    Ok(true)
+}
+
+// Every arXiv paper has a PDF at its ID, so a record with the ID but no PDF link (an abs-only
+// arXiv entry, or another source that knows the preprint) is open and downloadable
+fn derive_arxiv_pdf(paper: &mut PaperMetadata) {
+    if paper.pdf_url.is_some() {
+        return;
+    }
+    if let Some(id) = paper.arxiv_id.as_deref().filter(|id| !id.is_empty()) {
+        paper.pdf_url = Some(arxiv_pdf_url(id));
+        paper.is_oa = true;
+    }
+}
+
+pub struct DiscoveryOrchestrator {
+    ss_client: SemanticScholarClient,
+    arxiv_client: ArxivClient,
+    open_alex_client: OpenAlexClient,
+    europe_pmc_client: EuropePmcClient,
+    // Optional sources, used only when configured
+    core_client: Option<CoreClient>,
+    dblp_client: Option<DblpClient>,
+    unpaywall_client: Option<UnpaywallClient>,
+    diagnostics: Arc<Diagnostics>,
+}
+
+impl DiscoveryOrchestrator {
+    pub fn new(ss_api_key: Option<String>, open_alex_email: Option<String>) -> Self {
+        Self {
+            ss_client: SemanticScholarClient::new(ss_api_key),
+            arxiv_client: ArxivClient::new(),
+            open_alex_client: OpenAlexClient::new(open_alex_email),
+            europe_pmc_client: EuropePmcClient::new(),
+            core_client: None,
+            dblp_client: None,
+            unpaywall_client: None,
+            diagnostics: Arc::default(),
+        }
+    }
+
+    /// Also search CORE. Without an API key the source is skipped.
+    pub fn with_core(mut self, api_key: Option<String>) -> Self {
+        self.core_client = api_key.map(CoreClient::new);
+        self
+    }
+
+    /// Also search DBLP. Off by default: it adds a request but never a PDF of its own.
+    pub fn with_dblp(mut self, enabled: bool) -> Self {
+        self.dblp_client = enabled.then(DblpClient::new);
+        self
+    }
+
+    /// Look up open copies of closed or PDF-less candidates on Unpaywall, which requires an email.
+    pub fn with_unpaywall(mut self, email: Option<String>, paywall: PaywallHeuristic) -> Self {
+        self.unpaywall_client = email.map(|email| UnpaywallClient::new(email).with_paywall(paywall));
+        self
+    }
+
+    /// Records source failures and per-source warnings into a shared collector.
+    pub fn with_diagnostics(mut self, diagnostics: Arc<Diagnostics>) -> Self {
+        self.ss_client.diagnostics = diagnostics.clone();
+        self.arxiv_client.diagnostics = diagnostics.clone();
+        self.open_alex_client.diagnostics = diagnostics.clone();
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// The limiter the arXiv search waits on, for other requests to arXiv in the same run.
+    pub fn arxiv_limiter(&self) -> ArxivLimiter {
+        self.arxiv_client.limiter.clone()
+    }
+
+    /// Every source's results, merged. A thin wrapper over `search_into`: each source's results
+    /// are merged as they arrive, so only the distinct papers are held, never every raw record.
+    /// Filtering and scoring wait for the merge, since a later record can fill in an abstract
+    /// or year.
+    pub async fn search_all(&self, query: &DiscoveryQuery, cancel: &CancellationToken) -> Result<Vec<PaperMetadata>> {
+        let index = self.search_merged(query, cancel).await?;
+        let total = index.seen();
+        let merged = index.into_papers();
+        tracing::info!("Merged {} results into {} distinct papers", total, merged.len());
+        Ok(merged)
+    }
+
+    async fn search_merged(&self, query: &DiscoveryQuery, cancel: &CancellationToken) -> Result<MergeIndex> {
+        let (tx, mut rx) = mpsc::channel(CANDIDATE_CHANNEL_CAPACITY);
+        let mut index = MergeIndex::default();
+        let consume = async {
+            while let Some(paper) = rx.recv().await {
+                index.push(paper);
+            }
+        };
+        let (searched, ()) = tokio::join!(self.search_into(query, cancel, tx), consume);
+        searched?;
+        Ok(index)
+    }
+
+    /// Queries every source at once and sends their results into `tx`. Sources are drained in a
+    /// fixed order (PDF-bearing sources first, DBLP last) so merging stays deterministic; a full
+    /// channel pauses the sending until the receiver catches up. Failed sources are recorded
+    /// in diagnostics and skipped.
+    pub async fn search_into(&self, query: &DiscoveryQuery, cancel: &CancellationToken, tx: mpsc::Sender<PaperMetadata>) -> Result<()> {
+        type Outcome = (&'static str, DiscoverySource, Source, Result<Vec<PaperMetadata>>);
+        let mut searches: FuturesOrdered<LocalBoxFuture<'_, Outcome>> = FuturesOrdered::new();
+        searches.push_back(Box::pin(async {
+            // A title wants relevance ranking; author and university sweeps want volume
+            let result = match query.title {
+                Some(_) => self.ss_client.search(query).await,
+                None => self.ss_client.search_bulk(query).await,
+            };
+            ("Semantic Scholar", DiscoverySource::SemanticScholar, Source::SemanticScholar, result)
+        }));
+        searches.push_back(Box::pin(async { ("arXiv", DiscoverySource::Arxiv, Source::Arxiv, self.arxiv_client.search(query).await) }));
+        searches.push_back(Box::pin(async { ("OpenAlex", DiscoverySource::OpenAlex, Source::OpenAlex, self.open_alex_client.search(query).await) }));
+        searches.push_back(Box::pin(async { ("Europe PMC", DiscoverySource::EuropePmc, Source::EuropePmc, self.europe_pmc_client.search(query).await) }));
+        if let Some(core) = &self.core_client {
+            searches.push_back(Box::pin(async { ("CORE", DiscoverySource::Core, Source::Core, core.search(query).await) }));
+        }
+        if let Some(dblp) = &self.dblp_client {
+            searches.push_back(Box::pin(async { ("DBLP", DiscoverySource::Dblp, Source::Dblp, dblp.search(query).await) }));
+        }
+
+        // Dropping the pending futures aborts the in-flight requests
+        loop {
+            let next = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Err(Cancelled.into()),
+                next = searches.next() => next,
+            };
+            let Some((name, source, diagnostic_source, result)) = next else { break };
+            let requested = query.limit_for(source);
+            match result {
+                Ok(mut results) => {
+                    tracing::info!("{}: requested {}, returned {}", name, requested, results.len());
+                    // Truncate while the full list is at hand, so a queried author past the cap survives
+                    if let Some(max) = query.max_authors {
+                        for paper in &mut results {
+                            paper.truncate_authors(max, query.author.as_deref());
+                        }
+                    }
+                    for paper in &mut results {
+                        paper.garbled_title = paper.title.contains(char::REPLACEMENT_CHARACTER);
+                        // Sources differ on URLs and version suffixes; store the bare ID
+                        paper.arxiv_id = paper.arxiv_id.as_deref().map(arxiv_id::normalize);
+                        derive_arxiv_pdf(paper);
+                    }
+                    if !forward(results, &tx, cancel).await? {
+                        // Nobody is listening any more
+                        return Ok(());
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("{} discovery failed: {}", name, e);
+                    tracing::info!("{}: requested {}, returned 0 (failed)", name, requested);
+                    self.diagnostics.warn(WarningKind::SourceFailed, diagnostic_source, e.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Second pass after discovery: fills `pdf_url`, `is_oa` and `license` from Unpaywall for
+    /// candidates that have a DOI but no PDF URL. Does nothing without an Unpaywall email.
+    pub async fn enrich_oa(&self, papers: &mut [PaperMetadata], queue: &EnrichmentQueue, cancel: &CancellationToken) -> Result<()> {
+        match &self.unpaywall_client {
+            Some(unpaywall) => queue.run(unpaywall, papers, cancel).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::enrichment::ENRICHMENT_CONCURRENCY;
+    use crate::layers::filter::CandidateFilter;
+    use crate::layers::merge::Merger;
+    use crate::layers::normalize::TitleFolding;
+    use crate::layers::resolution::{ResolutionQuery, Resolver, Similarity, SortBy, Threshold};
+    use crate::layers::ArxivSort;
+    use crate::test_support::{MockResponse, MockServer};
+
+    fn fixture(path: &str) -> String {
+        std::fs::read_to_string(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap()
+    }
+
+    #[test]
+    fn test_reconstruct_abstract_orders_by_position() {
+        let mut index = HashMap::new();
+        index.insert("world".to_string(), vec![1]);
+        index.insert("hello".to_string(), vec![0, 2]);
+        assert_eq!(reconstruct_abstract(&index).as_deref(), Some("hello world hello"));
+        assert_eq!(reconstruct_abstract(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_openalex_abstract_is_reconstructed_before_filtering() {
+        let papers = OpenAlexClient::parse_response(&fixture("openalex/works_abstracts.json")).unwrap();
+        assert_eq!(papers.len(), 2);
+        assert_eq!(
+            papers[0].abstract_text.as_deref(),
+            Some("The dominant sequence transduction models are based on complex sequence networks.")
+        );
+        assert_eq!(papers[1].abstract_text, None);
+        assert_eq!(papers[0].citation_count, Some(98000));
+        assert_eq!(papers[1].citation_count, None);
+
+        // The OpenAlex hit only has an inverted index, yet survives an abstract filter
+        let filter = CandidateFilter { require_abstract: true, min_abstract_words: Some(10), ..Default::default() };
+        let kept = filter.apply(papers);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].title, "Attention Is All You Need");
+    }
+
+    #[test]
+    fn test_openalex_locations_give_venue_arxiv_id_and_type() {
+        let papers = OpenAlexClient::parse_response(&fixture("openalex/works_locations.json")).unwrap();
+        let [article, preprint, unplaced] = &papers[..] else { panic!("expected three works") };
+
+        assert_eq!(article.venue.as_deref(), Some("Nature"));
+        assert_eq!(article.arxiv_id, None);
+        assert_eq!(article.publication_type.as_deref(), Some("article"));
+
+        assert_eq!(preprint.arxiv_id.as_deref(), Some("1706.03762v5"));
+        assert_eq!(preprint.venue.as_deref(), Some("arXiv"));
+        assert_eq!(preprint.publication_type.as_deref(), Some("preprint"));
+
+        assert_eq!(unplaced.venue, None);
+        assert_eq!(unplaced.arxiv_id, None);
+        assert_eq!(unplaced.pdf_url, None);
+        assert_eq!(unplaced.publication_type.as_deref(), Some("report"));
+    }
+
+    #[test]
+    fn test_openalex_preprint_merges_with_arxiv_hit() {
+        let openalex = OpenAlexClient::parse_response(&fixture("openalex/works_locations.json")).unwrap().remove(1);
+        let arxiv = PaperMetadata {
+            title: "Attention Is All You Need".to_string(),
+            arxiv_id: Some("1706.03762v7".to_string()),
+            ..Default::default()
+        };
+        let merged = Merger::merge(vec![arxiv, openalex]);
+        assert_eq!(merged.len(), 1);
+        assert!(merged[0].open_alex_id.is_some());
+    }
+
+    fn title_query(title: &str) -> DiscoveryQuery {
+        DiscoveryQuery { title: Some(title.to_string()), limit: 10, ..Default::default() }
+    }
+
+    #[test]
+    fn test_ss_url_without_filters() {
+        let client = SemanticScholarClient::new(None);
+        let url = client.build_url(&title_query("Deep learning"), true, 0);
+        assert_eq!(url, format!("{}/paper/search?query=Deep%20learning&fields={}&limit=10", SEMANTIC_SCHOLAR_BASE_URL, SS_FIELDS));
+    }
+
+    #[test]
+    fn test_ss_url_with_combined_filters() {
+        let client = SemanticScholarClient::new(None);
+        let query = DiscoveryQuery {
+            year_from: Some(2018),
+            year_to: Some(2020),
+            publication_filter: Some(PublicationFilter::Published),
+            oa_only: true,
+            ..title_query("Deep learning")
+        };
+        let url = client.build_url(&query, true, 0);
+        assert!(url.contains("&publicationDateOrYear=2018:2020"));
+        assert!(url.contains("&publicationTypes=JournalArticle,Conference"));
+        assert!(url.ends_with("&openAccessPdf"));
+        // openAccessPdf appears once in fields and once as a filter, never duplicated in fields
+        assert_eq!(url.matches("fields=").count(), 1);
+        assert_eq!(url.matches("openAccessPdf").count(), 2);
+
+        let unfiltered = client.build_url(&query, false, 0);
+        assert!(!unfiltered.contains("publicationDateOrYear"));
+        assert!(!unfiltered.ends_with("&openAccessPdf"));
+    }
+
+    #[test]
+    fn test_ss_url_uses_its_own_limit() {
+        let client = SemanticScholarClient::new(None);
+        let mut query = title_query("x");
+        query.source_limits.insert(DiscoverySource::SemanticScholar, 3);
+        query.source_limits.insert(DiscoverySource::Arxiv, 50);
+        assert!(client.build_url(&query, true, 0).contains("&limit=3"));
+    }
+
+    #[test]
+    fn test_limits_are_clamped_to_each_api_maximum() {
+        let query = DiscoveryQuery { limit: 5000, ..title_query("x") };
+        assert!(SemanticScholarClient::new(None).build_url(&query, false, 0).contains("&limit=100"));
+        // arXiv serves up to 2000 in one request, but is asked a page at a time
+        assert_eq!(query.limit_for(DiscoverySource::Arxiv), 2000);
+        assert!(ArxivClient::new().build_url(&query, 0).ends_with("&max_results=100"));
+        assert!(OpenAlexClient::new(None).build_url(&query, "*", None).ends_with("per_page=200&cursor=*"));
+
+        let zero = DiscoveryQuery { limit: 0, ..title_query("x") };
+        assert!(ArxivClient::new().build_url(&zero, 0).ends_with("&max_results=1"));
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_retries_once_after_503() {
+        let server = MockServer::start(vec![
+            MockResponse::new(503, "Rate exceeded."),
+            MockResponse::new(200, fixture("arxiv/query.xml")),
+        ]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        let papers = client.search(&title_query("Attention Is All You Need")).await.unwrap();
+        assert_eq!(papers.len(), 1);
+        assert_eq!(papers[0].arxiv_id.as_deref(), Some("1706.03762"));
+        assert_eq!(server.requests().len(), 2);
+
+        // Only one retry
+        let server = MockServer::start(vec![MockResponse::new(503, "Rate exceeded."); 3]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        assert!(client.search(&title_query("x")).await.unwrap_err().to_string().contains("503"));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_latin1_bodies_are_transcoded() {
+        let bytes = |name: &str| std::fs::read(format!("{}/tests/fixtures/encoding/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap();
+        let server = MockServer::start(vec![MockResponse::new(200, bytes("ss_latin1.json"))]).await;
+        let papers = SemanticScholarClient::new(None).with_base_url(&server.url).search(&title_query("Réseaux")).await.unwrap();
+        assert_eq!(papers[0].title, "Réseaux de neurones pour la détection");
+        assert_eq!(papers[0].authors, ["François Müller"]);
+        assert_eq!(papers[0].venue.as_deref(), Some("Revue “IA”"));
+
+        // The feed still parses, rather than failing on the stray byte
+        let server = MockServer::start(vec![MockResponse::new(200, bytes("arxiv_latin1.xml"))]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        let papers = client.search(&title_query("Attention Is All You Need")).await.unwrap();
+        assert!(papers[0].authors.iter().any(|a| a == "José Shazeer"));
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_categories_put_the_primary_first() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("arxiv/categories.xml"))]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        let papers = client.search(&title_query("BERT")).await.unwrap();
+        assert_eq!(papers.len(), 3);
+        assert_eq!(papers[0].categories, ["cs.CL", "cs.LG", "stat.ML"]);
+        assert_eq!(papers[0].primary_arxiv_category(), Some("cs.CL"));
+        assert!(papers[1].categories.is_empty());
+        assert_eq!(papers[1].primary_arxiv_category(), None);
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_ids_are_bare_and_dois_parsed() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("arxiv/categories.xml"))]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        let papers = client.search(&title_query("BERT")).await.unwrap();
+        assert_eq!(papers[0].arxiv_id.as_deref(), Some("1810.04805"));
+        assert_eq!(papers[0].doi.as_deref(), Some("10.18653/v1/N19-1423"));
+        assert_eq!(papers[1].doi, None);
+        assert_eq!(papers[2].arxiv_id.as_deref(), Some("math/0309136"));
+        assert_eq!(papers[2].categories, ["math.GT"]);
+        assert_eq!(papers[2].doi, None);
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_entry_with_only_an_abs_link_is_downloadable() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("arxiv/abs_only.xml"))]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        let papers = client.search(&title_query("duality")).await.unwrap();
+        assert_eq!(papers.len(), 2);
+        // The version the entry describes, not whatever is latest
+        assert_eq!(papers[0].pdf_url.as_deref(), Some("https://arxiv.org/pdf/2305.01234v3"));
+        assert_eq!(papers[1].pdf_url.as_deref(), Some("https://arxiv.org/pdf/hep-th/9711200v3"));
+        assert!(papers.iter().all(|p| p.is_oa));
+    }
+
+    #[test]
+    fn test_arxiv_pdf_is_derived_for_other_sources() {
+        let mut closed = PaperMetadata { title: "T".to_string(), arxiv_id: Some("1512.03385".to_string()), is_oa: false, ..Default::default() };
+        derive_arxiv_pdf(&mut closed);
+        assert_eq!(closed.pdf_url.as_deref(), Some("https://arxiv.org/pdf/1512.03385"));
+        assert!(closed.is_oa);
+
+        let mut old_style = PaperMetadata { arxiv_id: Some("math/0309136".to_string()), ..Default::default() };
+        derive_arxiv_pdf(&mut old_style);
+        assert_eq!(old_style.pdf_url.as_deref(), Some("https://arxiv.org/pdf/math/0309136"));
+
+        // A PDF the source gave is kept, and so is its access status
+        let publisher = Some("https://publisher.example.com/paper.pdf".to_string());
+        let mut linked = PaperMetadata { arxiv_id: Some("1512.03385".to_string()), pdf_url: publisher.clone(), ..Default::default() };
+        derive_arxiv_pdf(&mut linked);
+        assert_eq!(linked.pdf_url, publisher);
+        assert!(!linked.is_oa);
+
+        let mut no_id = PaperMetadata::default();
+        derive_arxiv_pdf(&mut no_id);
+        assert_eq!(no_id.pdf_url, None);
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_requests_wait_for_the_limiter() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("arxiv/query.xml")); 3]).await;
+        let period = Duration::from_millis(300);
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::with_period(period).unwrap());
+        let started = std::time::Instant::now();
+        for _ in 0..3 {
+            client.search(&title_query("Attention Is All You Need")).await.unwrap();
+        }
+        // The first request goes at once, each later one waits out the period
+        assert!(started.elapsed() >= period * 2, "{:?}", started.elapsed());
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_arxiv_url_passes_limit() {
+        let query = DiscoveryQuery { limit: 50, author: Some("Vaswani".to_string()), ..title_query("Attention") };
+        assert_eq!(
+            ArxivClient::new().build_url(&query, 0),
+            "http://export.arxiv.org/api/query?search_query=ti%3A%22Attention%22%20AND%20au%3A%22Vaswani%22&start=0&max_results=50"
+        );
+    }
+
+    #[test]
+    fn test_arxiv_page_urls_and_sort() {
+        let query = DiscoveryQuery { limit: 150, author: Some("Yoshua Bengio".to_string()), arxiv_sort: ArxivSort::Newest, ..Default::default() };
+        let client = ArxivClient::new();
+        assert_eq!(client.build_url(&query, 0),
+            "http://export.arxiv.org/api/query?search_query=au%3A%22Yoshua%20Bengio%22&start=0&max_results=100&sortBy=submittedDate&sortOrder=descending");
+        // The second page asks only for what the limit still allows
+        assert_eq!(client.build_url(&query, 100),
+            "http://export.arxiv.org/api/query?search_query=au%3A%22Yoshua%20Bengio%22&start=100&max_results=50&sortBy=submittedDate&sortOrder=descending");
+        assert!(client.build_url(&DiscoveryQuery { arxiv_sort: ArxivSort::Oldest, ..query }, 0).ends_with("&sortBy=submittedDate&sortOrder=ascending"));
+    }
+
+    fn arxiv_feed(ids: std::ops::Range<usize>) -> String {
+        let entries: String = ids.map(|i| format!(
+            "<entry><id>http://arxiv.org/abs/2301.{:05}v1</id><published>2023-01-02T00:00:00Z</published><title>Paper {}</title>\
+             <summary>S</summary><author><name>Yoshua Bengio</name></author></entry>", i, i)).collect();
+        format!(r#"<?xml version="1.0" encoding="UTF-8"?><feed xmlns="http://www.w3.org/2005/Atom">{}</feed>"#, entries)
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_pages_until_a_short_page() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, arxiv_feed(0..100)),
+            MockResponse::new(200, arxiv_feed(100..200)),
+            MockResponse::new(200, arxiv_feed(200..230)),
+        ]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        let query = DiscoveryQuery { limit: 500, author: Some("Yoshua Bengio".to_string()), ..Default::default() };
+        let papers = client.search(&query).await.unwrap();
+        assert_eq!(papers.len(), 230);
+        assert_eq!(papers[100].arxiv_id.as_deref(), Some("2301.00100"));
+        let starts: Vec<String> = server.requests().iter()
+            .map(|r| r.split('&').find(|p| p.starts_with("start=")).unwrap().to_string()).collect();
+        assert_eq!(starts, ["start=0", "start=100", "start=200"]);
+
+        // Reaching the limit ends the sweep without another request
+        let server = MockServer::start(vec![MockResponse::new(200, arxiv_feed(0..100))]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        assert_eq!(client.search(&DiscoveryQuery { limit: 100, author: query.author.clone(), ..Default::default() }).await.unwrap().len(), 100);
+        assert_eq!(server.requests().len(), 1);
+
+        // A failed later page keeps the first
+        let server = MockServer::start(vec![MockResponse::new(200, arxiv_feed(0..100)), MockResponse::new(500, "oops"), MockResponse::new(500, "oops")]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        assert_eq!(client.search(&DiscoveryQuery { limit: 150, ..query }).await.unwrap().len(), 100);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_openalex_url_passes_limit() {
+        let client = OpenAlexClient::new(Some("me@example.org".to_string()));
+        let query = DiscoveryQuery { limit: 50, university: Some("New York University".to_string()), ..title_query("Deep learning") };
+        assert_eq!(
+            client.build_url(&query, "*", None),
+            "https://api.openalex.org/works?filter=raw_affiliation_strings.search%3ANew+York+University&search=Deep+learning&per_page=50&cursor=*&mailto=me%40example.org"
+        );
+
+        // An address with a plus or ampersand stays one parameter
+        let client = OpenAlexClient::new(Some("me+scholar&co@example.org".to_string()));
+        assert!(client.build_url(&query, "*", None).ends_with("&mailto=me%2Bscholar%26co%40example.org"));
+        assert!(client.institutions_url("Texas A&M").ends_with("/institutions?search=Texas+A%26M&per_page=5&mailto=me%2Bscholar%26co%40example.org"));
+    }
+
+    #[tokio::test]
+    async fn test_openalex_follows_cursor_until_limit() {
+        let page = |titles: &[&str], next: Option<&str>| {
+            let results: Vec<_> = titles.iter().map(|t| serde_json::json!({"id": format!("https://openalex.org/{}", t), "title": t, "authorships": []})).collect();
+            serde_json::json!({"meta": {"count": 5, "next_cursor": next}, "results": results}).to_string()
+        };
+        let server = MockServer::start(vec![
+            MockResponse::new(200, page(&["W1", "W2"], Some("IlsxNjA5XSI="))),
+            MockResponse::new(200, page(&["W3", "W4"], Some("IlsxNjEwXSI="))),
+            MockResponse::new(200, page(&["W5"], None)),
+        ]).await;
+        let client = OpenAlexClient::new(Some("me@example.org".to_string())).with_base_url(&server.url);
+
+        let papers = client.search(&DiscoveryQuery { limit: 3, ..title_query("Deep learning") }).await.unwrap();
+        assert_eq!(papers.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), vec!["W1", "W2", "W3"]);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].ends_with("per_page=3&cursor=*&mailto=me%40example.org"));
+        assert!(requests[1].ends_with("per_page=3&cursor=IlsxNjA5XSI%3D&mailto=me%40example.org"));
+    }
+
+    #[tokio::test]
+    async fn test_openalex_university_resolves_to_institution_id() {
+        let institutions = r#"{"meta": {"count": 2}, "results": [
+            {"id": "https://openalex.org/I63966007", "display_name": "Massachusetts Institute of Technology"},
+            {"id": "https://openalex.org/I4210089026", "display_name": "MIT Lincoln Laboratory"}
+        ]}"#;
+        let works = r#"{"meta": {"count": 0, "next_cursor": null}, "results": []}"#;
+        let server = MockServer::start(vec![MockResponse::new(200, institutions), MockResponse::new(200, works)]).await;
+        let client = OpenAlexClient::new(Some("me@example.org".to_string())).with_base_url(&server.url);
+        let query = DiscoveryQuery { university: Some("MIT".to_string()), ..title_query("Deep learning") };
+
+        client.search(&query).await.unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[0], "/institutions?search=MIT&per_page=5&mailto=me%40example.org");
+        assert_eq!(requests[1], "/works?filter=institutions.id%3AI63966007&search=Deep+learning&per_page=10&cursor=*&mailto=me%40example.org");
+    }
+
+    #[tokio::test]
+    async fn test_openalex_unknown_university_falls_back_to_affiliation_search() {
+        let works = r#"{"meta": {"count": 0, "next_cursor": null}, "results": []}"#;
+        for institutions in [MockResponse::new(200, r#"{"meta": {"count": 0}, "results": []}"#), MockResponse::new(500, "oops")] {
+            let server = MockServer::start(vec![institutions, MockResponse::new(200, works)]).await;
+            let client = OpenAlexClient::new(None).with_base_url(&server.url)
+                .with_retry(RetryPolicy { max_attempts: 1, ..quick_retry() });
+            let query = DiscoveryQuery { university: Some("Nowhere U".to_string()), ..title_query("x") };
+
+            client.search(&query).await.unwrap();
+            assert_eq!(server.requests()[1], "/works?filter=raw_affiliation_strings.search%3ANowhere+U&search=x&per_page=10&cursor=*");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openalex_keeps_earlier_pages_when_a_later_one_fails() {
+        let page = |titles: &[&str], next: Option<&str>| {
+            let results: Vec<_> = titles.iter().map(|t| serde_json::json!({"id": format!("https://openalex.org/{}", t), "title": t, "authorships": []})).collect();
+            MockResponse::new(200, serde_json::json!({"meta": {"count": 5, "next_cursor": next}, "results": results}).to_string())
+        };
+        let server = MockServer::start(vec![
+            MockResponse::new(429, "slow down").with_header("Retry-After", "0"),
+            page(&["W1", "W2"], Some("c1")),
+            MockResponse::new(503, "busy"),
+            MockResponse::new(503, "busy"),
+        ]).await;
+        let diagnostics = Arc::new(Diagnostics::default());
+        let mut client = OpenAlexClient::new(None).with_base_url(&server.url)
+            .with_retry(RetryPolicy { max_attempts: 2, ..quick_retry() });
+        client.diagnostics = diagnostics.clone();
+
+        // The first page is retried; the failed second one ends the search with what came before
+        let papers = client.search(&DiscoveryQuery { limit: 10, ..title_query("x") }).await.unwrap();
+        assert_eq!(papers.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), vec!["W1", "W2"]);
+        assert_eq!(server.requests().len(), 4);
+        let summary = diagnostics.summary();
+        assert_eq!((summary[0].kind, summary[0].source, summary[0].count), (WarningKind::SourceFailed, Source::OpenAlex, 1));
+        assert!(summary[0].examples[0].contains("503"));
+
+        // A failed first page is still an error
+        let server = MockServer::start(vec![MockResponse::new(500, "oops"); 2]).await;
+        let client = OpenAlexClient::new(None).with_base_url(&server.url)
+            .with_retry(RetryPolicy { max_attempts: 2, ..quick_retry() });
+        assert!(client.search(&title_query("x")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_openalex_stops_on_last_page() {
+        let body = serde_json::json!({"meta": {"count": 1, "next_cursor": null}, "results": [{"id": "https://openalex.org/W1", "title": "Only", "authorships": []}]});
+        let server = MockServer::start(vec![MockResponse::new(200, body.to_string())]).await;
+        let papers = OpenAlexClient::new(None).with_base_url(&server.url)
+            .search(&DiscoveryQuery { limit: 500, ..title_query("Only") }).await.unwrap();
+        assert_eq!(papers.len(), 1);
+        assert_eq!(server.requests().len(), 1);
+        assert!(server.requests()[0].contains("per_page=200&cursor=*"));
+    }
+
+    #[test]
+    fn test_ss_url_open_ended_year_ranges() {
+        let client = SemanticScholarClient::new(None);
+        let from = DiscoveryQuery { year_from: Some(2019), ..title_query("x") };
+        let to = DiscoveryQuery { year_to: Some(2015), ..title_query("x") };
+        assert!(client.build_url(&from, true, 0).contains("publicationDateOrYear=2019:"));
+        assert!(client.build_url(&to, true, 0).contains("publicationDateOrYear=:2015"));
+    }
+
+    #[test]
+    fn test_core_field_mapping() {
+        let papers = CoreClient::parse_response(&fixture("core/search_works.json")).unwrap();
+        assert_eq!(papers.len(), 3);
+
+        let first = &papers[0];
+        assert_eq!(first.title, "Deep Residual Learning for Image Recognition");
+        assert_eq!(first.authors, vec!["He, Kaiming", "Zhang, Xiangyu", "Ren, Shaoqing", "Sun, Jian"]);
+        assert_eq!(first.year, Some(2016));
+        assert_eq!(first.doi.as_deref(), Some("10.1109/cvpr.2016.90"));
+        assert_eq!(first.arxiv_id.as_deref(), Some("1512.03385"));
+        assert_eq!(first.core_id.as_deref(), Some("80916119"));
+        assert_eq!(first.pdf_url.as_deref(), Some("https://core.ac.uk/download/80916119.pdf"));
+        assert!(first.is_oa);
+        assert!(first.abstract_text.as_deref().unwrap().starts_with("Deeper neural networks"));
+
+        // Metadata-only record: empty download URL, no DOI, no abstract
+        let second = &papers[1];
+        assert_eq!(second.pdf_url, None);
+        assert!(!second.is_oa);
+        assert_eq!(second.doi, None);
+        assert_eq!(second.abstract_text, None);
+        assert_eq!(second.authors.len(), 0);
+
+        assert_eq!(papers[2].title, "Untitled");
+    }
+
+    #[tokio::test]
+    async fn test_core_sends_key_and_query() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("core/search_works.json"))]).await;
+        let client = CoreClient::new("secret".to_string()).with_base_url(&server.url);
+        let query = DiscoveryQuery { author: Some("He".to_string()), ..title_query("Deep Residual Learning") };
+
+        assert_eq!(client.search(&query).await.unwrap().len(), 3);
+        let head = &server.request_heads()[0];
+        assert!(head.starts_with("GET /search/works?q=title%3A%22Deep%20Residual%20Learning%22%20AND%20authors%3A%22He%22&limit=10 "));
+        assert!(head.to_lowercase().contains("authorization: bearer secret"));
+    }
+
+    #[test]
+    fn test_core_is_skipped_without_a_key() {
+        let orchestrator = DiscoveryOrchestrator::new(None, None).with_core(None);
+        assert!(orchestrator.core_client.is_none());
+    }
+
+    #[test]
+    fn test_europe_pmc_field_mapping() {
+        let papers = EuropePmcClient::parse_response(&fixture("europepmc/search_core.json")).unwrap();
+        assert_eq!(papers.len(), 3);
+
+        let alphafold = &papers[0];
+        assert_eq!(alphafold.title, "Highly accurate protein structure prediction with AlphaFold");
+        assert_eq!(alphafold.authors, vec!["John Jumper", "Richard Evans", "CASP14 Consortium"]);
+        assert_eq!(alphafold.year, Some(2021));
+        assert_eq!(alphafold.doi.as_deref(), Some("10.1038/s41586-021-03819-2"));
+        assert_eq!(alphafold.venue.as_deref(), Some("Nature"));
+        assert_eq!(alphafold.europe_pmc_id.as_deref(), Some("MED:34265844"));
+        // The PDF entry, not the DOI or HTML links
+        assert_eq!(alphafold.pdf_url.as_deref(), Some("https://europepmc.org/articles/PMC8371605?pdf=render"));
+        assert!(alphafold.is_oa);
+        assert!(alphafold.abstract_text.as_deref().unwrap().starts_with("Proteins are essential"));
+
+        // A free preprint PDF counts even though the record isn't flagged open access
+        let preprint = &papers[1];
+        assert_eq!(preprint.authors, vec!["Richard Evans", "O'Neill M"]);
+        assert_eq!(preprint.venue, None);
+        assert!(preprint.is_oa);
+        assert!(preprint.pdf_url.as_deref().unwrap().ends_with("v1.full.pdf"));
+
+        let closed = &papers[2];
+        assert_eq!(closed.authors, vec!["Doe J"]);
+        assert_eq!(closed.pdf_url, None);
+        assert!(!closed.is_oa);
+    }
+
+    #[test]
+    fn test_europe_pmc_url() {
+        let client = EuropePmcClient::new();
+        let query = DiscoveryQuery { author: Some("Jumper".to_string()), ..title_query("protein structure") };
+        assert_eq!(
+            client.build_url(&query).unwrap(),
+            format!("{}/search?query=TITLE%3A%22protein%20structure%22%20AND%20AUTH%3A%22Jumper%22&format=json&resultType=core&pageSize=10", EUROPE_PMC_BASE_URL)
+        );
+        let category_only = DiscoveryQuery { category: Some("q-bio".to_string()), limit: 10, ..Default::default() };
+        assert_eq!(client.build_url(&category_only), None);
+    }
+
+    #[tokio::test]
+    async fn test_europe_pmc_http_error_is_an_error_not_a_panic() {
+        let server = MockServer::start(vec![MockResponse::new(503, "busy")]).await;
+        let client = EuropePmcClient::new().with_base_url(&server.url);
+        let err = client.search(&title_query("protein")).await.unwrap_err();
+        assert!(err.to_string().contains("503"));
+    }
+
+    #[test]
+    fn test_dblp_field_mapping() {
+        let papers = DblpClient::parse_response(&fixture("dblp/search_publ.json")).unwrap();
+        assert_eq!(papers.len(), 3);
+
+        let nips = &papers[0];
+        assert_eq!(nips.title, "Attention is All you Need");
+        assert_eq!(nips.authors, vec!["Ashish Vaswani", "Noam Shazeer", "Niki Parmar"]);
+        assert_eq!(nips.venue.as_deref(), Some("NIPS"));
+        assert_eq!(nips.year, Some(2017));
+        assert_eq!(nips.dblp_key.as_deref(), Some("conf/nips/VaswaniSPUJGKP17"));
+        assert!(nips.pdf_url.is_none() && !nips.is_oa);
+
+        // A lone author and a list of links both arrive as bare values or arrays
+        let corr = &papers[1];
+        assert_eq!(corr.authors, vec!["Ashish Vaswani"]);
+        assert_eq!(corr.doi.as_deref(), Some("10.48550/ARXIV.1706.03762"));
+        assert_eq!(corr.arxiv_id.as_deref(), Some("1706.03762"));
+
+        let survey = &papers[2];
+        assert_eq!(survey.venue.as_deref(), Some("ACM Comput. Surv."));
+        assert_eq!(survey.authors[1], "Wei Wang");
+    }
+
+    #[test]
+    fn test_dblp_empty_result_has_no_hit_list() {
+        let json = r#"{"result": {"hits": {"@total": "0", "@sent": "0"}}}"#;
+        assert!(DblpClient::parse_response(json).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dblp_url_searches_title_and_author_only() {
+        let client = DblpClient::new();
+        let query = DiscoveryQuery { author: Some("Vaswani".to_string()), category: Some("cs.CL".to_string()), ..title_query("Attention") };
+        assert_eq!(client.build_url(&query).unwrap(), format!("{}?q=Attention%20Vaswani&format=json&h=10", DBLP_BASE_URL));
+        let university_only = DiscoveryQuery { university: Some("MIT".to_string()), limit: 10, ..Default::default() };
+        assert_eq!(client.build_url(&university_only), None);
+    }
+
+    #[tokio::test]
+    async fn test_dblp_hit_supplies_venue_to_merged_pdf_record() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("dblp/search_publ.json"))]).await;
+        let dblp = DblpClient::new().with_base_url(&server.url).search(&title_query("Attention")).await.unwrap();
+        let survey = dblp.into_iter().nth(2).unwrap();
+
+        // OpenAlex knows the DOI and an open PDF but not the venue
+        let openalex = PaperMetadata {
+            title: "Attention in graph neural networks: a survey".to_string(),
+            doi: Some("https://doi.org/10.1145/3495161".to_string()),
+            open_alex_id: Some("W1".to_string()),
+            pdf_url: Some("https://example.org/survey.pdf".to_string()),
+            is_oa: true,
+            ..Default::default()
+        };
+        let merged = Merger::merge(vec![openalex, survey]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].venue.as_deref(), Some("ACM Comput. Surv."));
+        assert_eq!(merged[0].pdf_url.as_deref(), Some("https://example.org/survey.pdf"));
+        assert_eq!(merged[0].authors.len(), 2);
+        assert_eq!(merged[0].dblp_key.as_deref(), Some("journals/csur/DoeW23"));
+    }
+
+    #[test]
+    fn test_dblp_is_off_by_default() {
+        assert!(DiscoveryOrchestrator::new(None, None).dblp_client.is_none());
+        assert!(DiscoveryOrchestrator::new(None, None).with_dblp(true).dblp_client.is_some());
+    }
+
+    #[test]
+    fn test_unpaywall_falls_back_to_a_location_with_a_pdf() {
+        let copy = UnpaywallClient::parse_response(&fixture("unpaywall/green_repository_copy.json")).unwrap().unwrap();
+        assert_eq!(copy.pdf_url, "https://europepmc.org/articles/pmc4658098?pdf=render");
+        assert_eq!(copy.license.as_deref(), Some("cc-by-nc"));
+
+        let mut paper = PaperMetadata { title: "Deep learning".to_string(), ..Default::default() };
+        copy.apply_to(&mut paper);
+        assert!(paper.is_oa);
+        assert_eq!(paper.version_hint, Some(crate::layers::VersionHint::AcceptedManuscript));
+    }
+
+    #[test]
+    fn test_unpaywall_closed_paper_has_no_copy() {
+        assert_eq!(UnpaywallClient::parse_response(&fixture("unpaywall/closed.json")).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_enrich_oa_only_looks_up_pdf_less_papers_with_a_doi() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("unpaywall/green_repository_copy.json"))]).await;
+        let orchestrator = DiscoveryOrchestrator {
+            unpaywall_client: Some(UnpaywallClient::new("me@example.org".to_string()).with_base_url(&server.url)),
+            ..DiscoveryOrchestrator::new(None, None)
+        };
+        let mut papers = vec![
+            PaperMetadata { title: "Has PDF".to_string(), doi: Some("10.1/a".to_string()), pdf_url: Some("https://x/a.pdf".to_string()), is_oa: true, ..Default::default() },
+            PaperMetadata { title: "Deep learning".to_string(), doi: Some("https://doi.org/10.1038/Nature14539".to_string()), ..Default::default() },
+            PaperMetadata { title: "No DOI".to_string(), ..Default::default() },
+        ];
+
+        orchestrator.enrich_oa(&mut papers, &EnrichmentQueue::new(ENRICHMENT_CONCURRENCY), &CancellationToken::new()).await.unwrap();
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("/10.1038/nature14539?email=me%40example.org"));
+        assert_eq!(papers[0].pdf_url.as_deref(), Some("https://x/a.pdf"));
+        assert!(papers[1].is_oa);
+        assert_eq!(papers[1].pdf_url.as_deref(), Some("https://europepmc.org/articles/pmc4658098?pdf=render"));
+        assert_eq!(papers[2].pdf_url, None);
+    }
+
+    #[tokio::test]
+    async fn test_enrich_oa_replaces_a_paywall_prone_link() {
+        let server = MockServer::start(vec![MockResponse::new(200, fixture("unpaywall/green_repository_copy.json"))]).await;
+        let paywall = PaywallHeuristic::new(vec!["link.springer.com".to_string()]);
+        let orchestrator = DiscoveryOrchestrator {
+            unpaywall_client: Some(UnpaywallClient::new("me@example.org".to_string()).with_base_url(&server.url).with_paywall(paywall.clone())),
+            ..DiscoveryOrchestrator::new(None, None)
+        };
+        let mut papers = vec![
+            PaperMetadata { title: "Flagged".to_string(), doi: Some("10.1038/nature14539".to_string()), pdf_url: Some("https://link.springer.com/content/pdf/x.pdf".to_string()), is_oa: true, ..Default::default() },
+            PaperMetadata { title: "Open host".to_string(), doi: Some("10.1/b".to_string()), pdf_url: Some("https://x/b.pdf".to_string()), is_oa: true, ..Default::default() },
+        ];
+        assert!(paywall.is_suspicious(&papers[0]));
+
+        orchestrator.enrich_oa(&mut papers, &EnrichmentQueue::new(ENRICHMENT_CONCURRENCY), &CancellationToken::new()).await.unwrap();
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(papers[0].pdf_url.as_deref(), Some("https://europepmc.org/articles/pmc4658098?pdf=render"));
+        assert!(!paywall.is_suspicious(&papers[0]));
+        assert_eq!(papers[1].pdf_url.as_deref(), Some("https://x/b.pdf"));
+    }
+
+    #[tokio::test]
+    async fn test_search_all_returns_cancelled() {
+        let orchestrator = DiscoveryOrchestrator::new(None, None);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let err = orchestrator.search_all(&title_query("x"), &cancel).await.unwrap_err();
+        assert!(crate::layers::is_cancelled(&err));
+    }
+
+    fn synthetic(i: usize) -> PaperMetadata {
+        // Every third record repeats an earlier DOI, as overlapping sources do
+        let n = if i % 3 == 2 { i / 3 } else { i };
+        PaperMetadata {
+            title: format!("Sparse Attention Variant {}", n % 500),
+            doi: Some(format!("10.1000/{}", n)),
+            pdf_url: i.is_multiple_of(2).then(|| format!("https://example.org/{}.pdf", n)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_channel_pauses_the_sender() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let cancel = CancellationToken::new();
+        let sender = tokio::spawn(async move {
+            let papers = (0..100).map(synthetic).collect();
+            forward(papers, &tx, &cancel).await
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(rx.len(), 4);
+        assert!(!sender.is_finished());
+
+        let mut received = Vec::new();
+        while let Some(paper) = rx.recv().await {
+            assert!(rx.len() <= 4);
+            received.push(paper.doi.unwrap());
+        }
+        assert!(sender.await.unwrap().unwrap());
+        assert_eq!(received, (0..100).map(|i| synthetic(i).doi.unwrap()).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_forward_stops_when_receiver_is_gone() {
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        assert!(!forward(vec![synthetic(0)], &tx, &CancellationToken::new()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_pipeline_matches_batch() {
+        let papers: Vec<PaperMetadata> = (0..20_000).map(synthetic).collect();
+        let folding = TitleFolding::default();
+        let batch = Resolver::sort_by_similarity(Resolver::resolve(&ResolutionQuery { title: "Sparse Attention Variant 42", author: None }, Merger::merge(papers.clone()), Threshold::Distance(2), &folding));
+
+        let (tx, mut rx) = mpsc::channel(CANDIDATE_CHANNEL_CAPACITY);
+        let cancel = CancellationToken::new();
+        let produce = async move {
+            for chunk in papers.chunks(1000) {
+                assert!(forward(chunk.to_vec(), &tx, &cancel).await.unwrap());
+            }
+        };
+        let mut index = MergeIndex::default();
+        let consume = async {
+            while let Some(paper) = rx.recv().await {
+                index.push(paper);
+            }
+        };
+        tokio::join!(produce, consume);
+        assert_eq!(index.seen(), 20_000);
+        let matches = Resolver::resolve(&ResolutionQuery { title: "Sparse Attention Variant 42", author: None }, index.into_papers(), Threshold::Distance(2), &folding);
+        let streamed = Resolver::top_k(matches, SortBy::Similarity, 50);
+
+        let key = |(p, d): &(PaperMetadata, Similarity)| (p.doi.clone(), p.pdf_url.clone(), *d);
+        assert_eq!(streamed.len(), 50);
+        assert_eq!(streamed.iter().map(key).collect::<Vec<_>>(), batch.iter().take(50).map(key).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_implausible_years_become_unknown() {
+        let body = r#"{"data": [
+            {"paperId": "a", "title": "Zero", "year": 0, "authors": []},
+            {"paperId": "b", "title": "Future", "year": 2999, "authors": []},
+            {"paperId": "c", "title": "Fine", "year": 2020, "authors": []}
+        ]}"#;
+        let papers = SemanticScholarClient::parse_response(body).unwrap();
+        assert_eq!(papers[0].year, None);
+        assert_eq!(papers[0].rejected_years, vec!["0"]);
+        assert_eq!(papers[1].year, None);
+        assert_eq!(papers[1].rejected_years, vec!["2999"]);
+        assert_eq!(papers[2].year, Some(2020));
+        assert!(papers[2].rejected_years.is_empty());
+
+        // An unknown year never satisfies a year filter
+        let query = DiscoveryQuery { year_to: Some(2025), ..title_query("x") };
+        assert!(!SemanticScholarClient::passes_filters(&papers[1], &query));
+    }
+
+    #[test]
+    fn test_ss_null_data_is_an_empty_page() {
+        assert!(SemanticScholarClient::parse_response(&fixture("semantic_scholar/null_data.json")).unwrap().is_empty());
+        assert!(SemanticScholarClient::parse_response(r#"{"total": 0}"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ss_missing_external_ids() {
+        let papers = SemanticScholarClient::parse_response(&fixture("semantic_scholar/missing_external_ids.json")).unwrap();
+        assert_eq!(papers.len(), 2);
+        assert_eq!(papers[0].doi, None);
+        assert_eq!(papers[0].arxiv_id, None);
+        assert!(papers[0].external_ids.is_empty());
+        assert_eq!(papers[0].authors, vec!["Jane Doe"]);
+        assert_eq!(papers[1].doi, None);
+        assert_eq!(papers[1].pdf_url, None);
+    }
+
+    #[test]
+    fn test_ss_unexpected_fields_and_bad_records() {
+        let papers = SemanticScholarClient::parse_response(&fixture("semantic_scholar/extra_fields.json")).unwrap();
+        // The record with a numeric title and the one without a title are skipped
+        assert_eq!(papers.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), vec!["Attention Is All You Need"]);
+        let paper = &papers[0];
+        assert_eq!(paper.arxiv_id.as_deref(), Some("1706.03762"));
+        assert_eq!(paper.external_ids.get("DBLP").map(String::as_str), Some("conf/nips/VaswaniSPUJGKP17"));
+        assert_eq!(paper.external_ids.get("CorpusId").map(String::as_str), Some("13756489"));
+        assert_eq!(paper.authors, vec!["Ashish Vaswani"]);
+        assert_eq!(paper.categories, vec!["s2:Computer Science"]);
+    }
+
+    fn quick_retry() -> RetryPolicy {
+        RetryPolicy { initial_backoff: Duration::from_millis(1), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_ss_retries_after_429() {
+        let body = r#"{"data": [{"paperId": "a", "title": "Throttled", "authors": []}]}"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(429, "Too Many Requests").with_header("Retry-After", "0"),
+            MockResponse::new(429, "Too Many Requests"),
+            MockResponse::new(200, body),
+        ]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url).with_retry(quick_retry());
+
+        let papers = client.search(&title_query("Throttled")).await.unwrap();
+        assert_eq!(papers.len(), 1);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    fn ss_page(ids: std::ops::Range<usize>, next: Option<usize>) -> String {
+        let data: Vec<_> = ids.map(|i| serde_json::json!({"paperId": format!("p{}", i), "title": format!("Paper {}", i), "authors": []})).collect();
+        let mut page = serde_json::json!({"total": 250, "data": data});
+        if let Some(next) = next {
+            page["next"] = next.into();
+        }
+        page.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_ss_stitches_pages() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, ss_page(0..100, Some(100))),
+            MockResponse::new(200, ss_page(100..200, Some(200))),
+        ]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url);
+
+        let papers = client.search(&DiscoveryQuery { limit: 150, ..title_query("x") }).await.unwrap();
+        assert_eq!(papers.len(), 150);
+        assert_eq!(papers[100].semantic_scholar_id.as_deref(), Some("p100"));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].ends_with("&limit=100"));
+        assert!(requests[1].ends_with("&limit=100&offset=100"));
+    }
+
+    #[tokio::test]
+    async fn test_ss_keeps_earlier_pages_when_a_later_one_fails() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, ss_page(0..100, Some(100))),
+            MockResponse::new(429, "slow down"),
+            MockResponse::new(429, "slow down"),
+        ]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url)
+            .with_retry(RetryPolicy { max_attempts: 2, ..quick_retry() });
+
+        let papers = client.search(&DiscoveryQuery { limit: 300, ..title_query("x") }).await.unwrap();
+        assert_eq!(papers.len(), 100);
+        assert_eq!(server.requests().len(), 3);
+
+        // The last page has no `next`
+        let server = MockServer::start(vec![MockResponse::new(200, ss_page(0..40, None))]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url);
+        assert_eq!(client.search(&DiscoveryQuery { limit: 300, ..title_query("x") }).await.unwrap().len(), 40);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_matches_mocked_runs() {
+        use crate::layers::estimate::{estimate, SearchPlan};
+        // Full pages all the way: the estimate is exact
+        let query = DiscoveryQuery { limit: 250, ..title_query("x") };
+        let server = MockServer::start(vec![
+            MockResponse::new(200, ss_page(0..100, Some(100))),
+            MockResponse::new(200, ss_page(100..200, Some(200))),
+            MockResponse::new(200, ss_page(200..300, Some(300))),
+        ]).await;
+        SemanticScholarClient::new(None).with_base_url(&server.url).search(&query).await.unwrap();
+        assert_eq!(server.requests().len(), estimate(&query, SearchPlan::default()).requests(DiscoverySource::SemanticScholar));
+
+        let works = |from: usize, to: usize, next: Option<&str>| {
+            let results: Vec<_> = (from..to).map(|i| serde_json::json!({"id": format!("https://openalex.org/W{}", i), "title": format!("W{}", i), "authorships": []})).collect();
+            MockResponse::new(200, serde_json::json!({"meta": {"count": 10_000, "next_cursor": next}, "results": results}).to_string())
+        };
+        let query = DiscoveryQuery { limit: 450, ..title_query("x") };
+        let server = MockServer::start(vec![works(0, 200, Some("c1")), works(200, 400, Some("c2")), works(400, 600, Some("c3"))]).await;
+        OpenAlexClient::new(None).with_base_url(&server.url).search(&query).await.unwrap();
+        assert_eq!(server.requests().len(), estimate(&query, SearchPlan::default()).requests(DiscoverySource::OpenAlex));
+
+        let server = MockServer::start(vec![
+            MockResponse::new(200, arxiv_feed(0..100)),
+            MockResponse::new(200, arxiv_feed(100..200)),
+            MockResponse::new(200, arxiv_feed(200..250)),
+        ]).await;
+        let client = ArxivClient::new().with_base_url(&server.url).with_quota(Quota::per_second(nonzero!(1000u32)));
+        client.search(&DiscoveryQuery { limit: 250, ..title_query("x") }).await.unwrap();
+        assert_eq!(server.requests().len(), estimate(&DiscoveryQuery { limit: 250, ..title_query("x") }, SearchPlan::default()).requests(DiscoverySource::Arxiv));
+
+        // Results running out early only ever make fewer requests than estimated
+        let query = DiscoveryQuery { limit: 1000, ..title_query("x") };
+        let server = MockServer::start(vec![works(0, 200, Some("c1")), works(200, 260, None)]).await;
+        OpenAlexClient::new(None).with_base_url(&server.url).search(&query).await.unwrap();
+        let estimated = estimate(&query, SearchPlan::default()).requests(DiscoverySource::OpenAlex);
+        assert!(server.requests().len() <= estimated, "{} > {}", server.requests().len(), estimated);
+
+        // A filtered search is counted with the unfiltered retry S2 may force
+        let query = DiscoveryQuery { year_from: Some(2020), publication_filter: Some(PublicationFilter::Published), ..title_query("x") };
+        let server = MockServer::start(vec![MockResponse::new(400, "bad filters"), MockResponse::new(200, ss_page(0..10, None))]).await;
+        SemanticScholarClient::new(None).with_base_url(&server.url).search(&query).await.unwrap();
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(server.requests().len(), estimate(&query, SearchPlan::default()).requests(DiscoverySource::SemanticScholar));
+
+        // Europe PMC and DBLP skip searches they have no terms for
+        let plan = SearchPlan { dblp: true, ..Default::default() };
+        for query in [title_query("Attention"), DiscoveryQuery { university: Some("MIT".to_string()), ..Default::default() }] {
+            let server = MockServer::start(vec![MockResponse::new(200, fixture("europepmc/search_core.json"))]).await;
+            EuropePmcClient::new().with_base_url(&server.url).search(&query).await.unwrap();
+            assert_eq!(server.requests().len(), estimate(&query, plan).requests(DiscoverySource::EuropePmc));
+
+            let server = MockServer::start(vec![MockResponse::new(200, fixture("dblp/search_publ.json"))]).await;
+            DblpClient::new().with_base_url(&server.url).search(&query).await.unwrap();
+            assert_eq!(server.requests().len(), estimate(&query, plan).requests(DiscoverySource::Dblp));
+        }
+        let categories = DiscoveryQuery { category: Some("cs.LG".to_string()), ..Default::default() };
+        let estimated = estimate(&categories, plan);
+        assert_eq!((estimated.requests(DiscoverySource::EuropePmc), estimated.requests(DiscoverySource::Dblp)), (0, 0));
+    }
+
+    #[test]
+    fn test_ss_bulk_response() {
+        let page = SemanticScholarClient::parse_bulk_page(&fixture("semantic_scholar/bulk.json")).unwrap();
+        assert_eq!(page.total, Some(48213));
+        assert_eq!(page.token.as_deref(), Some("PCOA3RZZB2ADADAEYCX2BLJJRSEGRPF6"));
+        // Same fields as relevance search; the untitled record is skipped
+        assert_eq!(page.papers.len(), 2);
+        assert_eq!(page.papers[0].arxiv_id.as_deref(), Some("1706.03762"));
+        assert_eq!(page.papers[0].pdf_url.as_deref(), Some("https://arxiv.org/pdf/1706.03762"));
+        assert_eq!(page.papers[1].doi.as_deref(), Some("10.1038/nature14539"));
+        assert_eq!(page.papers[1].categories, vec!["s2:Computer Science"]);
+        assert_eq!(page.papers[0].citation_count, Some(132154));
+        assert_eq!(page.papers[1].citation_count, None);
+
+        let last = SemanticScholarClient::parse_bulk_page(r#"{"total": 2, "token": null, "data": null}"#).unwrap();
+        assert!(last.token.is_none() && last.papers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ss_bulk_follows_the_token() {
+        let batch = |ids: std::ops::Range<usize>, token: Option<&str>| {
+            let mut page: serde_json::Value = serde_json::from_str(&ss_page(ids, None)).unwrap();
+            page["token"] = token.into();
+            MockResponse::new(200, page.to_string())
+        };
+        let server = MockServer::start(vec![batch(0..1000, Some("a/b+c")), batch(1000..1500, None)]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url);
+        let query = DiscoveryQuery { author: Some("Jane Doe".to_string()), limit: 5000, year_from: Some(2020), ..Default::default() };
+
+        let papers = client.search_bulk(&query).await.unwrap();
+        assert_eq!(papers.len(), 1500);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0], format!("/paper/search/bulk?query=Jane%20Doe&fields={}&publicationDateOrYear=2020:", SS_FIELDS));
+        assert!(requests[1].contains("&token=a%2Fb%2Bc&"));
+
+        // Stops once the limit is reached, whatever the token says
+        let server = MockServer::start(vec![batch(0..1000, Some("more"))]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url);
+        let papers = client.search_bulk(&DiscoveryQuery { limit: 200, ..query }).await.unwrap();
+        assert_eq!(papers.len(), 200);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ss_gives_up_after_max_attempts() {
+        let server = MockServer::start(vec![MockResponse::new(503, "busy"); 5]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url).with_retry(quick_retry());
+
+        let err = client.search(&title_query("x")).await.unwrap_err();
+        assert!(err.to_string().contains("503"));
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let server = MockServer::start(vec![MockResponse::new(404, "missing"), MockResponse::new(200, "")]).await;
+        let client = Client::new();
+        let resp = quick_retry().send("Test", || async { Ok(client.get(&server.url).send().await?) }).await.unwrap();
+        assert_eq!(resp.status(), 404);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_after_forms() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        // A date in the past means "now"
+        headers.insert(reqwest::header::RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+        headers.insert(reqwest::header::RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_ss_rejected_filters_fall_back_to_client_side() {
+        let body = r#"{"total": 2, "data": [
+            {"paperId": "a", "title": "Old", "year": 2010, "authors": [], "isOpenAccess": false},
+            {"paperId": "b", "title": "New", "year": 2021, "authors": [], "isOpenAccess": true,
+             "openAccessPdf": {"url": "https://example.org/b.pdf"}}
+        ]}"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(400, r#"{"error": "Unacceptable filter combination"}"#),
+            MockResponse::new(200, body),
+        ]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url);
+        let query = DiscoveryQuery { year_from: Some(2020), oa_only: true, ..title_query("x") };
+
+        let papers = client.search(&query).await.unwrap();
+        assert_eq!(papers.len(), 1);
+        assert_eq!(papers[0].title, "New");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("publicationDateOrYear=2020:"));
+        assert!(!requests[1].contains("publicationDateOrYear"));
+    }
+
+    #[tokio::test]
+    async fn test_ss_locally_filtered_empty_page_is_not_the_end() {
+        let page = |year: u32, next: Option<usize>, token: Option<&str>| {
+            let mut page = serde_json::json!({"data": [{"paperId": format!("p{}", year), "title": format!("Paper {}", year), "year": year, "authors": []}]});
+            page["next"] = next.into();
+            page["token"] = token.into();
+            MockResponse::new(200, page.to_string())
+        };
+        let rejected = || MockResponse::new(400, r#"{"error": "Unacceptable filter combination"}"#);
+        let query = DiscoveryQuery { year_from: Some(2020), limit: 10, ..title_query("x") };
+
+        let server = MockServer::start(vec![rejected(), page(2010, Some(1), None), page(2021, None, None)]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url);
+        let papers = client.search(&query).await.unwrap();
+        assert_eq!(papers.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), ["Paper 2021"]);
+        assert_eq!(server.requests().len(), 3);
+
+        let server = MockServer::start(vec![rejected(), page(2010, None, Some("more")), page(2021, None, None)]).await;
+        let client = SemanticScholarClient::new(None).with_base_url(&server.url);
+        let papers = client.search_bulk(&query).await.unwrap();
+        assert_eq!(papers.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), ["Paper 2021"]);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_ss_fields_of_study_from_both_shapes() {
+        let body = r#"{"data": [
+            {"paperId": "a", "title": "Legacy", "authors": [],
+             "fieldsOfStudy": ["Computer Science", "Mathematics"]},
+            {"paperId": "b", "title": "Newer", "authors": [], "fieldsOfStudy": null,
+             "s2FieldsOfStudy": [{"category": "Computer Science", "source": "external"},
+                                 {"category": "Medicine", "source": "s2-fos-model"}]},
+            {"paperId": "c", "title": "Both", "authors": [],
+             "fieldsOfStudy": ["Computer Science"],
+             "s2FieldsOfStudy": [{"category": "computer science", "source": "s2-fos-model"},
+                                 {"category": "Linguistics", "source": "s2-fos-model"}]},
+            {"paperId": "d", "title": "Neither", "authors": []},
+            {"paperId": "e", "title": "Old arXiv", "authors": [],
+             "externalIds": {"ArXiv": "cs.DS/0301001"}, "fieldsOfStudy": ["Computer Science"]}
+        ]}"#;
+        let papers = SemanticScholarClient::parse_response(body).unwrap();
+        assert_eq!(papers[0].categories, vec!["s2:Computer Science", "s2:Mathematics"]);
+        assert_eq!(papers[1].categories, vec!["s2:Computer Science", "s2:Medicine"]);
+        assert_eq!(papers[2].categories, vec!["s2:Computer Science", "s2:Linguistics"]);
+        assert!(papers[3].categories.is_empty());
+        assert_eq!(papers[4].categories, vec!["cs.DS", "s2:Computer Science"]);
+        assert_eq!(papers[4].primary_arxiv_category(), Some("cs.DS"));
+        assert_eq!(papers[0].primary_arxiv_category(), None);
+        assert_eq!(papers[1].s2_fields().next(), Some("Computer Science"));
+    }
+}
+
+// Pinned queries against the real APIs, to catch schema drift the fixtures can't. Built only with
+// `--features live-tests` and skipped unless CSR_LIVE_TESTS=1; each test makes one request to its
+// own source, through the client's rate limiter where it has one, so a run stays polite. They
+// assert only what won't change (a title, a plausible year, a PDF), and tell an unreachable
+// source apart from one whose responses no longer parse.
+#[cfg(all(test, feature = "live-tests"))]
+mod live_tests {
+    use super::*;
+    use crate::layers::enrichment::CrossrefWork;
+
+    fn enabled() -> bool {
+        let on = std::env::var("CSR_LIVE_TESTS").is_ok_and(|v| v == "1");
+        if !on {
+            eprintln!("skipping live test: set CSR_LIVE_TESTS=1 to run it");
+        }
+        on
+    }
+
+    // A drifted schema still answers 200, so failing here means the source or the network is down
+    async fn get(source: &str, client: &Client, url: &str) -> String {
+        let resp = client.get(url).send().await
+            .unwrap_or_else(|e| panic!("{} unreachable ({}): {}", source, url, e));
+        let status = resp.status();
+        assert!(status.is_success(), "{} answered {} for {}", source, status, url);
+        encoding::response_text(resp, source).await.unwrap()
+    }
+
+    fn drifted(source: &str, what: impl std::fmt::Display) -> String {
+        format!("{} schema may have drifted: {}", source, what)
+    }
+
+    fn assert_plausible_year(source: &str, year: Option<u32>) {
+        let year = year.unwrap_or_else(|| panic!("{}", drifted(source, "no year")));
+        assert!((1990..=2100).contains(&year), "{}", drifted(source, format_args!("implausible year {}", year)));
+    }
+
+    #[tokio::test]
+    async fn live_arxiv_paper_by_id() {
+        if !enabled() { return; }
+        let client = ArxivClient::new();
+        let url = format!("{}?id_list=1706.03762", client.base_url);
+        let papers = client.fetch_page(&url).await.unwrap_or_else(|e| panic!("arXiv unreachable ({}): {}", url, e));
+        let parse_errors = client.diagnostics.summary();
+        assert!(parse_errors.is_empty(), "{}", drifted("arXiv", format_args!("{:?}", parse_errors)));
+        let paper = papers.first().unwrap_or_else(|| panic!("{}", drifted("arXiv", "no <entry> in the feed")));
+        assert_eq!(paper.arxiv_id.as_deref(), Some("1706.03762"), "{}", drifted("arXiv", "unexpected <id>"));
+        assert!(paper.title.contains("Attention"), "{}", drifted("arXiv", format_args!("title '{}'", paper.title)));
+        assert!(!paper.authors.is_empty(), "{}", drifted("arXiv", "no <author>"));
+        assert_plausible_year("arXiv", paper.year);
+        let pdf = paper.pdf_url.as_deref().unwrap_or_else(|| panic!("{}", drifted("arXiv", "no PDF link")));
+        assert!(pdf.contains("arxiv.org/pdf/1706.03762"), "{}", drifted("arXiv", format_args!("PDF link {}", pdf)));
+    }
+
+    #[tokio::test]
+    async fn live_crossref_doi_lookup() {
+        if !enabled() { return; }
+        let url = "https://api.crossref.org/works/10.1038/nature14539";
+        let body = get("Crossref", &Client::new(), url).await;
+        let work = CrossrefWork::parse(&body).unwrap_or_else(|e| panic!("{}", drifted("Crossref", e)));
+        assert_plausible_year("Crossref", work.year);
+        assert!(work.container_title.is_some_and(|t| !t.is_empty()), "{}", drifted("Crossref", "no container-title"));
+    }
 
-        all_results
+    #[tokio::test]
+    async fn live_openalex_institution_resolution() {
+        if !enabled() { return; }
+        let client = OpenAlexClient::new(std::env::var("OPENALEX_EMAIL").ok());
+        let url = client.institutions_url("Massachusetts Institute of Technology");
+        let body = get("OpenAlex", &client.client, &url).await;
+        let institutions = OpenAlexClient::parse_institutions(&body).unwrap_or_else(|e| panic!("{}", drifted("OpenAlex", e)));
+        let first = institutions.first().unwrap_or_else(|| panic!("{}", drifted("OpenAlex", "no institutions")));
+        assert!(first.id.rsplit('/').next().is_some_and(|id| id.starts_with('I')), "{}", drifted("OpenAlex", format_args!("institution ID {}", first.id)));
+        assert!(!first.display_name.is_empty(), "{}", drifted("OpenAlex", "no display_name"));
     }
 }
@@ -1,125 +1,942 @@
 use anyhow::{Result, anyhow};
 use reqwest::Client;
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
+use std::io::{BufRead, Write};
 use tokio::fs::{self, create_dir_all, File};
-use tokio::io::{AsyncWriteExt, AsyncReadExt};
-use crate::layers::PaperMetadata;
+use tokio::io::AsyncWriteExt;
+use crate::layers::{PaperMetadata, VersionHint, Cancelled};
+use crate::layers::{arxiv_id, http};
+use crate::layers::discovery::{arxiv_limiter, ArxivLimiter};
+use crate::layers::enrichment::normalize_doi;
+use crate::layers::legality::{AccessBasis, LegalityChecker};
+use crate::layers::proxy::InstitutionalProxy;
+use crate::layers::integrity::{hash_file, Check, Verdict};
+use tokio_util::sync::CancellationToken;
+use crate::layers::verify::{check_pdf_title, TitleCheck};
+use crate::layers::migrations::{self, DocumentKind};
+use crate::layers::diagnostics::{Diagnostics, Source, WarningKind};
+use crate::layers::mirror::{LocalMirror, PdfSource};
+use crate::layers::landing::{extract_pdf_link, NotAPdf, MAX_LANDING_PAGE_BYTES};
+use crate::layers::readme;
+use std::sync::Arc;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use chrono::Utc;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct ManifestEntry {
-    title: String,
-    first_author: String,
-    year: Option<u32>,
-    id: String,
-    relative_path: String,
-    downloaded_at: String,
+#[derive(Debug, Default, Serialize, Deserialize, Clone, schemars::JsonSchema)]
+pub struct ManifestEntry {
+    pub title: String,
+    pub first_author: String,
+    pub year: Option<u32>,
+    pub id: String,
+    pub relative_path: String,
+    pub downloaded_at: String,
+    pub version_hint: Option<VersionHint>,
+    #[serde(default)]
+    pub title_mismatch: bool,
+    // Oldest first, including failed tries before the one that succeeded
+    #[serde(default)]
+    pub attempts: Vec<AttemptRecord>,
+    // Set when the PDF came from the local mirror rather than the paper's own URL
+    #[serde(default)]
+    pub mirror: Option<String>,
+    // Set when the paper's URL served this HTML landing page and the PDF link was read off it
+    #[serde(default)]
+    pub landing_page: Option<String>,
+    // Open Access, or a closed paper fetched through the institutional proxy
+    #[serde(default)]
+    pub access: AccessBasis,
+    // Of the PDF as saved, checked by `verify`; absent for entries from older releases
+    #[serde(default)]
+    pub sha256: Option<String>,
+    // The arXiv e-print next to the PDF ("<id>/source.tar.gz"), when --with-source fetched one
+    #[serde(default)]
+    pub arxiv_source: Option<String>,
+    // The search named in the README cover note, so `verify --fix` can rewrite the note
+    #[serde(default)]
+    pub found_by: Option<String>,
 }
 
+// Only the most recent attempts are kept per paper
+const MAX_ATTEMPTS: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema)]
+pub struct AttemptRecord {
+    pub timestamp: String,
+    pub url: String,
+    pub outcome: AttemptOutcome,
+    pub http_status: Option<u16>,
+    pub bytes: Option<u64>,
+    pub error_kind: Option<AttemptError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+pub enum AttemptOutcome {
+    Success,
+    Failed,
+    Cancelled,
+    Quarantined,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+pub enum AttemptError {
+    HttpStatus,
+    Network,
+    Io,
+    TitleMismatch,
+    // An HTML page where the PDF should have been
+    NotAPdf,
+    Other,
+}
+
+impl AttemptRecord {
+    fn new(url: &str) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            url: url.to_string(),
+            outcome: AttemptOutcome::Failed,
+            http_status: None,
+            bytes: None,
+            error_kind: None,
+        }
+    }
+
+    // Settles outcome and error kind once the attempt is over
+    fn finish<T>(&mut self, result: &Result<T>) {
+        let err = match result {
+            Ok(_) => {
+                self.outcome = AttemptOutcome::Success;
+                return;
+            }
+            Err(err) => err,
+        };
+        if crate::layers::is_cancelled(err) {
+            self.outcome = AttemptOutcome::Cancelled;
+            return;
+        }
+        if self.error_kind.is_none() {
+            self.error_kind = Some(if err.downcast_ref::<NotAPdf>().is_some() {
+                AttemptError::NotAPdf
+            } else if err.downcast_ref::<reqwest::Error>().is_some() {
+                AttemptError::Network
+            } else if err.downcast_ref::<std::io::Error>().is_some() {
+                AttemptError::Io
+            } else if self.http_status.is_some_and(|s| !(200..300).contains(&s)) {
+                AttemptError::HttpStatus
+            } else {
+                AttemptError::Other
+            });
+        }
+    }
+}
+
+fn is_html(response: &reqwest::Response) -> bool {
+    response.headers().get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            let v = v.trim().to_ascii_lowercase();
+            v.starts_with("text/html") || v.starts_with("application/xhtml+xml")
+        })
+}
+
+fn push_attempts(attempts: &mut Vec<AttemptRecord>, new: impl IntoIterator<Item = AttemptRecord>) {
+    attempts.extend(new);
+    if attempts.len() > MAX_ATTEMPTS {
+        attempts.drain(..attempts.len() - MAX_ATTEMPTS);
+    }
+}
+
+/// A paper that has been tried but never downloaded successfully.
+#[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
+pub struct FailedPaper {
+    pub id: String,
+    pub title: String,
+    pub attempts: Vec<AttemptRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, schemars::JsonSchema)]
+pub struct ManifestFile {
+    pub schema_version: u32,
+    pub entries: Vec<ManifestEntry>,
+    #[serde(default)]
+    pub failures: Vec<FailedPaper>,
+}
+
+// One line of the pending journal
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum PendingRecord {
+    Entry(ManifestEntry),
+    Failure { id: String, title: String, attempt: AttemptRecord },
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UnavailableFile {
+    pub schema_version: u32,
+    // university -> category -> author -> title -> [papers], whichever of those the query had
+    #[schemars(with = "std::collections::BTreeMap<String, SearchNode>")]
+    pub searches: serde_json::Value,
+}
+
+// The shape of `searches` for the JSON Schema. The tree is built as a Value because its depth
+// depends on which of the query fields were given.
+#[derive(schemars::JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum SearchNode {
+    Papers(Vec<PaperMetadata>),
+    Group(std::collections::BTreeMap<String, SearchNode>),
+}
+
+// metadata.json is the paper itself with the version marker alongside its fields
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MetadataFile<T> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub paper: T,
+}
+
+/// A paper taken out of the library with `remove`, as archived in `archive/removed.jsonl`.
+#[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
+pub struct RemovedRecord {
+    pub removed_at: String,
+    pub reason: Option<String>,
+    pub entry: ManifestEntry,
+    // metadata.json upgraded to the current schema, if it could still be read
+    pub metadata: Option<PaperMetadata>,
+}
+
+/// What `verify --fix` changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FixSummary {
+    pub hashes_recorded: usize,
+    pub readmes_rewritten: usize,
+}
+
+impl RemovedRecord {
+    /// The removal date ("2026-03-01"), or the raw timestamp if it doesn't parse.
+    pub fn removed_on(&self) -> String {
+        chrono::DateTime::parse_from_rfc3339(&self.removed_at)
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|_| self.removed_at.clone())
+    }
+}
+
+// Append-only, so a removal can always be undone by hand from what was archived
+pub const REMOVED_ARCHIVE: &str = "archive/removed.jsonl";
+
+// Manifest entries for a batch are appended here and folded into manifest.json when the
+// batch ends, so a crash mid-batch loses nothing and the manifest is rewritten only once
+const PENDING_JOURNAL: &str = "pending_entries.jsonl";
+
+// The submitted source of the latest version, gzipped
+pub const ARXIV_EPRINT_URL: &str = "https://arxiv.org/e-print";
+
 pub struct Downloader {
     client: Client,
     base_dir: PathBuf,
+    strict_verify: bool,
+    flush_per_download: bool,
+    diagnostics: Arc<Diagnostics>,
+    mirror: Option<LocalMirror>,
+    // Set with --via-proxy: closed papers are fetched through it
+    proxy: Option<InstitutionalProxy>,
+    // The search that found the papers; when set, each paper directory gets a README.md
+    readme_query: Option<String>,
+    // Set with --with-source: arXiv papers also get their e-print
+    with_source: bool,
+    eprint_base_url: String,
+    // E-prints count against arXiv's rate limit like searches do
+    arxiv_limiter: ArxivLimiter,
+    // How long a download may stall before it fails (see http::READ_TIMEOUT)
+    read_timeout: Duration,
+}
+
+// Folds the journal into the manifest however download_many exits (error, cancel, panic)
+struct FlushOnDrop<'a>(&'a Downloader);
+
+impl Drop for FlushOnDrop<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.flush_pending() {
+            tracing::error!("Failed to flush pending manifest entries: {}", e);
+        }
+    }
 }
 
 impl Downloader {
     pub fn new(base_dir: impl Into<PathBuf>) -> Self {
         Self {
-            client: Client::new(),
+            client: http::download_client(http::READ_TIMEOUT).build().expect("a client with timeouts always builds"),
             base_dir: base_dir.into(),
+            strict_verify: false,
+            flush_per_download: false,
+            diagnostics: Arc::default(),
+            mirror: None,
+            proxy: None,
+            readme_query: None,
+            with_source: false,
+            eprint_base_url: ARXIV_EPRINT_URL.to_string(),
+            arxiv_limiter: arxiv_limiter(),
+            read_timeout: http::READ_TIMEOUT,
         }
     }
 
-    pub async fn download_paper(&self, paper: &PaperMetadata) -> Result<PathBuf> {
-        if !paper.is_oa {
-            return Err(anyhow!("Paper is not Open Access, skipping download."));
+    #[cfg(test)]
+    fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self.client = http::download_client(read_timeout).build().unwrap();
+        self
+    }
+
+    #[cfg(test)]
+    fn with_eprint_base_url(mut self, base_url: &str) -> Self {
+        use governor::{Quota, RateLimiter};
+        self.eprint_base_url = base_url.to_string();
+        // The mock server doesn't need arXiv's spacing
+        self.arxiv_limiter = Arc::new(RateLimiter::direct(Quota::per_second(nonzero_ext::nonzero!(1000u32))));
+        self
+    }
+
+    /// Wait on this limiter before fetching e-prints, normally the arXiv search's own.
+    pub fn with_arxiv_limiter(mut self, limiter: ArxivLimiter) -> Self {
+        self.arxiv_limiter = limiter;
+        self
+    }
+
+    /// Write a README.md cover note into each paper directory, naming `query` as what found it.
+    pub fn with_readme(mut self, query: Option<String>) -> Self {
+        self.readme_query = query;
+        self
+    }
+
+    /// Also fetch the LaTeX source of papers with an arXiv ID.
+    pub fn with_source(mut self, with_source: bool) -> Self {
+        self.with_source = with_source;
+        self
+    }
+
+    /// Try this mirror before the paper's own PDF URL.
+    pub fn with_mirror(mut self, mirror: Option<LocalMirror>) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    /// Fetch closed papers through this institutional proxy, sending its session cookie.
+    pub fn with_proxy(mut self, proxy: Option<InstitutionalProxy>) -> Self {
+        if let Some(proxy) = &proxy {
+            self.client = http::download_client(self.read_timeout).cookie_provider(proxy.cookie_jar()).build()
+                .expect("a client with a cookie jar always builds");
         }
+        self.proxy = proxy;
+        self
+    }
 
-        let pdf_url = paper.pdf_url.as_ref()
+    pub fn with_diagnostics(mut self, diagnostics: Arc<Diagnostics>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Rewrite the manifest after every download instead of once per batch.
+    pub fn with_flush_per_download(mut self, flush_per_download: bool) -> Self {
+        self.flush_per_download = flush_per_download;
+        self
+    }
+
+    /// Quarantine PDFs whose first-page title doesn't match the metadata instead of keeping them.
+    pub fn with_strict_verify(mut self, strict_verify: bool) -> Self {
+        self.strict_verify = strict_verify;
+        self
+    }
+
+    /// Downloads each paper in turn, skipping ones that aren't legally downloadable.
+    /// Returns the directories that were written, or `Cancelled` if the token fires.
+    pub async fn download_many(&self, papers: &[PaperMetadata], cancel: &CancellationToken) -> Result<Vec<PathBuf>> {
+        let flush = FlushOnDrop(self);
+        let mut saved = Vec::new();
+        for paper in papers {
+            if cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+
+            // 3. Legality Check (Layer 3) - Late binding check
+            let Some(access) = LegalityChecker::access_basis(paper, self.proxy.is_some()) else {
+                tracing::warn!("Skipping '{}': Not Open Access.", paper.title);
+                self.diagnostics.warn(WarningKind::NotOpenAccess, Source::Download, paper.title.clone());
+                continue;
+            };
+
+            if self.pdf_url(paper, access).is_none() {
+                tracing::warn!("Skipping '{}': No PDF URL available.", paper.title);
+                self.diagnostics.warn(WarningKind::NoPdfUrl, Source::Download, paper.title.clone());
+                continue;
+            }
+
+            tracing::info!("Downloading: {}", paper.title);
+            match self.download_paper(paper, cancel).await {
+                Ok(path) => {
+                    tracing::info!("Success! Saved to: {:?}", path);
+                    saved.push(path);
+                }
+                Err(e) if crate::layers::is_cancelled(&e) => return Err(e),
+                Err(e) => {
+                    tracing::error!("Failed to download '{}': {}", paper.title, e);
+                    self.diagnostics.warn(WarningKind::DownloadFailed, Source::Download, paper.title.clone());
+                }
+            }
+        }
+        drop(flush);
+        Ok(saved)
+    }
+
+    /// Downloads one paper. Its manifest entry goes to the pending journal and only reaches
+    /// manifest.json on the next flush (end of `download_many`, or immediately with
+    /// `with_flush_per_download`).
+    pub async fn download_paper(&self, paper: &PaperMetadata, cancel: &CancellationToken) -> Result<PathBuf> {
+        let access = LegalityChecker::access_basis(paper, self.proxy.is_some())
+            .ok_or_else(|| anyhow!("Paper is not Open Access, skipping download."))?;
+
+        let pdf_url = self.pdf_url(paper, access)
             .ok_or_else(|| anyhow!("No PDF URL found for paper despite OA status."))?;
 
-        let raw_id = paper.doi.as_ref()
-            .or(paper.arxiv_id.as_ref())
-            .or(paper.semantic_scholar_id.as_ref())
-            .map(|s| s.as_str())
-            .unwrap_or("unknown_id");
+        let paper_id = paper_id(paper);
 
-        // Sanitize ID: remove scheme, replace non-alphanumeric chars
-        let paper_id = raw_id.replace("http://", "")
-                             .replace("https://", "")
-                             .replace(|c: char| !c.is_alphanumeric() && c != '.' && c != '-', "_");
+        // The mirror goes first; if it doesn't have the paper we fall back to the external URL
+        let mirror_source = self.mirror.as_ref().and_then(|m| m.locate(paper));
+        let mut sources: std::collections::VecDeque<(PdfSource, bool)> = mirror_source.map(|s| (s, true)).into_iter()
+            .chain(std::iter::once((PdfSource::Url(pdf_url.clone()), false)))
+            .collect();
 
+        // Every try is recorded, so flaky papers show their history in the manifest
+        let mut attempts = Vec::new();
+        let mut result = Err(anyhow!("No PDF source tried"));
+        let mut mirror = None;
+        let mut landing_page = None;
+        while let Some((source, is_mirror)) = sources.pop_front() {
+            let mut attempt = AttemptRecord::new(&source.location());
+            result = self.fetch_paper(paper, &paper_id, &source, cancel, &mut attempt).await;
+            attempt.finish(&result);
+            attempts.push(attempt);
+            match &result {
+                Ok(_) if is_mirror => mirror = Some(source.location()),
+                Err(e) if is_mirror && !crate::layers::is_cancelled(e) => {
+                    tracing::info!("Local mirror miss for '{}': {}", paper.title, e);
+                    continue;
+                }
+                // At most one extracted link, and never from a page reached through another
+                Err(e) if landing_page.is_none() => {
+                    if let Some(page) = e.downcast_ref::<NotAPdf>() {
+                        match extract_pdf_link(&page.html, &page.url) {
+                            Some(link) => {
+                                tracing::info!("'{}' is a landing page; trying its PDF link {}", page.url, link);
+                                landing_page = Some(page.url.clone());
+                                sources.push_back((PdfSource::Url(link), false));
+                                continue;
+                            }
+                            None => tracing::info!("Landing page '{}' has no PDF link on its own host", page.url),
+                        }
+                    }
+                }
+                _ => {}
+            }
+            break;
+        }
+
+        match &result {
+            Ok((pdf_path, title_mismatch)) => {
+                // Before the README, so its file list includes the source; the PDF is saved either way
+                let arxiv_source = match self.with_source {
+                    true => self.download_arxiv_source(paper, cancel).await.unwrap_or_else(|e| {
+                        tracing::warn!("Could not fetch the arXiv source of '{}': {}", paper.title, e);
+                        None
+                    }),
+                    false => None,
+                };
+                if let (Some(query), Some(source)) = (&self.readme_query, attempts.last()) {
+                    // The cover note is a convenience; the PDF and metadata are already saved
+                    let dir = pdf_path.parent().ok_or_else(|| anyhow!("PDF path has no directory"));
+                    if let Err(e) = dir.and_then(|dir| readme::write(dir, paper, query, &source.url, mirror.is_some(), access)) {
+                        tracing::warn!("Failed to write README for '{}': {}", paper.title, e);
+                    }
+                }
+                let mut entry = self.manifest_entry(paper, &paper_id, pdf_path, *title_mismatch);
+                entry.attempts = attempts;
+                entry.mirror = mirror;
+                entry.landing_page = landing_page;
+                entry.access = access;
+                entry.found_by = self.readme_query.clone();
+                entry.arxiv_source = arxiv_source.map(|path| self.relative_path(&path));
+                let pdf = pdf_path.clone();
+                match tokio::task::spawn_blocking(move || hash_file(&pdf)).await? {
+                    Ok((sha256, _)) => entry.sha256 = Some(sha256),
+                    Err(e) => tracing::warn!("Could not hash '{}': {}", paper.title, e),
+                }
+                self.append_pending(&PendingRecord::Entry(entry))?;
+            }
+            Err(_) => {
+                for attempt in attempts {
+                    let record = PendingRecord::Failure { id: paper_id.clone(), title: paper.title.clone(), attempt };
+                    // Don't let a journal problem mask the download error
+                    if let Err(e) = self.append_pending(&record) {
+                        tracing::warn!("Failed to record download attempt for '{}': {}", paper.title, e);
+                    }
+                }
+            }
+        }
+        if self.flush_per_download {
+            self.flush_pending()?;
+        }
+
+        let (pdf_path, _) = result?;
+        Ok(pdf_path.parent().map(|p| p.to_path_buf()).unwrap_or(pdf_path))
+    }
+
+    // Returns the saved PDF path and whether its title looked wrong
+    async fn fetch_paper(&self, paper: &PaperMetadata, paper_id: &str, source: &PdfSource, cancel: &CancellationToken, attempt: &mut AttemptRecord) -> Result<(PathBuf, bool)> {
+        let target_dir = self.base_dir.join(paper_id);
+        let pdf_path = target_dir.join("paper.pdf");
+        match source {
+            PdfSource::Url(pdf_url) => self.fetch_url(pdf_url, &pdf_path, cancel, attempt).await?,
+            PdfSource::File(mirror_path) => {
+                tracing::info!("Copying PDF from local mirror: {:?}", mirror_path);
+                create_dir_all(&target_dir).await?;
+                attempt.bytes = Some(fs::copy(mirror_path, &pdf_path).await?);
+            }
+        }
+
+        // Repository mix-ups occasionally serve a different paper under the right URL
+        let title_mismatch = self.verify_title(paper, &pdf_path).await?;
+        if title_mismatch && self.strict_verify {
+            let quarantine_dir = self.base_dir.join("quarantine");
+            create_dir_all(&quarantine_dir).await?;
+            let quarantined = quarantine_dir.join(paper_id);
+            if quarantined.exists() {
+                fs::remove_dir_all(&quarantined).await?;
+            }
+            fs::rename(&target_dir, &quarantined).await?;
+            attempt.outcome = AttemptOutcome::Quarantined;
+            attempt.error_kind = Some(AttemptError::TitleMismatch);
+            return Err(anyhow!("PDF title does not match metadata; quarantined at {:?}", quarantined));
+        }
+
+        // Save Metadata
+        let metadata_path = target_dir.join("metadata.json");
+        tracing::info!("Saving metadata to: {:?}", metadata_path);
+        let metadata_json = serde_json::to_string_pretty(&MetadataFile {
+            schema_version: migrations::METADATA_VERSION,
+            paper,
+        })?;
+        let mut meta_file = File::create(&metadata_path).await?;
+        meta_file.write_all(metadata_json.as_bytes()).await?;
+
+        Ok((pdf_path, title_mismatch))
+    }
+
+    async fn fetch_url(&self, pdf_url: &str, pdf_path: &Path, cancel: &CancellationToken, attempt: &mut AttemptRecord) -> Result<()> {
         // Download PDF
         tracing::info!("Downloading PDF from: {}", pdf_url);
-        let mut response = self.client.get(pdf_url).send().await?;
-        
+        let mut response = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err(Cancelled.into()),
+            resp = self.client.get(pdf_url).send() => resp?,
+        };
+        attempt.http_status = Some(response.status().as_u16());
+
         if !response.status().is_success() {
             let err = format!("Failed to download PDF: {}", response.status());
             tracing::error!("{}", err);
             return Err(anyhow!(err));
         }
 
+        if is_html(&response) {
+            let url = response.url().to_string();
+            let mut html = Vec::new();
+            while html.len() < MAX_LANDING_PAGE_BYTES {
+                let chunk = tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => return Err(Cancelled.into()),
+                    chunk = response.chunk() => chunk?,
+                };
+                match chunk {
+                    Some(chunk) => html.extend_from_slice(&chunk),
+                    None => break,
+                }
+            }
+            return Err(NotAPdf { url, html: String::from_utf8_lossy(&html).into_owned() }.into());
+        }
+
         // Only create directory if request was successful
-        let target_dir = self.base_dir.join(&paper_id);
-        create_dir_all(&target_dir).await?;
-        
-        let pdf_path = target_dir.join("paper.pdf");
-        let mut file = File::create(&pdf_path).await?;
-        while let Some(chunk) = response.chunk().await? {
-            file.write_all(&chunk).await?;
+        if let Some(target_dir) = pdf_path.parent() {
+            create_dir_all(target_dir).await?;
         }
 
-        // Save Metadata
-        let metadata_path = target_dir.join("metadata.json");
-        tracing::info!("Saving metadata to: {:?}", metadata_path);
-        let metadata_json = serde_json::to_string_pretty(paper)?;
-        let mut meta_file = File::create(&metadata_path).await?;
-        meta_file.write_all(metadata_json.as_bytes()).await?;
+        let mut file = File::create(pdf_path).await?;
+        let mut bytes = 0u64;
+        loop {
+            let chunk = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    drop(file);
+                    Self::remove_partial(pdf_path).await;
+                    return Err(Cancelled.into());
+                }
+                chunk = response.chunk() => chunk,
+            };
+            // A dropped connection or a stalled body leaves nothing behind, like a cancel
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    drop(file);
+                    Self::remove_partial(pdf_path).await;
+                    return Err(e.into());
+                }
+            };
+            match chunk {
+                Some(chunk) => {
+                    file.write_all(&chunk).await?;
+                    bytes += chunk.len() as u64;
+                    attempt.bytes = Some(bytes);
+                }
+                None => break,
+            }
+        }
+        file.flush().await?;
+        attempt.bytes = Some(bytes);
+        Ok(())
+    }
+
+    /// Fetches the paper's arXiv e-print into its directory: `source.tar.gz` for a multi-file
+    /// submission, `source.gz` for a single file. Returns the saved path, or None when the paper
+    /// has no arXiv ID. Nothing is written unless the response is a gzipped e-print.
+    pub async fn download_arxiv_source(&self, paper: &PaperMetadata, cancel: &CancellationToken) -> Result<Option<PathBuf>> {
+        let Some(id) = paper.arxiv_id.as_deref().map(arxiv_id::normalize).filter(|id| !id.is_empty()) else {
+            return Ok(None);
+        };
+        let url = format!("{}/{}", self.eprint_base_url, id);
+        let fetch = async {
+            self.arxiv_limiter.until_ready().await;
+            tracing::info!("Downloading arXiv source from: {}", url);
+            let response = self.client.get(&url).send().await?;
+            if !response.status().is_success() {
+                return Err(anyhow!("Failed to download arXiv source: {}", response.status()));
+            }
+            let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            Ok((content_type, response.bytes().await?))
+        };
+        let (content_type, bytes) = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err(Cancelled.into()),
+            fetched = fetch => fetched?,
+        };
+        let file_name = eprint_file_name(&content_type, &bytes)?;
+
+        let dir = self.base_dir.join(paper_id(paper));
+        create_dir_all(&dir).await?;
+        let path = dir.join(file_name);
+        fs::write(&path, &bytes).await?;
+        Ok(Some(path))
+    }
+
+    // Removes a half-written PDF, and its directory if nothing else lives there
+    async fn remove_partial(pdf_path: &std::path::Path) {
+        if let Err(e) = fs::remove_file(pdf_path).await {
+            tracing::warn!("Failed to remove partial download {:?}: {}", pdf_path, e);
+        }
+        if let Some(dir) = pdf_path.parent() {
+            let _ = fs::remove_dir(dir).await;
+        }
+    }
+
+    // Returns true on a confident mismatch; unreadable PDFs are given the benefit of the doubt
+    async fn verify_title(&self, paper: &PaperMetadata, pdf_path: &std::path::Path) -> Result<bool> {
+        let bytes = fs::read(pdf_path).await?;
+        let expected = paper.title.clone();
+        let check = tokio::task::spawn_blocking(move || check_pdf_title(&bytes, &expected)).await?;
+
+        match check {
+            TitleCheck::Match { score } => {
+                tracing::debug!("PDF title verified for '{}' (similarity {:.2})", paper.title, score);
+                Ok(false)
+            }
+            TitleCheck::Mismatch { extracted, score } => {
+                tracing::warn!("PDF title mismatch for '{}': first page reads '{}' (similarity {:.2})", paper.title, extracted, score);
+                self.diagnostics.warn(WarningKind::TitleMismatch, Source::Download, paper.title.clone());
+                Ok(true)
+            }
+            TitleCheck::Unknown => {
+                tracing::debug!("Could not extract a title from the PDF for '{}'", paper.title);
+                Ok(false)
+            }
+        }
+    }
 
-        // Update Manifest
-        self.update_manifest(paper, &paper_id, &pdf_path).await?;
+    pub fn base_dir(&self) -> &std::path::Path {
+        &self.base_dir
+    }
 
-        Ok(target_dir)
+    pub async fn read_manifest(&self) -> Result<Vec<ManifestEntry>> {
+        Ok(self.load_manifest()?.entries)
     }
 
-    async fn update_manifest(&self, paper: &PaperMetadata, id: &str, pdf_path: &std::path::Path) -> Result<()> {
+    // Sync so the drop guard can flush; the manifest is small
+    fn load_manifest(&self) -> Result<ManifestFile> {
         let manifest_path = self.base_dir.join("manifest.json");
-        let mut entries: Vec<ManifestEntry> = if manifest_path.exists() {
-            let mut file = File::open(&manifest_path).await?;
-            let mut content = String::new();
-            file.read_to_string(&mut content).await?;
-            serde_json::from_str(&content).unwrap_or_else(|_| Vec::new())
-        } else {
-            Vec::new()
-        };
+        if !manifest_path.exists() {
+            return Ok(ManifestFile { schema_version: migrations::MANIFEST_VERSION, ..Default::default() });
+        }
+        let content = std::fs::read_to_string(&manifest_path)?;
+        let value = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse {:?}: {}", manifest_path, e))?;
+        Ok(serde_json::from_value(migrations::migrate(DocumentKind::Manifest, value)?)?)
+    }
 
+    // Where the PDF is fetched from: the paper's own URL, or the proxied one for a closed paper
+    fn pdf_url(&self, paper: &PaperMetadata, access: AccessBasis) -> Option<String> {
+        match (access, &self.proxy) {
+            (AccessBasis::InstitutionalEntitlement, Some(proxy)) => proxy.rewrite(paper),
+            (AccessBasis::InstitutionalEntitlement, None) => None,
+            (AccessBasis::OpenAccess, _) => paper.pdf_url.clone(),
+            (AccessBasis::Green, _) => LegalityChecker::open_copy(paper),
+        }
+    }
+
+    fn manifest_entry(&self, paper: &PaperMetadata, id: &str, pdf_path: &Path, title_mismatch: bool) -> ManifestEntry {
         let first_author = paper.authors.first().map(|s| s.as_str()).unwrap_or("Unknown").to_string();
-        let relative_path = pdf_path.strip_prefix(&self.base_dir)
-            .unwrap_or(pdf_path)
-            .to_string_lossy()
-            .into_owned();
 
-        let new_entry = ManifestEntry {
+        ManifestEntry {
             title: paper.title.clone(),
             first_author,
             year: paper.year,
             id: id.to_string(),
-            relative_path,
+            relative_path: self.relative_path(pdf_path),
             downloaded_at: Utc::now().to_rfc3339(),
-        };
+            version_hint: paper.version_hint,
+            title_mismatch,
+            ..Default::default()
+        }
+    }
 
-        // Remove existing entry with same ID if exists (update)
-        entries.retain(|e| e.id != id);
-        entries.push(new_entry);
+    fn relative_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.base_dir).unwrap_or(path).to_string_lossy().into_owned()
+    }
 
-        let json = serde_json::to_string_pretty(&entries)?;
-        let mut file = File::create(&manifest_path).await?;
-        file.write_all(json.as_bytes()).await?;
-        tracing::info!("Updated manifest at: {:?}", manifest_path);
+    fn append_pending(&self, entry: &PendingRecord) -> Result<()> {
+        let mut journal = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.base_dir.join(PENDING_JOURNAL))?;
+        writeln!(journal, "{}", serde_json::to_string(entry)?)?;
+        journal.sync_data()?;
+        Ok(())
+    }
 
+    /// Folds any journaled entries into manifest.json and removes the journal.
+    /// Also recovers entries left behind by a run that crashed mid-batch.
+    /// Returns how many entries were folded in.
+    pub fn flush_pending(&self) -> Result<usize> {
+        let journal_path = self.base_dir.join(PENDING_JOURNAL);
+        if !journal_path.exists() {
+            return Ok(0);
+        }
+
+        let mut pending = Vec::new();
+        for line in std::io::BufReader::new(std::fs::File::open(&journal_path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<PendingRecord>(&line) {
+                Ok(record) => pending.push(record),
+                // A crash mid-append leaves a torn last line
+                Err(e) => tracing::warn!("Skipping unreadable pending manifest entry: {}", e),
+            }
+        }
+
+        let count = pending.len();
+        let mut manifest = self.load_manifest()?;
+        for record in pending {
+            Self::apply_pending(&mut manifest, record);
+        }
+
+        self.write_manifest(&mut manifest)?;
+        std::fs::remove_file(&journal_path)?;
+        tracing::info!("Updated manifest in {:?} with {} entries", self.base_dir, count);
+
+        Ok(count)
+    }
+
+    // Write beside the manifest and rename over it so a crash never leaves it half-written
+    fn write_manifest(&self, manifest: &mut ManifestFile) -> Result<()> {
+        let manifest_path = self.base_dir.join("manifest.json");
+        let tmp_path = self.base_dir.join("manifest.json.tmp");
+        manifest.schema_version = migrations::MANIFEST_VERSION;
+        let json = serde_json::to_string_pretty(&manifest)?;
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, &manifest_path)?;
         Ok(())
     }
 
+    /// The manifest entry with this ID.
+    pub fn find_entry(&self, id: &str) -> Result<ManifestEntry> {
+        self.load_manifest()?.entries.into_iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| anyhow!("No paper with ID '{}' in {:?}", id, self.base_dir.join("manifest.json")))
+    }
+
+    /// The directory holding the entry's files, or None if it is already gone. Errors if the
+    /// manifest points anywhere but a subdirectory of the download dir, so a hand-edited
+    /// relative_path can never make `remove` delete something outside the library.
+    pub fn paper_dir(&self, entry: &ManifestEntry) -> Result<Option<PathBuf>> {
+        let relative = Path::new(&entry.relative_path);
+        let escapes = relative.components().any(|c| !matches!(c, std::path::Component::Normal(_)));
+        let dir = relative.parent().filter(|p| !p.as_os_str().is_empty());
+        let (Some(dir), false) = (dir, escapes) else {
+            return Err(anyhow!("Refusing to remove '{}': its path {:?} is not inside a paper directory", entry.id, entry.relative_path));
+        };
+        let dir = self.base_dir.join(dir);
+        if !dir.exists() {
+            return Ok(None);
+        }
+        // Symlinks could still point elsewhere
+        let base = self.base_dir.canonicalize()?;
+        let resolved = dir.canonicalize()?;
+        if !resolved.starts_with(&base) || resolved == base {
+            return Err(anyhow!("Refusing to remove '{}': {:?} resolves outside {:?}", entry.id, dir, base));
+        }
+        Ok(Some(resolved))
+    }
+
+    /// Archives the entry and its metadata.json to `archive/removed.jsonl`, drops it from the
+    /// manifest, then deletes the paper directory. Each step leaves a state the next run copes with.
+    pub fn remove_paper(&self, id: &str, reason: Option<String>) -> Result<RemovedRecord> {
+        let mut manifest = self.load_manifest()?;
+        let entry = manifest.entries.iter().find(|e| e.id == id).cloned()
+            .ok_or_else(|| anyhow!("No paper with ID '{}' in {:?}", id, self.base_dir.join("manifest.json")))?;
+        let dir = self.paper_dir(&entry)?;
+
+        let metadata = self.read_metadata(&entry).unwrap_or_else(|e| {
+            tracing::warn!("Archiving '{}' without its unreadable metadata.json: {}", id, e);
+            None
+        });
+        let record = RemovedRecord { removed_at: Utc::now().to_rfc3339(), reason, entry, metadata };
+
+        let archive_path = self.base_dir.join(REMOVED_ARCHIVE);
+        if let Some(parent) = archive_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut archive = std::fs::OpenOptions::new().create(true).append(true).open(&archive_path)?;
+        writeln!(archive, "{}", serde_json::to_string(&record)?)?;
+        archive.sync_data()?;
+
+        manifest.entries.retain(|e| e.id != id);
+        self.write_manifest(&mut manifest)?;
+        if let Some(dir) = dir {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        tracing::info!("Removed '{}' from the library", id);
+        Ok(record)
+    }
+
+    /// The entry's metadata.json, upgraded to the current schema; None if it is gone.
+    pub fn read_metadata(&self, entry: &ManifestEntry) -> Result<Option<PaperMetadata>> {
+        let path = self.base_dir.join(&entry.relative_path).with_file_name("metadata.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let value = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| anyhow!("Failed to parse {:?}: {}", path, e))?;
+        let file: MetadataFile<PaperMetadata> = serde_json::from_value(migrations::migrate(DocumentKind::Metadata, value)?)?;
+        Ok(Some(file.paper))
+    }
+
+    /// Repairs what `verify` found without downloading anything: records the hash of checked
+    /// entries that had none, then rewrites the README.md of each fixed paper and of any whose
+    /// metadata.json changed after its README was written. Papers without a README don't get one.
+    pub fn fix(&self, checks: &[Check]) -> Result<FixSummary> {
+        let mut manifest = self.load_manifest()?;
+        let mut fixed = std::collections::HashSet::new();
+        for check in checks {
+            if let Verdict::Unhashed { actual } = &check.verdict {
+                if let Some(entry) = manifest.entries.iter_mut().find(|e| e.id == check.id) {
+                    entry.sha256 = Some(actual.clone());
+                    fixed.insert(entry.id.clone());
+                }
+            }
+        }
+        if !fixed.is_empty() {
+            self.write_manifest(&mut manifest)?;
+        }
+
+        let mut summary = FixSummary { hashes_recorded: fixed.len(), ..Default::default() };
+        for entry in manifest.entries.iter().filter(|e| checks.iter().any(|c| c.id == e.id)) {
+            let Some(dir) = self.base_dir.join(&entry.relative_path).parent().map(Path::to_path_buf) else { continue };
+            if !dir.join(readme::README_FILE).exists() || !(fixed.contains(&entry.id) || readme::is_stale(&dir)) {
+                continue;
+            }
+            let source = entry.attempts.last().map(|a| a.url.as_str()).unwrap_or_default();
+            let written = self.read_metadata(entry)
+                .and_then(|paper| paper.ok_or_else(|| anyhow!("metadata.json is missing")))
+                .and_then(|paper| readme::write(&dir, &paper, entry.found_by.as_deref().unwrap_or_default(), source, entry.mirror.is_some(), entry.access));
+            match written {
+                Ok(()) => summary.readmes_rewritten += 1,
+                Err(e) => tracing::warn!("Could not rewrite the README of '{}': {}", entry.title, e),
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Everything removed so far, oldest first.
+    pub fn read_removed(&self) -> Result<Vec<RemovedRecord>> {
+        let path = self.base_dir.join(REMOVED_ARCHIVE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut removed = Vec::new();
+        for line in std::io::BufReader::new(std::fs::File::open(&path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => removed.push(record),
+                Err(e) => tracing::warn!("Skipping unreadable line in {:?}: {}", path, e),
+            }
+        }
+        Ok(removed)
+    }
+
+    fn apply_pending(manifest: &mut ManifestFile, record: PendingRecord) {
+        match record {
+            PendingRecord::Entry(mut entry) => {
+                // Carry over earlier tries, whether they belonged to a previous download or only failed
+                let mut attempts = Vec::new();
+                if let Some(pos) = manifest.failures.iter().position(|f| f.id == entry.id) {
+                    attempts = manifest.failures.remove(pos).attempts;
+                }
+                if let Some(existing) = manifest.entries.iter().find(|e| e.id == entry.id) {
+                    attempts.extend(existing.attempts.iter().cloned());
+                }
+                push_attempts(&mut attempts, entry.attempts);
+                entry.attempts = attempts;
+
+                // Remove existing entry with same ID if exists (update)
+                manifest.entries.retain(|e| e.id != entry.id);
+                manifest.entries.push(entry);
+            }
+            PendingRecord::Failure { id, title, attempt } => {
+                if let Some(existing) = manifest.entries.iter_mut().find(|e| e.id == id) {
+                    push_attempts(&mut existing.attempts, [attempt]);
+                } else if let Some(failed) = manifest.failures.iter_mut().find(|f| f.id == id) {
+                    push_attempts(&mut failed.attempts, [attempt]);
+                } else {
+                    manifest.failures.push(FailedPaper { id, title, attempts: vec![attempt] });
+                }
+            }
+        }
+    }
+
     pub async fn save_unavailable(&self, query: &crate::layers::DiscoveryQuery, papers: Vec<PaperMetadata>) -> Result<()> {
         if papers.is_empty() {
             return Ok(());
@@ -128,7 +945,10 @@ impl Downloader {
         let path = self.base_dir.join("unavailable.json");
         let mut root: serde_json::Value = if path.exists() {
             let content = fs::read_to_string(&path).await?;
-            serde_json::from_str(&content).unwrap_or(serde_json::Value::Object(serde_json::Map::new()))
+            let value = serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Failed to parse {:?}: {}", path, e))?;
+            let file: UnavailableFile = serde_json::from_value(migrations::migrate(DocumentKind::Unavailable, value)?)?;
+            file.searches
         } else {
             serde_json::Value::Object(serde_json::Map::new())
         };
@@ -187,7 +1007,10 @@ impl Downloader {
             }
         }
 
-        let json = serde_json::to_string_pretty(&root)?;
+        let json = serde_json::to_string_pretty(&UnavailableFile {
+            schema_version: migrations::UNAVAILABLE_VERSION,
+            searches: root,
+        })?;
         let mut file = File::create(&path).await?;
         file.write_all(json.as_bytes()).await?;
         tracing::info!("Saved {} unavailable papers to unavailable.json", papers.len());
@@ -195,3 +1018,627 @@ impl Downloader {
         Ok(())
     }
 }
+
+// arXiv gzips a multi-file submission as a tarball (served as application/x-eprint-tar) and a
+// lone .tex file on its own (application/x-eprint). A PDF-only submission has no source and
+// serves the PDF instead.
+fn eprint_file_name(content_type: &str, bytes: &[u8]) -> Result<&'static str> {
+    if bytes.starts_with(b"%PDF") {
+        return Err(anyhow!("arXiv has no source for this paper, only the PDF"));
+    }
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return Err(anyhow!("arXiv source is not gzipped (Content-Type '{}')", content_type));
+    }
+    Ok(if content_type.contains("tar") { "source.tar.gz" } else { "source.gz" })
+}
+
+/// The library directory (and manifest ID) for a paper. DOIs and arXiv IDs are normalized
+/// first, so the name doesn't depend on which source's spelling of them won the merge.
+fn paper_id(paper: &PaperMetadata) -> String {
+    let raw_id = paper.doi.as_deref().map(normalize_doi).filter(|d| !d.is_empty())
+        .or_else(|| paper.arxiv_id.as_deref().map(arxiv_id::normalize))
+        .or_else(|| paper.semantic_scholar_id.clone())
+        .or_else(|| paper.core_id.clone())
+        .or_else(|| paper.europe_pmc_id.clone())
+        .or_else(|| paper.dblp_key.clone())
+        .unwrap_or_else(|| "unknown_id".to_string());
+
+    // Sanitize ID: remove scheme, replace non-alphanumeric chars
+    raw_id.replace("http://", "")
+          .replace("https://", "")
+          .replace(|c: char| !c.is_alphanumeric() && c != '.' && c != '-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, MockResponse, MockServer};
+    use std::time::Duration;
+
+    fn oa_paper(pdf_url: String) -> PaperMetadata {
+        PaperMetadata {
+            title: "Slow Paper".to_string(),
+            doi: Some("10.1234/slow".to_string()),
+            pdf_url: Some(pdf_url),
+            is_oa: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_download_cleans_up() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, vec![b'x'; 64 * 1024]).slow(Duration::from_millis(20)),
+        ]).await;
+        let base = temp_dir("download-cancel");
+        let downloader = Downloader::new(&base);
+        let paper = oa_paper(format!("{}/slow.pdf", server.url));
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            trigger.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let err = downloader.download_paper(&paper, &cancel).await.unwrap_err();
+        assert!(crate::layers::is_cancelled(&err), "unexpected error: {}", err);
+        // The full body would take over a second; cancellation must not wait for it
+        assert!(started.elapsed() < Duration::from_millis(1000));
+        assert!(!base.join("10.1234_slow").exists());
+        assert!(!base.join("manifest.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_download_times_out_and_cleans_up() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, vec![b'x'; 4 * 1024]).slow(Duration::from_secs(2)),
+        ]).await;
+        let base = temp_dir("download-stalled");
+        let downloader = Downloader::new(&base).with_read_timeout(Duration::from_millis(100));
+        let paper = oa_paper(format!("{}/slow.pdf", server.url));
+
+        let started = std::time::Instant::now();
+        let err = downloader.download_paper(&paper, &CancellationToken::new()).await.unwrap_err();
+        assert!(!crate::layers::is_cancelled(&err), "unexpected error: {}", err);
+        // Gives up on the first pause, long before the body would finish
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(!base.join("10.1234_slow").exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_many_stops_when_cancelled() {
+        let base = temp_dir("download-many");
+        let downloader = Downloader::new(&base);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let err = downloader.download_many(&[oa_paper("http://127.0.0.1:9/x.pdf".to_string())], &cancel).await.unwrap_err();
+        assert!(crate::layers::is_cancelled(&err));
+    }
+
+    fn entry(id: &str) -> ManifestEntry {
+        ManifestEntry {
+            title: format!("Paper {}", id),
+            first_author: "Unknown".to_string(),
+            id: id.to_string(),
+            relative_path: format!("{}/paper.pdf", id),
+            downloaded_at: "2025-01-01T00:00:00+00:00".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flush_recovers_journal_with_torn_last_line() {
+        let base = temp_dir("download-recover");
+        let downloader = Downloader::new(&base);
+        downloader.append_pending(&PendingRecord::Entry(entry("a"))).unwrap();
+        downloader.flush_pending().unwrap();
+
+        // A previous run journaled an update to "a" and a new "b", then died mid-write
+        downloader.append_pending(&PendingRecord::Entry(ManifestEntry { title: "Updated".to_string(), ..entry("a") })).unwrap();
+        downloader.append_pending(&PendingRecord::Entry(entry("b"))).unwrap();
+        let mut journal = std::fs::OpenOptions::new().append(true).open(base.join(PENDING_JOURNAL)).unwrap();
+        write!(journal, "{{\"title\": \"Torn").unwrap();
+
+        assert_eq!(downloader.flush_pending().unwrap(), 2);
+        assert!(!base.join(PENDING_JOURNAL).exists());
+        let entries = downloader.read_manifest().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries.iter().find(|e| e.id == "a").unwrap().title, "Updated");
+        assert_eq!(downloader.flush_pending().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_download_many_writes_manifest_once_at_end() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "first"),
+            MockResponse::new(200, "second"),
+        ]).await;
+        let base = temp_dir("download-batch");
+        let downloader = Downloader::new(&base);
+        let cancel = CancellationToken::new();
+        let papers = [
+            PaperMetadata { doi: Some("10.1/one".to_string()), ..oa_paper(format!("{}/1.pdf", server.url)) },
+            PaperMetadata { doi: Some("10.1/two".to_string()), ..oa_paper(format!("{}/2.pdf", server.url)) },
+        ];
+
+        // Mid-batch the entry only exists in the journal
+        downloader.download_paper(&papers[0], &cancel).await.unwrap();
+        assert!(!base.join("manifest.json").exists());
+        assert!(base.join(PENDING_JOURNAL).exists());
+
+        downloader.download_many(&papers[1..], &cancel).await.unwrap();
+        assert!(!base.join(PENDING_JOURNAL).exists());
+        assert_eq!(downloader.read_manifest().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_flush_per_download_updates_manifest_immediately() {
+        let server = MockServer::start(vec![MockResponse::new(200, "pdf")]).await;
+        let base = temp_dir("download-per-download");
+        let downloader = Downloader::new(&base).with_flush_per_download(true);
+
+        downloader.download_paper(&oa_paper(format!("{}/x.pdf", server.url)), &CancellationToken::new()).await.unwrap();
+        assert!(!base.join(PENDING_JOURNAL).exists());
+        assert_eq!(downloader.read_manifest().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_skips_are_recorded_in_diagnostics() {
+        let diagnostics = Arc::new(Diagnostics::default());
+        let downloader = Downloader::new(temp_dir("download-diagnostics")).with_diagnostics(diagnostics.clone());
+        let closed = PaperMetadata { is_oa: false, ..oa_paper("http://127.0.0.1:9/x.pdf".to_string()) };
+        let no_pdf = PaperMetadata { pdf_url: None, ..oa_paper(String::new()) };
+
+        downloader.download_many(&[closed.clone(), closed, no_pdf], &CancellationToken::new()).await.unwrap();
+        let summary = diagnostics.summary();
+        assert_eq!(summary.len(), 2);
+        assert_eq!((summary[0].kind, summary[0].count), (WarningKind::NotOpenAccess, 2));
+        assert_eq!((summary[1].kind, summary[1].count), (WarningKind::NoPdfUrl, 1));
+    }
+
+    #[tokio::test]
+    async fn test_mirror_directory_short_circuits_download() {
+        let mirror_dir = temp_dir("download-mirror-source");
+        std::fs::write(mirror_dir.join("10.1234_slow.pdf"), b"mirrored pdf").unwrap();
+        let base = temp_dir("download-mirror-dir");
+        let downloader = Downloader::new(&base)
+            .with_flush_per_download(true)
+            .with_mirror(Some(LocalMirror::Directory(mirror_dir.clone())));
+
+        // The external URL is unreachable; only the mirror can satisfy this
+        let dir = downloader.download_paper(&oa_paper("http://127.0.0.1:9/x.pdf".to_string()), &CancellationToken::new()).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("paper.pdf")).unwrap(), b"mirrored pdf");
+        let entry = &downloader.read_manifest().await.unwrap()[0];
+        assert_eq!(entry.mirror.as_deref(), Some(mirror_dir.join("10.1234_slow.pdf").to_str().unwrap()));
+        assert_eq!(entry.attempts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mirror_url_template_hit() {
+        let server = MockServer::start(vec![MockResponse::new(200, "from mirror")]).await;
+        let base = temp_dir("download-mirror-url-hit");
+        let template = format!("{}/pdf/{{doi}}", server.url);
+        let downloader = Downloader::new(&base)
+            .with_flush_per_download(true)
+            .with_mirror(Some(LocalMirror::UrlTemplate(template)));
+
+        let dir = downloader.download_paper(&oa_paper("http://127.0.0.1:9/x.pdf".to_string()), &CancellationToken::new()).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("paper.pdf")).unwrap(), b"from mirror");
+        let entry = &downloader.read_manifest().await.unwrap()[0];
+        assert_eq!(entry.mirror, Some(format!("{}/pdf/10.1234/slow", server.url)));
+        assert_eq!(entry.sha256, Some(hash_file(&dir.join("paper.pdf")).unwrap().0));
+    }
+
+    #[tokio::test]
+    async fn test_closed_paper_through_proxy() {
+        let server = MockServer::start(vec![MockResponse::new(200, "entitled pdf")]).await;
+        let base = temp_dir("download-proxy");
+        let prefix = format!("{}/login?url=", server.url);
+        let proxy = InstitutionalProxy::new(&prefix, Some("ezproxy=s3ss10n")).unwrap();
+        let downloader = Downloader::new(&base).with_flush_per_download(true).with_readme(Some("title \"Closed\"".to_string())).with_proxy(Some(proxy));
+        let closed = PaperMetadata { title: "Closed".to_string(), doi: Some("10.1234/closed".to_string()), ..Default::default() };
+
+        let dir = downloader.download_paper(&closed, &CancellationToken::new()).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("paper.pdf")).unwrap(), b"entitled pdf");
+        assert_eq!(server.requests(), vec!["/login?url=https://doi.org/10.1234/closed"]);
+        assert!(server.request_heads()[0].to_lowercase().contains("cookie: ezproxy=s3ss10n"));
+
+        let entry = &downloader.read_manifest().await.unwrap()[0];
+        assert_eq!(entry.access, AccessBasis::InstitutionalEntitlement);
+        assert_eq!(entry.attempts[0].url, format!("{}https://doi.org/10.1234/closed", prefix));
+        let readme = std::fs::read_to_string(dir.join(crate::layers::readme::README_FILE)).unwrap();
+        assert!(readme.contains("institutional proxy"));
+    }
+
+    #[test]
+    fn test_closed_paper_uses_its_open_copy_not_the_publisher() {
+        let downloader = Downloader::new(temp_dir("download-green-url"));
+        let closed = PaperMetadata {
+            pdf_url: Some("https://dl.acm.org/doi/pdf/10.1145/3292500.3330701".to_string()),
+            arxiv_id: Some("1905.12345".to_string()),
+            ..Default::default()
+        };
+        let access = LegalityChecker::access_basis(&closed, false).unwrap();
+        assert_eq!(access, AccessBasis::Green);
+        assert_eq!(downloader.pdf_url(&closed, access).as_deref(), Some("https://arxiv.org/pdf/1905.12345"));
+    }
+
+    #[tokio::test]
+    async fn test_mirror_still_goes_before_the_open_copy() {
+        let server = MockServer::start(vec![MockResponse::new(200, "from mirror")]).await;
+        let base = temp_dir("download-green-mirror");
+        let downloader = Downloader::new(&base)
+            .with_flush_per_download(true)
+            .with_mirror(Some(LocalMirror::UrlTemplate(format!("{}/arxiv/{{arxiv_id}}.pdf", server.url))));
+        let closed = PaperMetadata { title: "Closed".to_string(), arxiv_id: Some("1905.12345".to_string()), ..Default::default() };
+
+        let dir = downloader.download_paper(&closed, &CancellationToken::new()).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("paper.pdf")).unwrap(), b"from mirror");
+        assert_eq!(server.requests(), vec!["/arxiv/1905.12345.pdf"]);
+        let entry = &downloader.read_manifest().await.unwrap()[0];
+        assert_eq!(entry.access, AccessBasis::Green);
+        assert_eq!(entry.attempts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_leaves_open_access_papers_alone() {
+        let server = MockServer::start(vec![MockResponse::new(200, "open pdf")]).await;
+        let base = temp_dir("download-proxy-oa");
+        let proxy = InstitutionalProxy::new("http://localhost:9/login?url=", Some("ezproxy=s3ss10n")).unwrap();
+        let downloader = Downloader::new(&base).with_flush_per_download(true).with_proxy(Some(proxy));
+
+        downloader.download_paper(&oa_paper(format!("{}/open.pdf", server.url)), &CancellationToken::new()).await.unwrap();
+        assert_eq!(server.requests(), vec!["/open.pdf"]);
+        // The session cookie is for the proxy's host only (cookies ignore ports, hence localhost)
+        assert!(!server.request_heads()[0].to_lowercase().contains("ezproxy"));
+        assert_eq!(downloader.read_manifest().await.unwrap()[0].access, AccessBasis::OpenAccess);
+    }
+
+    #[tokio::test]
+    async fn test_mirror_miss_falls_back_to_external_url() {
+        let server = MockServer::start(vec![
+            MockResponse::new(404, "not mirrored"),
+            MockResponse::new(200, "from publisher"),
+        ]).await;
+        let base = temp_dir("download-mirror-url-miss");
+        let downloader = Downloader::new(&base)
+            .with_flush_per_download(true)
+            .with_mirror(Some(LocalMirror::UrlTemplate(format!("{}/pdf/{{doi}}", server.url))));
+        let paper = oa_paper(format!("{}/external.pdf", server.url));
+
+        let dir = downloader.download_paper(&paper, &CancellationToken::new()).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("paper.pdf")).unwrap(), b"from publisher");
+        let entry = &downloader.read_manifest().await.unwrap()[0];
+        assert_eq!(entry.mirror, None);
+        let outcomes: Vec<_> = entry.attempts.iter().map(|a| (a.outcome, a.http_status)).collect();
+        assert_eq!(outcomes, vec![(AttemptOutcome::Failed, Some(404)), (AttemptOutcome::Success, Some(200))]);
+    }
+
+    #[tokio::test]
+    async fn test_landing_page_pdf_link_is_followed_once() {
+        let landing = r#"<html><head><meta name="citation_pdf_url" content="/real.pdf"></head></html>"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(200, landing).with_header("Content-Type", "text/html; charset=utf-8"),
+            MockResponse::new(200, "%PDF-1.4 real"),
+        ]).await;
+        let base = temp_dir("download-landing-page");
+        let downloader = Downloader::new(&base).with_flush_per_download(true);
+        let paper = oa_paper(format!("{}/doi/10.1234/slow", server.url));
+
+        let dir = downloader.download_paper(&paper, &CancellationToken::new()).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("paper.pdf")).unwrap(), b"%PDF-1.4 real");
+        let entry = &downloader.read_manifest().await.unwrap()[0];
+        assert_eq!(entry.landing_page, Some(format!("{}/doi/10.1234/slow", server.url)));
+        let kinds: Vec<_> = entry.attempts.iter().map(|a| (a.outcome, a.error_kind)).collect();
+        assert_eq!(kinds, vec![(AttemptOutcome::Failed, Some(AttemptError::NotAPdf)), (AttemptOutcome::Success, None)]);
+        assert_eq!(server.requests(), vec!["/doi/10.1234/slow", "/real.pdf"]);
+    }
+
+    #[tokio::test]
+    async fn test_landing_page_without_link_fails() {
+        let html = || MockResponse::new(200, "<html><a href='/more.html'>More</a></html>").with_header("Content-Type", "text/html");
+        let server = MockServer::start(vec![html(), html()]).await;
+        let base = temp_dir("download-landing-page-no-link");
+        let downloader = Downloader::new(&base).with_flush_per_download(true);
+        let paper = oa_paper(format!("{}/doi/10.1234/slow", server.url));
+
+        let err = downloader.download_paper(&paper, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.to_string().contains("HTML page"));
+        assert_eq!(server.requests().len(), 1);
+        assert!(!base.join("doi_10.1234_slow").exists());
+    }
+
+    const GZIP: &[u8] = &[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03];
+
+    #[tokio::test]
+    async fn test_arxiv_source_tarball_single_file_and_none() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, GZIP).with_header("Content-Type", "application/x-eprint-tar"),
+            MockResponse::new(200, GZIP).with_header("Content-Type", "application/x-eprint"),
+            MockResponse::new(200, "%PDF-1.5 only").with_header("Content-Type", "application/pdf"),
+            MockResponse::new(404, "not found"),
+        ]).await;
+        let base = temp_dir("download-arxiv-source");
+        let downloader = Downloader::new(&base).with_eprint_base_url(&server.url);
+        let paper = |id: &str| PaperMetadata { title: "T".to_string(), arxiv_id: Some(id.to_string()), ..Default::default() };
+        let cancel = CancellationToken::new();
+
+        let tarball = downloader.download_arxiv_source(&paper("1706.03762v5"), &cancel).await.unwrap().unwrap();
+        assert_eq!(tarball, base.join("1706.03762/source.tar.gz"));
+        assert_eq!(std::fs::read(&tarball).unwrap(), GZIP);
+        let single = downloader.download_arxiv_source(&paper("math/0309136"), &cancel).await.unwrap().unwrap();
+        assert_eq!(single, base.join("math_0309136/source.gz"));
+
+        let pdf_only = downloader.download_arxiv_source(&paper("2101.00001"), &cancel).await.unwrap_err();
+        assert!(pdf_only.to_string().contains("only the PDF"), "{}", pdf_only);
+        assert!(downloader.download_arxiv_source(&paper("2101.00002"), &cancel).await.unwrap_err().to_string().contains("404"));
+        assert!(!base.join("2101.00001").exists() && !base.join("2101.00002").exists());
+        assert_eq!(server.requests(), ["/1706.03762", "/math/0309136", "/2101.00001", "/2101.00002"]);
+
+        assert_eq!(downloader.download_arxiv_source(&PaperMetadata::default(), &cancel).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_source_waits_for_the_shared_limiter_and_cancels() {
+        use governor::{Quota, RateLimiter};
+        let server = MockServer::start(vec![MockResponse::new(200, GZIP).with_header("Content-Type", "application/x-eprint-tar")]).await;
+        // The search has just used the only request this limiter allows
+        let limiter = Arc::new(RateLimiter::direct(Quota::per_hour(nonzero_ext::nonzero!(1u32))));
+        limiter.check().unwrap();
+        let base = temp_dir("download-arxiv-source-limiter");
+        let downloader = Downloader::new(&base).with_eprint_base_url(&server.url).with_arxiv_limiter(limiter);
+        let paper = PaperMetadata { title: "T".to_string(), arxiv_id: Some("1706.03762".to_string()), ..Default::default() };
+
+        let cancel = CancellationToken::new();
+        let waiting = tokio::time::timeout(Duration::from_millis(200), downloader.download_arxiv_source(&paper, &cancel)).await;
+        assert!(waiting.is_err(), "the e-print request didn't wait for the limiter");
+        assert!(server.requests().is_empty());
+
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            trigger.cancel();
+        });
+        let err = downloader.download_arxiv_source(&paper, &cancel).await.unwrap_err();
+        assert!(crate::layers::is_cancelled(&err), "unexpected error: {}", err);
+        assert!(server.requests().is_empty() && !base.join("1706.03762").exists());
+    }
+
+    #[tokio::test]
+    async fn test_arxiv_source_is_recorded_and_never_fails_the_download() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "%PDF-1.4 a"),
+            MockResponse::new(200, GZIP).with_header("Content-Type", "application/x-eprint-tar"),
+            MockResponse::new(200, "%PDF-1.4 b"),
+            MockResponse::new(404, "not found"),
+        ]).await;
+        let base = temp_dir("download-arxiv-source-manifest");
+        let downloader = Downloader::new(&base).with_source(true).with_flush_per_download(true)
+            .with_eprint_base_url(&format!("{}/e-print", server.url));
+        let paper = |id: &str| PaperMetadata {
+            title: "Slow Paper".to_string(),
+            arxiv_id: Some(id.to_string()),
+            pdf_url: Some(format!("{}/pdf/{}", server.url, id)),
+            is_oa: true,
+            ..Default::default()
+        };
+        let cancel = CancellationToken::new();
+
+        downloader.download_paper(&paper("1706.03762"), &cancel).await.unwrap();
+        let dir = downloader.download_paper(&paper("1810.04805"), &cancel).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("paper.pdf")).unwrap(), b"%PDF-1.4 b");
+
+        let entries = downloader.read_manifest().await.unwrap();
+        assert_eq!(entries[0].arxiv_source.as_deref(), Some("1706.03762/source.tar.gz"));
+        assert!(base.join("1706.03762/source.tar.gz").exists());
+        assert_eq!(entries[1].arxiv_source, None);
+        assert_eq!(server.requests(), ["/pdf/1706.03762", "/e-print/1706.03762", "/pdf/1810.04805", "/e-print/1810.04805"]);
+    }
+
+    #[tokio::test]
+    async fn test_readme_is_written_when_enabled() {
+        let server = MockServer::start(vec![MockResponse::new(200, "pdf"), MockResponse::new(200, "pdf")]).await;
+        let paper = oa_paper(format!("{}/x.pdf", server.url));
+        let cancel = CancellationToken::new();
+
+        let plain = Downloader::new(temp_dir("download-no-readme"));
+        let dir = plain.download_paper(&paper, &cancel).await.unwrap();
+        assert!(!dir.join(readme::README_FILE).exists());
+
+        let with_readme = Downloader::new(temp_dir("download-readme")).with_readme(Some("title \"Slow Paper\"".to_string()));
+        let dir = with_readme.download_paper(&paper, &cancel).await.unwrap();
+        let note = std::fs::read_to_string(dir.join(readme::README_FILE)).unwrap();
+        assert!(note.starts_with("# Slow Paper\n"));
+        assert!(note.contains("- **Found by:** title \"Slow Paper\""));
+        assert!(note.contains(&format!("`{}/x.pdf` (external download)", server.url)));
+        assert!(note.contains("| metadata.json |"));
+        assert!(note.contains("| paper.pdf | 3 |"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempt_is_persisted() {
+        let server = MockServer::start(vec![MockResponse::new(503, "busy")]).await;
+        let base = temp_dir("download-failed-attempt");
+        let downloader = Downloader::new(&base);
+        let paper = oa_paper(format!("{}/busy.pdf", server.url));
+
+        assert!(downloader.download_many(std::slice::from_ref(&paper), &CancellationToken::new()).await.is_ok());
+        let manifest = downloader.load_manifest().unwrap();
+        assert!(manifest.entries.is_empty());
+        assert_eq!(manifest.failures.len(), 1);
+        let attempt = &manifest.failures[0].attempts[0];
+        assert_eq!(attempt.outcome, AttemptOutcome::Failed);
+        assert_eq!(attempt.http_status, Some(503));
+        assert_eq!(attempt.error_kind, Some(AttemptError::HttpStatus));
+        assert_eq!(attempt.url, paper.pdf_url.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_success_after_failures_keeps_history() {
+        let server = MockServer::start(vec![
+            MockResponse::new(500, "oops"),
+            MockResponse::new(200, "pdf bytes"),
+        ]).await;
+        let base = temp_dir("download-retry-history");
+        let downloader = Downloader::new(&base).with_flush_per_download(true);
+        let paper = oa_paper(format!("{}/flaky.pdf", server.url));
+        let cancel = CancellationToken::new();
+
+        assert!(downloader.download_paper(&paper, &cancel).await.is_err());
+        downloader.download_paper(&paper, &cancel).await.unwrap();
+
+        let manifest = downloader.load_manifest().unwrap();
+        assert!(manifest.failures.is_empty());
+        let outcomes: Vec<_> = manifest.entries[0].attempts.iter().map(|a| a.outcome).collect();
+        assert_eq!(outcomes, vec![AttemptOutcome::Failed, AttemptOutcome::Success]);
+        assert_eq!(manifest.entries[0].attempts[1].bytes, Some(9));
+    }
+
+    #[test]
+    fn test_paper_id_is_the_same_whichever_source_named_it() {
+        let from_arxiv = PaperMetadata { arxiv_id: Some("http://arxiv.org/abs/2301.01234v2".to_string()), ..Default::default() };
+        let from_ss = PaperMetadata { arxiv_id: Some("2301.01234".to_string()), semantic_scholar_id: Some("abc".to_string()), ..Default::default() };
+        assert_eq!(paper_id(&from_arxiv), "2301.01234");
+        assert_eq!(paper_id(&from_ss), "2301.01234");
+        let old_style = PaperMetadata { arxiv_id: Some("math.GT/0309136v1".to_string()), ..Default::default() };
+        assert_eq!(paper_id(&old_style), "math_0309136");
+
+        let openalex = PaperMetadata { doi: Some("https://doi.org/10.1234/ABC.5".to_string()), ..from_arxiv };
+        let crossref = PaperMetadata { doi: Some("10.1234/abc.5".to_string()), ..Default::default() };
+        assert_eq!(paper_id(&openalex), "10.1234_abc.5");
+        assert_eq!(paper_id(&crossref), "10.1234_abc.5");
+        assert_eq!(paper_id(&PaperMetadata::default()), "unknown_id");
+    }
+
+    #[test]
+    fn test_attempt_history_is_trimmed() {
+        let mut manifest = ManifestFile::default();
+        for i in 0..(MAX_ATTEMPTS + 3) {
+            let attempt = AttemptRecord { url: format!("https://example.org/{}", i), ..AttemptRecord::new("") };
+            Downloader::apply_pending(&mut manifest, PendingRecord::Failure { id: "a".to_string(), title: "A".to_string(), attempt });
+        }
+        let attempts = &manifest.failures[0].attempts;
+        assert_eq!(attempts.len(), MAX_ATTEMPTS);
+        assert_eq!(attempts[0].url, "https://example.org/3");
+        assert_eq!(attempts.last().unwrap().url, format!("https://example.org/{}", MAX_ATTEMPTS + 2));
+    }
+
+    // A library with one downloaded paper, written the way download_paper leaves it
+    fn library_with(base: &Path, entry: ManifestEntry) -> Downloader {
+        let dir = base.join(Path::new(&entry.relative_path).parent().unwrap());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("paper.pdf"), b"%PDF").unwrap();
+        let paper = PaperMetadata { title: entry.title.clone(), ..Default::default() };
+        let metadata = MetadataFile { schema_version: migrations::METADATA_VERSION, paper: &paper };
+        std::fs::write(dir.join("metadata.json"), serde_json::to_string(&metadata).unwrap()).unwrap();
+        let downloader = Downloader::new(base);
+        downloader.append_pending(&PendingRecord::Entry(entry)).unwrap();
+        downloader.flush_pending().unwrap();
+        downloader
+    }
+
+    #[test]
+    fn test_fix_records_hashes_and_rewrites_stale_readmes() {
+        use crate::layers::integrity::{verify, Progress};
+        let base = temp_dir("download-fix");
+        let query = "title \"Paper a\"";
+        let downloader = library_with(&base, ManifestEntry {
+            attempts: vec![AttemptRecord::new("https://example.org/a.pdf")],
+            found_by: Some(query.to_string()),
+            ..entry("a")
+        });
+        library_with(&base, entry("b"));
+
+        // A README written before the paper's metadata was corrected
+        let dir = base.join("a");
+        let old = PaperMetadata { title: "Paper a".to_string(), ..Default::default() };
+        readme::write(&dir, &old, query, "https://example.org/a.pdf", false, AccessBasis::OpenAccess).unwrap();
+        let an_hour_ago = std::time::SystemTime::now() - Duration::from_secs(3600);
+        std::fs::File::options().write(true).open(dir.join(readme::README_FILE)).unwrap().set_modified(an_hour_ago).unwrap();
+        let revised = PaperMetadata { title: "Paper a, revised".to_string(), ..Default::default() };
+        let metadata = MetadataFile { schema_version: migrations::METADATA_VERSION, paper: &revised };
+        std::fs::write(dir.join("metadata.json"), serde_json::to_string(&metadata).unwrap()).unwrap();
+
+        let entries = downloader.load_manifest().unwrap().entries;
+        let checks = verify(&base, &entries, 1, &Progress::new(entries.len())).unwrap();
+        assert_eq!(downloader.fix(&checks).unwrap(), FixSummary { hashes_recorded: 2, readmes_rewritten: 1 });
+        assert!(downloader.load_manifest().unwrap().entries.iter().all(|e| e.sha256.is_some()));
+        let rewritten = std::fs::read_to_string(dir.join(readme::README_FILE)).unwrap();
+        assert!(rewritten.starts_with("# Paper a, revised\n"), "{}", rewritten);
+        assert!(rewritten.contains("- **Found by:** title \"Paper a\"\n"));
+        assert!(rewritten.contains("`https://example.org/a.pdf`"));
+        // A paper that never had a README doesn't get one
+        assert!(!base.join("b").join(readme::README_FILE).exists());
+
+        // Nothing is left to fix on a second run
+        let entries = downloader.load_manifest().unwrap().entries;
+        let checks = verify(&base, &entries, 1, &Progress::new(entries.len())).unwrap();
+        assert_eq!(downloader.fix(&checks).unwrap(), FixSummary::default());
+    }
+
+    #[test]
+    fn test_remove_archives_entry_and_metadata() {
+        let base = temp_dir("download-remove");
+        let downloader = library_with(&base, entry("a"));
+
+        let record = downloader.remove_paper("a", Some("duplicate".to_string())).unwrap();
+        assert_eq!(record.entry.id, "a");
+        assert!(!base.join("a").exists());
+        assert!(downloader.load_manifest().unwrap().entries.is_empty());
+
+        let archived = downloader.read_removed().unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].reason.as_deref(), Some("duplicate"));
+        assert_eq!(archived[0].metadata.as_ref().unwrap().title, "Paper a");
+        assert!(chrono::DateTime::parse_from_rfc3339(&archived[0].removed_at).is_ok());
+    }
+
+    #[test]
+    fn test_remove_unknown_id_changes_nothing() {
+        let base = temp_dir("download-remove-unknown");
+        let downloader = library_with(&base, entry("a"));
+        assert!(downloader.remove_paper("b", None).is_err());
+        assert!(base.join("a/paper.pdf").exists());
+        assert!(!base.join(REMOVED_ARCHIVE).exists());
+    }
+
+    #[test]
+    fn test_remove_refuses_paths_outside_the_library() {
+        let base = temp_dir("download-remove-escape");
+        let outside = temp_dir("download-remove-escape-victim");
+        std::fs::write(outside.join("keep.txt"), b"keep").unwrap();
+        let victim = outside.file_name().unwrap().to_string_lossy().into_owned();
+
+        for relative_path in [format!("../{}/paper.pdf", victim), "paper.pdf".to_string(), format!("{}/paper.pdf", outside.display())] {
+            let downloader = Downloader::new(&base);
+            let mut manifest = downloader.load_manifest().unwrap();
+            manifest.entries = vec![ManifestEntry { relative_path: relative_path.clone(), ..entry("x") }];
+            downloader.write_manifest(&mut manifest).unwrap();
+
+            let err = downloader.remove_paper("x", None).unwrap_err();
+            assert!(err.to_string().contains("Refusing"), "{}: {}", relative_path, err);
+            assert!(outside.join("keep.txt").exists());
+            assert_eq!(downloader.load_manifest().unwrap().entries.len(), 1);
+        }
+        assert!(!base.join(REMOVED_ARCHIVE).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_refuses_symlinked_directory() {
+        let base = temp_dir("download-remove-symlink");
+        let outside = temp_dir("download-remove-symlink-victim");
+        std::fs::write(outside.join("paper.pdf"), b"%PDF").unwrap();
+        std::os::unix::fs::symlink(&outside, base.join("x")).unwrap();
+        let downloader = Downloader::new(&base);
+        let mut manifest = downloader.load_manifest().unwrap();
+        manifest.entries = vec![entry("x")];
+        downloader.write_manifest(&mut manifest).unwrap();
+
+        assert!(downloader.remove_paper("x", None).is_err());
+        assert!(outside.join("paper.pdf").exists());
+    }
+}
@@ -0,0 +1,86 @@
+// Response bodies are meant to be UTF-8, but some repositories and Crossref records carry
+// Latin-1 or Windows-1252 bytes labelled as UTF-8. Invalid bytes are transcoded from
+// Windows-1252 (a superset of Latin-1's printable range) so one bad byte neither breaks the
+// parse nor turns into a replacement character.
+use anyhow::Result;
+
+// Windows-1252 0x80..=0x9F; the five unassigned bytes stay unknown
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{FFFD}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{FFFD}', 'Ž', '\u{FFFD}',
+    '\u{FFFD}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{FFFD}', 'ž', 'Ÿ',
+];
+
+fn cp1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => CP1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+/// `bytes` as text: valid UTF-8 is kept as is, and each byte of an invalid sequence is read as
+/// Windows-1252. Returns the text and how many bytes had to be transcoded.
+pub fn decode(bytes: &[u8]) -> (String, usize) {
+    let mut out = String::with_capacity(bytes.len());
+    let mut transcoded = 0;
+    let mut rest = bytes;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                out.push_str(valid);
+                return (out, transcoded);
+            }
+            Err(e) => {
+                let (valid, invalid) = rest.split_at(e.valid_up_to());
+                out.push_str(std::str::from_utf8(valid).expect("checked by from_utf8"));
+                // A sequence cut off by the end of the body counts as invalid too
+                let bad = e.error_len().unwrap_or(invalid.len());
+                out.extend(invalid[..bad].iter().map(|b| cp1252(*b)));
+                transcoded += bad;
+                rest = &invalid[bad..];
+            }
+        }
+    }
+}
+
+/// The body of `resp`, decoded with `decode`; logs when anything had to be transcoded.
+pub async fn response_text(resp: reqwest::Response, source: &str) -> Result<String> {
+    let url = resp.url().to_string();
+    let bytes = resp.bytes().await?;
+    let (text, transcoded) = decode(&bytes);
+    if transcoded > 0 {
+        tracing::warn!("{} response from {} was not valid UTF-8; transcoded {} bytes as Windows-1252", source, url, transcoded);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/tests/fixtures/encoding/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn test_valid_utf8_is_untouched() {
+        assert_eq!(decode("Über Maßtheorie – “quoted”".as_bytes()), ("Über Maßtheorie – “quoted”".to_string(), 0));
+    }
+
+    #[test]
+    fn test_mixed_encodings() {
+        // "Café" in UTF-8 next to "Müller" in Latin-1 and “smart quotes” in Windows-1252
+        let bytes = fixture("mixed.json");
+        assert!(std::str::from_utf8(&bytes).is_err());
+        let (text, transcoded) = decode(&bytes);
+        assert_eq!(transcoded, 3);
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["title"], "Café at Müller's: “Notes”");
+        assert_eq!(value["venue"], "Société Mathématique");
+    }
+
+    #[test]
+    fn test_truncated_sequence_and_unassigned_bytes() {
+        assert_eq!(decode(b"abc\xC3"), ("abcÃ".to_string(), 1));
+        assert_eq!(decode(b"a\x81b"), ("a\u{FFFD}b".to_string(), 1));
+    }
+}
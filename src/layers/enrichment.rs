@@ -0,0 +1,458 @@
+use anyhow::{Result, anyhow};
+use reqwest::Client;
+use serde::Deserialize;
+use futures_util::StreamExt;
+use governor::{Quota, RateLimiter};
+use governor::clock::DefaultClock;
+use governor::state::{InMemoryState, direct::NotKeyed};
+use nonzero_ext::nonzero;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use crate::layers::{Cancelled, PaperMetadata};
+use crate::layers::{http, year};
+use crate::layers::diagnostics::{Diagnostics, Source, WarningKind};
+use url::Url;
+
+const CROSSREF_BASE_URL: &str = "https://api.crossref.org";
+
+// Crossref Data Structures
+#[derive(Deserialize)]
+struct CrossrefResponse {
+    message: CrossrefMessage,
+}
+
+#[derive(Deserialize)]
+struct CrossrefMessage {
+    volume: Option<String>,
+    issue: Option<String>,
+    page: Option<String>,
+    publisher: Option<String>,
+    #[serde(rename = "container-title", default)]
+    container_title: Vec<String>,
+    // Print and online dates often fall in different years
+    #[serde(rename = "published-print")]
+    published_print: Option<CrossrefDate>,
+    #[serde(rename = "published-online")]
+    published_online: Option<CrossrefDate>,
+    issued: Option<CrossrefDate>,
+}
+
+#[derive(Deserialize)]
+struct CrossrefDate {
+    // [[year, month, day]], any part of which may be null
+    #[serde(rename = "date-parts", default)]
+    date_parts: Vec<Vec<Option<i64>>>,
+}
+
+impl CrossrefDate {
+    fn year(&self) -> Option<i64> {
+        self.date_parts.first().and_then(|parts| parts.first().copied().flatten())
+    }
+}
+
+/// Bibliographic fields Crossref knows about that the discovery sources don't provide.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrossrefWork {
+    pub volume: Option<String>,
+    pub issue: Option<String>,
+    pub pages: Option<String>,
+    pub publisher: Option<String>,
+    pub container_title: Option<String>,
+    // The earliest plausible of the print, online and issued years
+    pub year: Option<u32>,
+}
+
+impl CrossrefWork {
+    pub fn parse(json: &str) -> Result<Self> {
+        let resp: CrossrefResponse = serde_json::from_str(json)?;
+        let msg = resp.message;
+        Ok(Self {
+            volume: non_empty(msg.volume),
+            issue: non_empty(msg.issue),
+            pages: msg.page.and_then(|p| normalize_pages(&p)),
+            publisher: non_empty(msg.publisher),
+            // Crossref returns a list here, and an empty one for preprints
+            container_title: non_empty(msg.container_title.into_iter().next()),
+            year: year::earliest([msg.published_print, msg.published_online, msg.issued].iter().flatten().filter_map(CrossrefDate::year)),
+        })
+    }
+
+    /// Fills fields the paper is missing; never overwrites what a source already gave us,
+    /// except to move the year back by one when Crossref dates the paper a year earlier.
+    pub fn apply_to(&self, paper: &mut PaperMetadata) {
+        paper.year = year::reconcile(paper.year, self.year);
+        if paper.volume.is_none() { paper.volume = self.volume.clone(); }
+        if paper.issue.is_none() { paper.issue = self.issue.clone(); }
+        if paper.pages.is_none() { paper.pages = self.pages.clone(); }
+        if paper.publisher.is_none() { paper.publisher = self.publisher.clone(); }
+        if paper.container_title.is_none() { paper.container_title = self.container_title.clone(); }
+        if paper.venue.is_none() { paper.venue = self.container_title.clone(); }
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Normalizes Crossref page strings ("770–778", "436-444", "e1002", "12-") to a plain hyphenated range.
+pub fn normalize_pages(raw: &str) -> Option<String> {
+    let folded = raw.replace(['\u{2013}', '\u{2014}'], "-");
+    let parts: Vec<&str> = folded.split('-')
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect();
+    match parts.as_slice() {
+        [] => None,
+        [single] => Some(single.to_string()),
+        [start, end, ..] => Some(format!("{}-{}", start, end)),
+    }
+}
+
+/// Strips resolver prefixes so "https://doi.org/10.1/X" and "10.1/x" compare equal.
+pub fn normalize_doi(doi: &str) -> String {
+    let lower = doi.trim().to_lowercase();
+    let stripped = lower.trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("dx.doi.org/")
+        .trim_start_matches("doi.org/")
+        .trim_start_matches("doi:");
+    stripped.to_string()
+}
+
+/// A service that fills in paper fields by DOI. Each provider spaces out its own requests;
+/// the queue decides which lookups run and how many are in flight.
+pub trait Provider {
+    type Found;
+    const SOURCE: Source;
+
+    /// The normalized DOI to look up for the paper, or None when the provider has nothing to add.
+    fn wants(&self, paper: &PaperMetadata) -> Option<String>;
+
+    /// What the provider knows about the DOI; None when it knows nothing.
+    async fn fetch(&self, doi: &str) -> Result<Option<Self::Found>>;
+
+    fn apply(&self, found: &Self::Found, paper: &mut PaperMetadata);
+}
+
+// Lookups in flight at once across every provider; each provider's limiter still spaces its own
+pub const ENRICHMENT_CONCURRENCY: usize = 8;
+
+/// Runs the lookups of every enrichment provider under one concurrency limit.
+pub struct EnrichmentQueue {
+    permits: Semaphore,
+    diagnostics: Arc<Diagnostics>,
+}
+
+impl EnrichmentQueue {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            permits: Semaphore::new(concurrency),
+            diagnostics: Arc::default(),
+        }
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Arc<Diagnostics>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Looks each distinct DOI up once and applies the answer to every paper that wanted it.
+    /// A failed lookup is reported and leaves its papers as they were.
+    pub async fn run<P: Provider>(&self, provider: &P, papers: &mut [PaperMetadata], cancel: &CancellationToken) -> Result<()> {
+        // The papers wanting each DOI, in the order the DOIs first appear
+        let mut wanted: Vec<(String, Vec<usize>)> = Vec::new();
+        let mut slots = HashMap::new();
+        for (i, paper) in papers.iter().enumerate() {
+            if let Some(doi) = provider.wants(paper) {
+                let slot = *slots.entry(doi.clone()).or_insert_with(|| {
+                    wanted.push((doi, Vec::new()));
+                    wanted.len() - 1
+                });
+                wanted[slot].1.push(i);
+            }
+        }
+
+        let lookups = futures_util::stream::iter(&wanted)
+            .map(|(doi, _)| async move {
+                let _permit = self.permits.acquire().await.expect("the queue never closes its semaphore");
+                provider.fetch(doi).await
+            })
+            .buffered(wanted.len().max(1))
+            .collect::<Vec<_>>();
+        let found = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err(Cancelled.into()),
+            found = lookups => found,
+        };
+
+        for ((doi, indices), found) in wanted.into_iter().zip(found) {
+            match found {
+                Ok(Some(found)) => {
+                    for i in indices {
+                        provider.apply(&found, &mut papers[i]);
+                    }
+                }
+                Ok(None) => tracing::debug!("{} has nothing for {}", P::SOURCE, doi),
+                Err(e) => {
+                    tracing::warn!("{} lookup failed for {}: {}", P::SOURCE, doi, e);
+                    self.diagnostics.warn(WarningKind::EnrichmentFailed, P::SOURCE, doi);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct CrossrefEnricher {
+    client: Client,
+    email: Option<String>,
+    limiter: RateLimiter<NotKeyed, InMemoryState, DefaultClock>,
+}
+
+impl CrossrefEnricher {
+    pub fn new(email: Option<String>) -> Self {
+        Self {
+            client: http::api_client(),
+            email,
+            // Crossref asks anonymous and polite-pool clients alike to keep to a few requests a second
+            limiter: RateLimiter::direct(Quota::per_second(nonzero!(5u32))),
+        }
+    }
+
+    // The DOI is one path segment, its slash and any '<', '>' or '#' encoded, as SICI-style
+    // DOIs like 10.1002/(SICI)1097-4571(199806)49:8<693::AID-ASI4>3.0.CO;2-0 need
+    fn url(&self, doi: &str) -> String {
+        let mut url = Url::parse(CROSSREF_BASE_URL).expect("the Crossref base URL is valid");
+        url.path_segments_mut().expect("an http URL has a path").push("works").push(doi);
+        if let Some(email) = &self.email {
+            url.query_pairs_mut().append_pair("mailto", email);
+        }
+        url.into()
+    }
+
+    async fn lookup(&self, doi: &str) -> Result<CrossrefWork> {
+        self.limiter.until_ready().await;
+        let url = self.url(doi);
+
+        tracing::info!("Querying Crossref: {}", url);
+        let resp = self.client.get(&url).send().await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Crossref API error: {}", resp.status()));
+        }
+        let text = crate::layers::encoding::response_text(resp, "Crossref").await?;
+        CrossrefWork::parse(&text)
+    }
+}
+
+impl Provider for CrossrefEnricher {
+    type Found = CrossrefWork;
+    const SOURCE: Source = Source::Crossref;
+
+    fn wants(&self, paper: &PaperMetadata) -> Option<String> {
+        paper.doi.as_deref().map(normalize_doi)
+    }
+
+    async fn fetch(&self, doi: &str) -> Result<Option<CrossrefWork>> {
+        self.lookup(doi).await.map(Some)
+    }
+
+    fn apply(&self, work: &CrossrefWork, paper: &mut PaperMetadata) {
+        work.apply_to(paper);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/crossref/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_parse_journal_article() {
+        let work = CrossrefWork::parse(&fixture("journal_article.json")).unwrap();
+        assert_eq!(work.volume.as_deref(), Some("521"));
+        assert_eq!(work.issue.as_deref(), Some("7553"));
+        assert_eq!(work.pages.as_deref(), Some("436-444"));
+        assert_eq!(work.publisher.as_deref(), Some("Springer Science and Business Media LLC"));
+        assert_eq!(work.container_title.as_deref(), Some("Nature"));
+        assert_eq!(work.year, Some(2015));
+    }
+
+    #[test]
+    fn test_parse_proceedings_missing_issue_and_en_dash_pages() {
+        let work = CrossrefWork::parse(&fixture("proceedings_article.json")).unwrap();
+        assert_eq!(work.volume, None);
+        assert_eq!(work.issue, None);
+        assert_eq!(work.pages.as_deref(), Some("770-778"));
+        assert_eq!(work.container_title.as_deref(), Some("2016 IEEE Conference on Computer Vision and Pattern Recognition (CVPR)"));
+    }
+
+    #[test]
+    fn test_parse_posted_content_empty_container() {
+        let work = CrossrefWork::parse(&fixture("posted_content.json")).unwrap();
+        assert_eq!(work.container_title, None);
+        assert_eq!(work.pages, None);
+        assert_eq!(work.publisher.as_deref(), Some("Cold Spring Harbor Laboratory"));
+    }
+
+    #[test]
+    fn test_normalize_pages() {
+        assert_eq!(normalize_pages("1-12").as_deref(), Some("1-12"));
+        assert_eq!(normalize_pages("1 \u{2013} 12").as_deref(), Some("1-12"));
+        assert_eq!(normalize_pages("e1002").as_deref(), Some("e1002"));
+        assert_eq!(normalize_pages("12-").as_deref(), Some("12"));
+        assert_eq!(normalize_pages(""), None);
+    }
+
+    #[test]
+    fn test_normalize_doi() {
+        assert_eq!(normalize_doi("https://doi.org/10.1038/Nature14539"), "10.1038/nature14539");
+        assert_eq!(normalize_doi("http://dx.doi.org/10.1109/CVPR.2016.90"), "10.1109/cvpr.2016.90");
+        assert_eq!(normalize_doi("doi:10.1101/2020.04.22.055095"), "10.1101/2020.04.22.055095");
+    }
+
+    #[test]
+    fn test_url_encodes_the_doi_and_address() {
+        let enricher = CrossrefEnricher::new(Some("me+crossref@example.org".to_string()));
+        assert_eq!(enricher.url("10.1038/nature14539"), "https://api.crossref.org/works/10.1038%2Fnature14539?mailto=me%2Bcrossref%40example.org");
+        assert_eq!(
+            CrossrefEnricher::new(None).url("10.1002/(SICI)1097-4571(199806)49:8<693::AID-ASI4>3.0.CO;2-0"),
+            "https://api.crossref.org/works/10.1002%2F(SICI)1097-4571(199806)49:8%3C693::AID-ASI4%3E3.0.CO;2-0"
+        );
+        assert_eq!(CrossrefEnricher::new(None).url("10.1000/a#b?c"), "https://api.crossref.org/works/10.1000%2Fa%23b%3Fc");
+    }
+
+    #[test]
+    fn test_apply_keeps_existing_fields() {
+        let work = CrossrefWork::parse(&fixture("journal_article.json")).unwrap();
+        let mut paper = PaperMetadata {
+            title: "Deep learning".to_string(),
+            venue: Some("Nature (London)".to_string()),
+            pages: Some("436".to_string()),
+            ..Default::default()
+        };
+        work.apply_to(&mut paper);
+        assert_eq!(paper.venue.as_deref(), Some("Nature (London)"));
+        assert_eq!(paper.pages.as_deref(), Some("436"));
+        assert_eq!(paper.volume.as_deref(), Some("521"));
+        assert_eq!(paper.container_title.as_deref(), Some("Nature"));
+    }
+
+    #[test]
+    fn test_print_and_online_years_prefer_the_earlier() {
+        let json = r#"{"message": {
+            "published-print": {"date-parts": [[2016, 1]]},
+            "published-online": {"date-parts": [[2015, 11, 30]]},
+            "issued": {"date-parts": [[null]]}
+        }}"#;
+        let work = CrossrefWork::parse(json).unwrap();
+        assert_eq!(work.year, Some(2015));
+
+        let mut paper = PaperMetadata { year: Some(2016), ..Default::default() };
+        work.apply_to(&mut paper);
+        assert_eq!(paper.year, Some(2015));
+
+        // A year that is far off is a different claim, not print vs online
+        let mut mismatched = PaperMetadata { year: Some(2019), ..Default::default() };
+        work.apply_to(&mut mismatched);
+        assert_eq!(mismatched.year, Some(2019));
+    }
+
+    // Answers with the DOI after as many milliseconds as its last segment, and fails for "fail"
+    #[derive(Default)]
+    struct FakeProvider {
+        calls: Mutex<Vec<String>>,
+        in_flight: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl Provider for FakeProvider {
+        type Found = String;
+        const SOURCE: Source = Source::Crossref;
+
+        fn wants(&self, paper: &PaperMetadata) -> Option<String> {
+            paper.doi.as_deref().map(normalize_doi)
+        }
+
+        async fn fetch(&self, doi: &str) -> Result<Option<String>> {
+            self.calls.lock().unwrap().push(doi.to_string());
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            let millis = doi.rsplit('/').next().and_then(|d| d.parse().ok()).unwrap_or(1);
+            tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if doi.ends_with("fail") {
+                return Err(anyhow!("lookup failed"));
+            }
+            Ok(Some(doi.to_string()))
+        }
+
+        fn apply(&self, found: &String, paper: &mut PaperMetadata) {
+            paper.publisher = Some(found.clone());
+        }
+    }
+
+    fn with_doi(doi: &str) -> PaperMetadata {
+        PaperMetadata { doi: Some(doi.to_string()), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_queue_looks_each_doi_up_once() {
+        let provider = FakeProvider::default();
+        let mut papers = vec![with_doi("10.1/A/5"), with_doi("https://doi.org/10.1/a/5"), with_doi("10.1/b/1"), PaperMetadata::default()];
+        EnrichmentQueue::new(ENRICHMENT_CONCURRENCY).run(&provider, &mut papers, &CancellationToken::new()).await.unwrap();
+
+        let mut calls = provider.calls.lock().unwrap().clone();
+        calls.sort();
+        assert_eq!(calls, vec!["10.1/a/5", "10.1/b/1"]);
+        assert_eq!(papers[0].publisher.as_deref(), Some("10.1/a/5"));
+        assert_eq!(papers[1].publisher.as_deref(), Some("10.1/a/5"));
+        assert_eq!(papers[2].publisher.as_deref(), Some("10.1/b/1"));
+        assert_eq!(papers[3].publisher, None);
+    }
+
+    #[tokio::test]
+    async fn test_queue_limit_is_shared_by_providers() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let slow = FakeProvider { in_flight: in_flight.clone(), peak: peak.clone(), ..Default::default() };
+        let fast = FakeProvider { in_flight, peak: peak.clone(), ..Default::default() };
+        // Earlier DOIs answer later, so answers come back out of order
+        let mut slow_papers: Vec<_> = (0..6).map(|i| with_doi(&format!("10.2/{}/{}", i, 30 - i * 5))).collect();
+        let mut fast_papers: Vec<_> = (0..6).map(|i| with_doi(&format!("10.3/{}/{}", i, 6 - i))).collect();
+
+        let queue = EnrichmentQueue::new(2);
+        let cancel = CancellationToken::new();
+        let (a, b) = tokio::join!(queue.run(&slow, &mut slow_papers, &cancel), queue.run(&fast, &mut fast_papers, &cancel));
+        a.unwrap();
+        b.unwrap();
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+        for paper in slow_papers.iter().chain(&fast_papers) {
+            assert_eq!(paper.publisher, paper.doi);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_lookup_is_reported_and_leaves_the_paper() {
+        let provider = FakeProvider::default();
+        let diagnostics = Arc::new(Diagnostics::default());
+        let mut papers = vec![with_doi("10.1/fail"), with_doi("10.1/ok/1")];
+        EnrichmentQueue::new(ENRICHMENT_CONCURRENCY).with_diagnostics(diagnostics.clone())
+            .run(&provider, &mut papers, &CancellationToken::new()).await.unwrap();
+
+        assert_eq!(papers[0].publisher, None);
+        assert_eq!(papers[1].publisher.as_deref(), Some("10.1/ok/1"));
+        let summary = diagnostics.summary();
+        assert_eq!(summary.len(), 1);
+        assert_eq!((summary[0].kind, summary[0].source), (WarningKind::EnrichmentFailed, Source::Crossref));
+        assert_eq!(summary[0].examples, vec!["10.1/fail"]);
+    }
+}
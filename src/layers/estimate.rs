@@ -0,0 +1,194 @@
+// What a search will cost before it runs: requests per source, worked out from the query's
+// limits the same way each client pages, and the time the rate limiters will stretch them over.
+use std::time::Duration;
+use crate::layers::{DiscoveryQuery, DiscoverySource};
+use crate::layers::discovery::{DblpClient, EuropePmcClient, SemanticScholarClient, ARXIV_PAGE_SIZE, ARXIV_REQUEST_INTERVAL, OPENALEX_PAGE_SIZE, SS_BULK_BATCH_SIZE, SS_MAX_RESULTS, SS_PAGE_SIZE};
+
+// A search over either is worth a second look before it starts
+pub const CONFIRM_ABOVE_REQUESTS: usize = 20;
+pub const CONFIRM_ABOVE_DURATION: Duration = Duration::from_secs(30);
+
+// Rough round trip for one paged request, for sources without a rate limit
+const TYPICAL_RESPONSE_TIME: Duration = Duration::from_secs(1);
+
+/// The sources a run will query, besides the query itself.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchPlan {
+    pub ss_api_key: bool,
+    pub core: bool,
+    pub dblp: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceCost {
+    pub source: DiscoverySource,
+    pub requests: usize,
+    // Spacing the client's rate limiter enforces between requests
+    pub interval: Option<Duration>,
+}
+
+impl SourceCost {
+    // Requests are sequential within a source: the first goes at once, later ones wait for
+    // the limiter or the previous response, whichever is longer
+    fn duration(&self) -> Duration {
+        if self.requests == 0 {
+            return Duration::ZERO;
+        }
+        let spacing = self.interval.unwrap_or_default().max(TYPICAL_RESPONSE_TIME);
+        TYPICAL_RESPONSE_TIME + spacing * (self.requests as u32 - 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    pub sources: Vec<SourceCost>,
+}
+
+impl Estimate {
+    // Only the tests look up a single source
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn requests(&self, source: DiscoverySource) -> usize {
+        self.sources.iter().filter(|s| s.source == source).map(|s| s.requests).sum()
+    }
+
+    pub fn total_requests(&self) -> usize {
+        self.sources.iter().map(|s| s.requests).sum()
+    }
+
+    /// Sources are searched in parallel, so the slowest one sets the time.
+    pub fn duration(&self) -> Duration {
+        self.sources.iter().map(SourceCost::duration).max().unwrap_or_default()
+    }
+
+    pub fn needs_confirmation(&self) -> bool {
+        self.total_requests() > CONFIRM_ABOVE_REQUESTS || self.duration() > CONFIRM_ABOVE_DURATION
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!("This search will make about {} requests:\n", self.total_requests());
+        for cost in &self.sources {
+            let plural = if cost.requests == 1 { "" } else { "s" };
+            let pace = match cost.interval {
+                Some(interval) if cost.requests > 1 => format!(", 1 every {}s", interval.as_secs_f64()),
+                _ => String::new(),
+            };
+            out.push_str(&format!("  {:<17} {} request{}{}\n", name(cost.source), cost.requests, plural, pace));
+        }
+        out.push_str(&format!("Estimated time: about {}s. Unpaywall and Crossref lookups depend on the results and aren't counted.",
+            self.duration().as_secs()));
+        out
+    }
+}
+
+fn name(source: DiscoverySource) -> &'static str {
+    match source {
+        DiscoverySource::SemanticScholar => "Semantic Scholar",
+        DiscoverySource::Arxiv => "arXiv",
+        DiscoverySource::OpenAlex => "OpenAlex",
+        DiscoverySource::Core => "CORE",
+        DiscoverySource::Dblp => "DBLP",
+        DiscoverySource::EuropePmc => "Europe PMC",
+    }
+}
+
+/// The requests `query` will make of each source in `plan`, assuming every page comes back
+/// full; a search that runs out of results early makes fewer.
+pub fn estimate(query: &DiscoveryQuery, plan: SearchPlan) -> Estimate {
+    let pages = |results: usize, per_page: usize| results.div_ceil(per_page).max(1);
+
+    let ss_limit = query.limit_for(DiscoverySource::SemanticScholar);
+    // Titled searches use relevance search, sweeps use bulk search (see search_into)
+    let ss_requests = match query.title {
+        Some(_) => pages(ss_limit.min(SS_MAX_RESULTS), SS_PAGE_SIZE),
+        None => pages(ss_limit, SS_BULK_BATCH_SIZE),
+    } + usize::from(SemanticScholarClient::has_filters(query)); // a 400 retries once without the filters
+    // --university is first resolved to an institution ID
+    let institution_lookup = usize::from(query.university.is_some());
+
+    let mut sources = vec![
+        SourceCost {
+            source: DiscoverySource::SemanticScholar,
+            requests: ss_requests,
+            interval: Some(SemanticScholarClient::request_interval(plan.ss_api_key)),
+        },
+        SourceCost {
+            source: DiscoverySource::Arxiv,
+            requests: pages(query.limit_for(DiscoverySource::Arxiv), ARXIV_PAGE_SIZE),
+            interval: Some(ARXIV_REQUEST_INTERVAL),
+        },
+        SourceCost {
+            source: DiscoverySource::OpenAlex,
+            requests: institution_lookup + pages(query.limit_for(DiscoverySource::OpenAlex), OPENALEX_PAGE_SIZE),
+            interval: None,
+        },
+    ];
+    // Europe PMC and DBLP skip a search they have no terms for
+    if EuropePmcClient::search_terms(query).is_some() {
+        sources.push(SourceCost { source: DiscoverySource::EuropePmc, requests: 1, interval: None });
+    }
+    if plan.core {
+        sources.push(SourceCost { source: DiscoverySource::Core, requests: 1, interval: None });
+    }
+    if plan.dblp && DblpClient::search_terms(query).is_some() {
+        sources.push(SourceCost { source: DiscoverySource::Dblp, requests: 1, interval: None });
+    }
+    Estimate { sources }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(title: Option<&str>, limit: usize) -> DiscoveryQuery {
+        DiscoveryQuery { title: title.map(str::to_string), limit, ..Default::default() }
+    }
+
+    #[test]
+    fn test_default_search_needs_no_confirmation() {
+        let estimate = estimate(&query(Some("Attention Is All You Need"), 10), SearchPlan::default());
+        assert_eq!(estimate.total_requests(), 4);
+        assert_eq!(estimate.duration(), TYPICAL_RESPONSE_TIME);
+        assert!(!estimate.needs_confirmation());
+    }
+
+    #[test]
+    fn test_large_limits_page_through_each_source() {
+        let estimate = estimate(&query(Some("deep learning"), 2000), SearchPlan { ss_api_key: false, core: true, dblp: true });
+        // Relevance search stops at 1000 results
+        assert_eq!(estimate.requests(DiscoverySource::SemanticScholar), 10);
+        assert_eq!(estimate.requests(DiscoverySource::OpenAlex), 10);
+        assert_eq!(estimate.requests(DiscoverySource::Arxiv), 20);
+        assert_eq!(estimate.requests(DiscoverySource::Core), 1);
+        assert_eq!(estimate.requests(DiscoverySource::Dblp), 1);
+        assert_eq!(estimate.total_requests(), 43);
+        // Twenty arXiv pages three seconds apart
+        assert_eq!(estimate.duration(), Duration::from_secs(58));
+        assert!(estimate.needs_confirmation());
+
+        // With a key, Semantic Scholar's ten pages go a second apart; arXiv still sets the time
+        let keyed = super::estimate(&query(Some("deep learning"), 2000), SearchPlan { ss_api_key: true, ..Default::default() });
+        assert_eq!(keyed.sources[0].duration(), Duration::from_secs(10));
+        assert_eq!(keyed.duration(), Duration::from_secs(58));
+    }
+
+    #[test]
+    fn test_sweeps_use_bulk_batches_and_resolve_the_university() {
+        let sweep = DiscoveryQuery { university: Some("MIT".to_string()), ..query(None, 10_000) };
+        let estimate = estimate(&sweep, SearchPlan::default());
+        assert_eq!(estimate.requests(DiscoverySource::SemanticScholar), 10);
+        // OpenAlex stops at 2000 works, plus the institution lookup
+        assert_eq!(estimate.requests(DiscoverySource::OpenAlex), 11);
+        assert_eq!(estimate.requests(DiscoverySource::Core), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let estimate = estimate(&query(Some("x"), 1000), SearchPlan::default());
+        assert_eq!(estimate.render(), "This search will make about 26 requests:\n\
+            \x20 Semantic Scholar  10 requests, 1 every 3s\n\
+            \x20 arXiv             10 requests, 1 every 3s\n\
+            \x20 OpenAlex          5 requests\n\
+            \x20 Europe PMC        1 request\n\
+            Estimated time: about 28s. Unpaywall and Crossref lookups depend on the results and aren't counted.");
+    }
+}
@@ -0,0 +1,322 @@
+// Post-discovery candidate filters. These run on the mapped candidates, so source-side
+// abstract recovery (e.g. OpenAlex inverted-index reconstruction) has already happened.
+use std::fmt;
+use anyhow::{Result, anyhow};
+use crate::layers::PaperMetadata;
+
+#[derive(Debug, Default, Clone)]
+pub struct CandidateFilter {
+    pub require_abstract: bool,
+    pub min_abstract_words: Option<usize>,
+    // Matched against Semantic Scholar fields of study; the other sources take it in the query
+    pub category: Option<String>,
+}
+
+impl CandidateFilter {
+    pub fn is_active(&self) -> bool {
+        self.require_abstract || self.min_abstract_words.is_some() || self.category.is_some()
+    }
+
+    pub fn keeps(&self, paper: &PaperMetadata) -> bool {
+        let words = paper.abstract_text.as_deref().map(count_words).unwrap_or(0);
+        if self.require_abstract && words == 0 {
+            return false;
+        }
+        if let Some(min) = self.min_abstract_words {
+            if words < min {
+                return false;
+            }
+        }
+        if let Some(category) = &self.category {
+            if !matches_category(paper, category) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn apply(&self, candidates: Vec<PaperMetadata>) -> Vec<PaperMetadata> {
+        if !self.is_active() {
+            return candidates;
+        }
+        let before = candidates.len();
+        let kept: Vec<PaperMetadata> = candidates.into_iter()
+            .filter(|p| {
+                let keep = self.keeps(p);
+                if !keep {
+                    tracing::debug!("Filtered out '{}': failed abstract or category filter", p.title);
+                }
+                keep
+            })
+            .collect();
+        tracing::info!("Candidate filters dropped {} of {} candidates.", before - kept.len(), before);
+        kept
+    }
+}
+
+/// --min-year/--max-year, both inclusive, checked against every source's candidates. Papers
+/// without a year are kept, and flagged in the listing, unless --require-year is given.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct YearRange {
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+    pub require_year: bool,
+}
+
+impl YearRange {
+    pub fn new(min: Option<u32>, max: Option<u32>, require_year: bool) -> Result<Self> {
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                return Err(anyhow!("--min-year {} is after --max-year {}", min, max));
+            }
+        }
+        Ok(Self { min, max, require_year })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.min.is_some() || self.max.is_some() || self.require_year
+    }
+
+    pub fn keeps(&self, paper: &PaperMetadata) -> bool {
+        match paper.year {
+            Some(year) => self.min.is_none_or(|min| year >= min) && self.max.is_none_or(|max| year <= max),
+            None => !self.require_year,
+        }
+    }
+
+    /// Whether `paper` is kept only because its year is unknown.
+    pub fn is_unchecked(&self, paper: &PaperMetadata) -> bool {
+        paper.year.is_none() && (self.min.is_some() || self.max.is_some())
+    }
+
+    pub fn apply(&self, candidates: Vec<PaperMetadata>) -> Vec<PaperMetadata> {
+        if !self.is_active() {
+            return candidates;
+        }
+        let before = candidates.len();
+        let kept: Vec<PaperMetadata> = candidates.into_iter()
+            .filter(|p| {
+                let keep = self.keeps(p);
+                if !keep {
+                    tracing::debug!("Filtered out '{}': year {:?} not {}", p.title, p.year, self);
+                }
+                keep
+            })
+            .collect();
+        tracing::info!("Year filter dropped {} of {} candidates.", before - kept.len(), before);
+        kept
+    }
+}
+
+// "published 2015-2020", "published 2015 or later, with a known year", ...
+impl fmt::Display for YearRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "published in {}", min)?,
+            (Some(min), Some(max)) => write!(f, "published {}-{}", min, max)?,
+            (Some(min), None) => write!(f, "published {} or later", min)?,
+            (None, Some(max)) => write!(f, "published {} or earlier", max)?,
+            (None, None) => return if self.require_year { write!(f, "with a known publication year") } else { Ok(()) },
+        }
+        if self.require_year {
+            write!(f, ", with a known year")?;
+        }
+        Ok(())
+    }
+}
+
+// Closest Semantic Scholar field for an arXiv archive ("cs.LG" -> "computer science")
+fn arxiv_archive_field(category: &str) -> Option<&'static str> {
+    let archive = category.split('.').next()?.to_lowercase();
+    match archive.as_str() {
+        "cs" => Some("computer science"),
+        "math" | "stat" => Some("mathematics"),
+        "eess" => Some("engineering"),
+        "econ" | "q-fin" => Some("economics"),
+        "q-bio" => Some("biology"),
+        "astro-ph" | "cond-mat" | "gr-qc" | "math-ph" | "nlin" | "physics" | "quant-ph" => Some("physics"),
+        a if a.starts_with("hep-") || a.starts_with("nucl-") => Some("physics"),
+        _ => None,
+    }
+}
+
+/// Case-insensitive substring match of `category` against the paper's Semantic Scholar
+/// fields. Papers without any fields pass, since their source already handled the category.
+pub fn matches_category(paper: &PaperMetadata, category: &str) -> bool {
+    let fields: Vec<String> = paper.s2_fields().map(|f| f.to_lowercase()).collect();
+    if fields.is_empty() {
+        return true;
+    }
+    let wanted = category.trim().to_lowercase();
+    let archive = arxiv_archive_field(&wanted);
+    fields.iter().any(|f| f.contains(&wanted) || wanted.contains(f.as_str()) || archive == Some(f.as_str()))
+}
+
+// Scripts written without spaces between words; each character counts as a word
+fn is_unspaced_script(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+    )
+}
+
+/// Counts words in an abstract. Hyphens and apostrophes inside a word don't split it,
+/// and CJK text is counted per character.
+pub fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_unspaced_script(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else if !(in_word && matches!(c, '-' | '\'' | '\u{2019}')) {
+            in_word = false;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper_with_abstract(abstract_text: Option<&str>) -> PaperMetadata {
+        PaperMetadata {
+            title: "Test".to_string(),
+            abstract_text: abstract_text.map(|s| s.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_count_words_ascii_and_punctuation() {
+        assert_eq!(count_words("State-of-the-art models don't generalize."), 4);
+        assert_eq!(count_words("  spaced   out\n\twords  "), 3);
+        assert_eq!(count_words(""), 0);
+        assert_eq!(count_words("— … !!"), 0);
+    }
+
+    #[test]
+    fn test_count_words_unicode() {
+        assert_eq!(count_words("Überblick über maschinelles Lernen"), 4);
+        assert_eq!(count_words("Réseaux de neurones l’apprentissage"), 4);
+        assert_eq!(count_words("深層学習"), 4);
+        assert_eq!(count_words("Transformer モデル"), 4);
+    }
+
+    #[test]
+    fn test_require_abstract() {
+        let filter = CandidateFilter { require_abstract: true, ..Default::default() };
+        assert!(filter.keeps(&paper_with_abstract(Some("An abstract."))));
+        assert!(!filter.keeps(&paper_with_abstract(Some("   "))));
+        assert!(!filter.keeps(&paper_with_abstract(None)));
+    }
+
+    #[test]
+    fn test_min_abstract_words() {
+        let filter = CandidateFilter { min_abstract_words: Some(3), ..Default::default() };
+        let kept = filter.apply(vec![
+            paper_with_abstract(Some("one two three")),
+            paper_with_abstract(Some("one two")),
+            paper_with_abstract(None),
+        ]);
+        assert_eq!(kept.len(), 1);
+    }
+
+    #[test]
+    fn test_inactive_filter_keeps_everything() {
+        let filter = CandidateFilter::default();
+        assert_eq!(filter.apply(vec![paper_with_abstract(None)]).len(), 1);
+    }
+
+    fn paper_with_fields(fields: &[&str]) -> PaperMetadata {
+        PaperMetadata {
+            title: "Test".to_string(),
+            categories: fields.iter().map(|f| format!("s2:{}", f)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_category_matches_s2_fields() {
+        let paper = paper_with_fields(&["Computer Science", "Mathematics"]);
+        assert!(matches_category(&paper, "computer science"));
+        assert!(matches_category(&paper, "Math"));
+        assert!(matches_category(&paper, "cs.LG"));
+        assert!(!matches_category(&paper, "Medicine"));
+        assert!(!matches_category(&paper, "q-bio.NC"));
+    }
+
+    #[test]
+    fn test_category_post_filter_on_mixed_candidates() {
+        let filter = CandidateFilter { category: Some("Machine Learning".to_string()), ..Default::default() };
+        let mut tagged = paper_with_fields(&["Computer Science", "Machine Learning"]);
+        tagged.title = "Tagged".to_string();
+        let mut unrelated = paper_with_fields(&["Medicine", "Biology"]);
+        unrelated.title = "Unrelated".to_string();
+        let mut arxiv = paper_with_fields(&[]);
+        arxiv.title = "From arXiv".to_string();
+        arxiv.categories = vec!["cs.LG".to_string()];
+
+        let kept: Vec<String> = filter.apply(vec![tagged, unrelated, arxiv]).into_iter().map(|p| p.title).collect();
+        assert_eq!(kept, vec!["Tagged", "From arXiv"]);
+    }
+
+    #[test]
+    fn test_category_keeps_papers_without_fields() {
+        let filter = CandidateFilter { category: Some("Medicine".to_string()), ..Default::default() };
+        let kept = filter.apply(vec![paper_with_fields(&[]), paper_with_fields(&["Physics"])]);
+        assert_eq!(kept.len(), 1);
+        assert!(kept[0].categories.is_empty());
+    }
+
+    fn paper_from(year: Option<u32>) -> PaperMetadata {
+        PaperMetadata { title: format!("{:?}", year), year, ..Default::default() }
+    }
+
+    #[test]
+    fn test_year_range_bounds_are_inclusive() {
+        let range = YearRange::new(Some(2015), Some(2020), false).unwrap();
+        let kept: Vec<Option<u32>> = range.apply([2014, 2015, 2017, 2020, 2021].map(|y| paper_from(Some(y))).to_vec())
+            .into_iter().map(|p| p.year).collect();
+        assert_eq!(kept, [Some(2015), Some(2017), Some(2020)]);
+        // Either end can be open
+        assert!(YearRange::new(None, Some(2020), false).unwrap().keeps(&paper_from(Some(1950))));
+        assert!(!YearRange::new(Some(2021), None, false).unwrap().keeps(&paper_from(Some(2020))));
+        assert!(YearRange::new(Some(2021), Some(2020), false).is_err());
+        assert!(YearRange::new(Some(2020), Some(2020), false).unwrap().keeps(&paper_from(Some(2020))));
+    }
+
+    #[test]
+    fn test_year_range_keeps_undated_papers_unless_required() {
+        let range = YearRange::new(Some(2015), None, false).unwrap();
+        assert!(range.keeps(&paper_from(None)));
+        assert!(range.is_unchecked(&paper_from(None)));
+        assert!(!range.is_unchecked(&paper_from(Some(2016))));
+
+        let strict = YearRange { require_year: true, ..range };
+        assert!(!strict.keeps(&paper_from(None)));
+        // --require-year works on its own too
+        let dated_only = YearRange::new(None, None, true).unwrap();
+        assert_eq!(dated_only.apply(vec![paper_from(None), paper_from(Some(1999))]).len(), 1);
+        assert!(!dated_only.is_unchecked(&paper_from(None)));
+        assert!(!YearRange::default().is_active());
+    }
+
+    #[test]
+    fn test_year_range_description() {
+        assert_eq!(YearRange::new(Some(2015), Some(2020), false).unwrap().to_string(), "published 2015-2020");
+        assert_eq!(YearRange::new(Some(2015), None, true).unwrap().to_string(), "published 2015 or later, with a known year");
+        assert_eq!(YearRange::new(None, Some(2020), false).unwrap().to_string(), "published 2020 or earlier");
+        assert_eq!(YearRange::new(Some(2020), Some(2020), false).unwrap().to_string(), "published in 2020");
+        assert_eq!(YearRange::new(None, None, true).unwrap().to_string(), "with a known publication year");
+    }
+}
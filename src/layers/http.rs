@@ -0,0 +1,29 @@
+// Timeouts shared by every HTTP client, so a server that stops answering fails the request
+// instead of hanging the run
+use reqwest::{Client, ClientBuilder};
+use std::time::Duration;
+
+// Opening a connection: DNS, TCP and TLS
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// A whole API request, body included; search and lookup responses are small
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+// The longest pause between pieces of a download. A large PDF over a slow link can take
+// minutes in all, so downloads get this instead of an overall limit
+pub const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A client for the search and enrichment APIs.
+pub fn api_client() -> Client {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("a client with timeouts always builds")
+}
+
+/// The settings of a download client, giving up on a body that stalls for `read_timeout`;
+/// callers may add a cookie jar before building.
+pub fn download_client(read_timeout: Duration) -> ClientBuilder {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(read_timeout)
+}
@@ -0,0 +1,214 @@
+// Re-hashes downloaded PDFs against the SHA-256 recorded in the manifest. Files are hashed on
+// a bounded pool of worker threads, each streaming its file in chunks, so a large library
+// neither runs serially nor holds whole PDFs in memory.
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use crate::layers::download::ManifestEntry;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The SHA-256 (lowercase hex) and size of the file at `path`, read a chunk at a time.
+pub fn hash_file(path: &Path) -> std::io::Result<(String, u64)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut bytes = 0u64;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        bytes += read as u64;
+    }
+    Ok((hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect(), bytes))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Ok,
+    Mismatch { expected: String, actual: String },
+    Missing,
+    // Downloaded before hashes were recorded; `actual` is what `verify --fix` records
+    Unhashed { actual: String },
+    Unreadable(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub id: String,
+    pub path: PathBuf,
+    pub verdict: Verdict,
+}
+
+/// Files and bytes hashed so far, shared by the workers.
+pub struct Progress {
+    total: usize,
+    files: AtomicUsize,
+    bytes: AtomicU64,
+    started: Instant,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self { total, files: AtomicUsize::new(0), bytes: AtomicU64::new(0), started: Instant::now() }
+    }
+
+    fn record(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.files.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn files(&self) -> usize {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.files() >= self.total
+    }
+
+    /// "12/40 files, 3.0 files/s, 55.2 MB/s" after `elapsed`.
+    pub fn render(&self, elapsed: Duration) -> String {
+        let seconds = elapsed.as_secs_f64().max(0.001);
+        format!("{}/{} files, {:.1} files/s, {:.1} MB/s",
+            self.files(), self.total, self.files() as f64 / seconds, self.bytes() as f64 / 1_000_000.0 / seconds)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+/// Parses `--changed-since`: a date ("2025-01-31", midnight UTC) or an RFC 3339 timestamp.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow!("'{}' is not a date (YYYY-MM-DD) or an RFC 3339 timestamp", value))?;
+    Ok(date.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc())
+}
+
+/// Whether the entry was downloaded, or its file modified, at or after `since`. Entries whose
+/// timestamp doesn't parse are kept, so nothing is skipped by accident.
+pub fn changed_since(entry: &ManifestEntry, path: &Path, since: DateTime<Utc>) -> bool {
+    let downloaded = DateTime::parse_from_rfc3339(&entry.downloaded_at).map(|at| at.with_timezone(&Utc));
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).map(DateTime::<Utc>::from);
+    match (downloaded, modified) {
+        (Err(_), _) => true,
+        (Ok(downloaded), modified) => downloaded >= since || modified.is_ok_and(|m| m >= since),
+    }
+}
+
+fn check(base_dir: &Path, entry: &ManifestEntry, progress: &Progress) -> Check {
+    let path = base_dir.join(&entry.relative_path);
+    let verdict = match hash_file(&path) {
+        Ok((actual, bytes)) => {
+            progress.record(bytes);
+            match &entry.sha256 {
+                Some(expected) if expected.eq_ignore_ascii_case(&actual) => Verdict::Ok,
+                Some(expected) => Verdict::Mismatch { expected: expected.clone(), actual },
+                None => Verdict::Unhashed { actual },
+            }
+        }
+        Err(e) => {
+            progress.record(0);
+            if e.kind() == std::io::ErrorKind::NotFound { Verdict::Missing } else { Verdict::Unreadable(e.to_string()) }
+        }
+    };
+    Check { id: entry.id.clone(), path, verdict }
+}
+
+/// Checks every entry on `workers` threads. Results come back in manifest order.
+pub fn verify(base_dir: &Path, entries: &[ManifestEntry], workers: usize, progress: &Progress) -> Result<Vec<Check>> {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(workers.max(1)).build()?;
+    Ok(pool.install(|| entries.par_iter().map(|entry| check(base_dir, entry, progress)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn entry(base: &Path, id: &str, contents: Option<&[u8]>, downloaded_at: &str) -> ManifestEntry {
+        let relative_path = format!("{}/paper.pdf", id);
+        let sha256 = contents.map(|bytes| {
+            std::fs::create_dir_all(base.join(id)).unwrap();
+            std::fs::write(base.join(&relative_path), bytes).unwrap();
+            hash_file(&base.join(&relative_path)).unwrap().0
+        });
+        ManifestEntry {
+            title: id.to_string(),
+            first_author: "Unknown".to_string(),
+            id: id.to_string(),
+            relative_path,
+            downloaded_at: downloaded_at.to_string(),
+            sha256,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_hash_file_streams_across_chunks() {
+        let base = temp_dir("integrity-chunks");
+        let contents: Vec<u8> = (0..CHUNK_SIZE * 2 + 17).map(|i| (i % 251) as u8).collect();
+        std::fs::write(base.join("big.pdf"), &contents).unwrap();
+        let (hash, bytes) = hash_file(&base.join("big.pdf")).unwrap();
+        assert_eq!(bytes, contents.len() as u64);
+        assert_eq!(hash, Sha256::digest(&contents).iter().map(|b| format!("{:02x}", b)).collect::<String>());
+    }
+
+    #[test]
+    fn test_verify_finds_corrupted_and_missing_files() {
+        let base = temp_dir("integrity-verify");
+        let mut entries: Vec<ManifestEntry> = (0..8)
+            .map(|i| entry(&base, &format!("p{}", i), Some(format!("%PDF-1.7 paper {}", i).as_bytes()), "2025-01-10T09:00:00+00:00"))
+            .collect();
+        std::fs::write(base.join("p3/paper.pdf"), b"%PDF-1.7 bit rot").unwrap();
+        std::fs::remove_file(base.join("p5/paper.pdf")).unwrap();
+        entries[6].sha256 = None;
+
+        let progress = Progress::new(entries.len());
+        let checks = verify(&base, &entries, 4, &progress).unwrap();
+        assert_eq!(checks.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), ["p0", "p1", "p2", "p3", "p4", "p5", "p6", "p7"]);
+        assert!(matches!(checks[3].verdict, Verdict::Mismatch { .. }));
+        assert_eq!(checks[5].verdict, Verdict::Missing);
+        assert!(matches!(checks[6].verdict, Verdict::Unhashed { .. }));
+        assert_eq!(checks.iter().filter(|c| c.verdict == Verdict::Ok).count(), 5);
+
+        // Every file is counted once, missing ones included, and the bytes of those read
+        assert!(progress.is_done());
+        assert_eq!(progress.files(), 8);
+        let expected_bytes = 7 * 16;
+        assert_eq!(progress.bytes(), expected_bytes);
+        assert_eq!(progress.render(Duration::from_secs(2)), format!("8/8 files, 4.0 files/s, {:.1} MB/s", expected_bytes as f64 / 2_000_000.0));
+
+        // One worker gives the same answers
+        assert_eq!(verify(&base, &entries, 1, &Progress::new(entries.len())).unwrap(), checks);
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let base = temp_dir("integrity-since");
+        let old = entry(&base, "old", Some(b"%PDF old"), "2024-03-01T10:00:00+00:00");
+        let new = entry(&base, "new", Some(b"%PDF new"), "2025-06-01T10:00:00+00:00");
+        let since = parse_since("2025-01-01").unwrap();
+        assert!(changed_since(&new, &base.join(&new.relative_path), since));
+        // Downloaded long ago, but the file was written just now
+        assert!(changed_since(&old, &base.join(&old.relative_path), since));
+        let future = parse_since("2999-01-01T00:00:00Z").unwrap();
+        assert!(!changed_since(&old, &base.join(&old.relative_path), future));
+        assert!(changed_since(&ManifestEntry { downloaded_at: "yesterday".to_string(), ..old }, Path::new("/nonexistent"), future));
+        assert!(parse_since("last week").is_err());
+    }
+}
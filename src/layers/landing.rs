@@ -0,0 +1,153 @@
+// Publisher landing pages served where a PDF was expected. Most carry the real link in a
+// `citation_pdf_url` meta tag (Highwire/Google Scholar tags) or a "Download PDF" anchor.
+use url::Url;
+
+// Landing pages larger than this are not scanned
+pub const MAX_LANDING_PAGE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Returned (inside anyhow) when a PDF URL answers with an HTML page instead.
+#[derive(Debug, Clone)]
+pub struct NotAPdf {
+    // Where the page was finally served from, after redirects
+    pub url: String,
+    pub html: String,
+}
+
+impl std::fmt::Display for NotAPdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Expected a PDF but got an HTML page at {}", self.url)
+    }
+}
+
+impl std::error::Error for NotAPdf {}
+
+struct Tag<'a> {
+    name: String,
+    attributes: Vec<(String, &'a str)>,
+}
+
+impl Tag<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        self.attributes.iter().find(|(n, _)| n == name).map(|(_, v)| decode_entities(v))
+    }
+}
+
+// The handful of entities that show up in URLs
+fn decode_entities(value: &str) -> String {
+    value.trim().replace("&amp;", "&").replace("&#38;", "&").replace("&#x2F;", "/").replace("&#47;", "/")
+}
+
+// Start tags in document order with their attributes; enough HTML for meta and anchor tags
+fn tags(html: &str) -> Vec<Tag<'_>> {
+    let mut tags = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if rest.starts_with("!--") {
+            rest = rest.find("-->").map(|end| &rest[end + 3..]).unwrap_or("");
+            continue;
+        }
+        let name_len = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+        if name_len == 0 {
+            continue;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = &rest[name_len..];
+
+        let mut attributes = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                break;
+            }
+            let attr_len = rest.find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/').unwrap_or(rest.len()).max(1);
+            let attr = rest[..attr_len].to_ascii_lowercase();
+            rest = rest[attr_len..].trim_start();
+            let value = if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let body = &after[1..];
+                        let end = body.find(quote).unwrap_or(body.len());
+                        rest = body.get(end + 1..).unwrap_or("");
+                        &body[..end]
+                    }
+                    _ => {
+                        let end = after.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(after.len());
+                        rest = &after[end..];
+                        &after[..end]
+                    }
+                }
+            } else {
+                ""
+            };
+            attributes.push((attr, value));
+        }
+        tags.push(Tag { name, attributes });
+    }
+    tags
+}
+
+// `href` resolved against the page, if it is http(s) on the page's own host
+fn same_host_link(href: &str, page: &Url) -> Option<Url> {
+    let link = page.join(href).ok()?;
+    let same_host = link.host_str().is_some_and(|h| Some(h) == page.host_str());
+    (same_host && matches!(link.scheme(), "http" | "https")).then_some(link)
+}
+
+/// The PDF link on a landing page: the `citation_pdf_url` meta tag if there is one, otherwise
+/// the first anchor whose path ends in `.pdf`. Only links on the page's own host count, so
+/// ads and trackers pointing elsewhere are never followed.
+pub fn extract_pdf_link(html: &str, page_url: &str) -> Option<String> {
+    let page = Url::parse(page_url).ok()?;
+    let tags = tags(html);
+    let meta = tags.iter()
+        .filter(|t| t.name == "meta" && t.get("name").is_some_and(|n| n.eq_ignore_ascii_case("citation_pdf_url")))
+        .filter_map(|t| t.get("content"))
+        .find_map(|content| same_host_link(&content, &page));
+    let link = meta.or_else(|| {
+        tags.iter()
+            .filter(|t| t.name == "a")
+            .filter_map(|t| t.get("href"))
+            .filter_map(|href| same_host_link(&href, &page))
+            .find(|link| link.path().to_ascii_lowercase().ends_with(".pdf") && *link != page)
+    })?;
+    Some(link.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!("{}/tests/fixtures/landing/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn test_meta_tag() {
+        assert_eq!(
+            extract_pdf_link(&fixture("meta_tag.html"), "https://journals.example.org/article/10.1234/abc").as_deref(),
+            Some("https://journals.example.org/article/10.1234/abc/pdf?download=true&v=2")
+        );
+    }
+
+    #[test]
+    fn test_same_host_anchor() {
+        // The ad's PDF on another host comes first and is passed over
+        assert_eq!(
+            extract_pdf_link(&fixture("anchor.html"), "https://repository.example.edu/handle/123/456").as_deref(),
+            Some("https://repository.example.edu/bitstream/123/456/thesis.pdf")
+        );
+    }
+
+    #[test]
+    fn test_no_link() {
+        assert_eq!(extract_pdf_link(&fixture("no_link.html"), "https://publisher.example.com/doi/10.1/x"), None);
+    }
+
+    #[test]
+    fn test_meta_tag_on_another_host_is_ignored() {
+        let html = r#"<meta name="citation_pdf_url" content="https://cdn.ads.example.net/f.pdf"><a href='/files/real.PDF'>PDF</a>"#;
+        assert_eq!(extract_pdf_link(html, "https://example.org/paper").as_deref(), Some("https://example.org/files/real.PDF"));
+    }
+}
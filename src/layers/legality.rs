@@ -1,19 +1,218 @@
 // Basic legality checker for Open Access
-use crate::layers::PaperMetadata;
+use crate::layers::{PaperMetadata, PdfCopy, VersionHint};
+use crate::layers::arxiv_id::ArxivId;
 
 pub struct LegalityChecker;
 
+/// Why a download was allowed; recorded in the manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessBasis {
+    #[default]
+    OpenAccess,
+    /// A paper its sources report as closed, fetched from an open copy on a known-OA host
+    /// (its arXiv preprint, the ACL Anthology, OpenReview). The publisher's copy stays closed.
+    Green,
+    /// A closed paper fetched through the user's institutional proxy (--via-proxy)
+    InstitutionalEntitlement,
+}
+
+// Hosts whose copies are open whatever a paper's sources say: preprint servers and open
+// proceedings. Author pages (*.github.io and the like) are deliberately absent; anyone can
+// post a publisher's PDF there.
+pub const OPEN_COPY_HOSTS: &[&str] = &["arxiv.org", "aclanthology.org", "openreview.net"];
+
+/// Whether `url` is served from one of `OPEN_COPY_HOSTS` (or a subdomain, like export.arxiv.org).
+pub fn is_open_copy_host(url: &str) -> bool {
+    let Some(host) = url::Url::parse(url).ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase())) else {
+        return false;
+    };
+    OPEN_COPY_HOSTS.iter().any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+}
+
 impl LegalityChecker {
     pub fn is_legally_downloadable(paper: &PaperMetadata) -> bool {
         // Basic check for Open Access
         paper.is_oa
     }
+
+    /// The verdict for fetching `paper` from `url` in particular. An OA paper may be fetched
+    /// from anywhere; a closed one only from an open host, so its arXiv copy can be allowed
+    /// while the publisher's stays off limits.
+    pub fn url_basis(paper: &PaperMetadata, url: &str) -> Option<AccessBasis> {
+        if Self::is_legally_downloadable(paper) {
+            Some(AccessBasis::OpenAccess)
+        } else if is_open_copy_host(url) {
+            Some(AccessBasis::Green)
+        } else {
+            None
+        }
+    }
+
+    /// A closed paper's open copy: its PDF URL if that is on an open host, else its arXiv PDF.
+    pub fn open_copy(paper: &PaperMetadata) -> Option<String> {
+        paper.pdf_url.clone().into_iter()
+            .chain(paper.arxiv_id.as_deref().map(arxiv_pdf_url))
+            .find(|url| Self::url_basis(paper, url) == Some(AccessBasis::Green))
+    }
+
+    /// On what grounds `paper` may be downloaded, if any. A closed paper qualifies through an
+    /// open copy, or else when the user opted into their institution's proxy.
+    pub fn access_basis(paper: &PaperMetadata, via_proxy: bool) -> Option<AccessBasis> {
+        if Self::is_legally_downloadable(paper) {
+            Some(AccessBasis::OpenAccess)
+        } else if Self::open_copy(paper).is_some() {
+            // No need to go through the proxy for a copy anyone may download
+            Some(AccessBasis::Green)
+        } else if via_proxy {
+            Some(AccessBasis::InstitutionalEntitlement)
+        } else {
+            None
+        }
+    }
+}
+
+// Publisher hosts that frequently serve a login wall even when a source reports the paper as OA
+pub const DEFAULT_PAYWALLED_HOSTS: &[&str] = &[
+    "link.springer.com",
+    "ieeexplore.ieee.org",
+    "www.sciencedirect.com",
+    "onlinelibrary.wiley.com",
+    "dl.acm.org",
+    "www.tandfonline.com",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct PaywallHeuristic {
+    hosts: Vec<String>,
+}
+
+impl PaywallHeuristic {
+    pub fn new(hosts: Vec<String>) -> Self {
+        Self {
+            hosts: hosts.into_iter().map(|h| h.trim().to_lowercase()).filter(|h| !h.is_empty()).collect(),
+        }
+    }
+
+    /// Reads a comma-separated `PAYWALLED_HOSTS` list, falling back to the built-in one.
+    pub fn from_env() -> Self {
+        match std::env::var("PAYWALLED_HOSTS") {
+            Ok(list) => Self::new(list.split(',').map(|h| h.to_string()).collect()),
+            Err(_) => Self::new(DEFAULT_PAYWALLED_HOSTS.iter().map(|h| h.to_string()).collect()),
+        }
+    }
+
+    pub fn is_paywalled_host(&self, url: &str) -> bool {
+        let Some(host) = url::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_lowercase())) else {
+            return false;
+        };
+        self.hosts.iter().any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+    }
+
+    /// True when the paper claims OA but its only PDF URL sits on a host from the list.
+    pub fn is_suspicious(&self, paper: &PaperMetadata) -> bool {
+        paper.is_oa && paper.pdf_url.as_deref().is_some_and(|u| self.is_paywalled_host(u))
+    }
+
+    /// Swaps a suspicious PDF URL for the paper's arXiv copy when it has one. Unpaywall is
+    /// asked first, during discovery (see UnpaywallClient); this covers what it didn't replace.
+    /// Returns true if an alternate was found.
+    pub fn resolve_alternate(&self, paper: &mut PaperMetadata) -> bool {
+        if !self.is_suspicious(paper) {
+            return false;
+        }
+        match paper.arxiv_id.as_deref().map(arxiv_pdf_url) {
+            Some(alternate) => {
+                tracing::info!("Replacing paywall-prone PDF URL for '{}' with {}", paper.title, alternate);
+                paper.pdf_url = Some(alternate);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Host fragments that indicate an institutional or subject repository rather than a publisher
+const REPOSITORY_HOST_MARKERS: &[&str] = &[
+    "repository", "eprints", "dspace", "escholarship", "scholarworks",
+    "hal.science", "hal.archives-ouvertes.fr", "europepmc.org", "ncbi.nlm.nih.gov",
+    "zenodo.org", "osf.io", "core.ac.uk", "figshare.com",
+];
+
+pub fn is_repository_url(url: &str) -> bool {
+    let Some(host) = url::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_lowercase())) else {
+        return false;
+    };
+    let path = url.to_lowercase();
+    REPOSITORY_HOST_MARKERS.iter().any(|m| host.contains(m))
+        // Many universities run DSpace/EPrints under a generic host but with recognisable paths
+        || path.contains("/bitstream/") || path.contains("/handle/")
+}
+
+/// Infers the version hint from OpenAlex's `oa_status`, the location's `version`, and the PDF host.
+/// The explicit location version wins; the host heuristic only fills gaps.
+pub fn classify_version(oa_status: Option<&str>, location_version: Option<&str>, pdf_url: Option<&str>) -> Option<VersionHint> {
+    match location_version {
+        Some("publishedVersion") => return Some(VersionHint::PublishedVersion),
+        Some("acceptedVersion") => return Some(VersionHint::AcceptedManuscript),
+        Some("submittedVersion") => return Some(VersionHint::Preprint),
+        _ => {}
+    }
+    match oa_status {
+        Some("green") => return Some(VersionHint::AcceptedManuscript),
+        Some("gold") | Some("hybrid") | Some("bronze") | Some("diamond") => return Some(VersionHint::PublishedVersion),
+        _ => {}
+    }
+    pdf_url.filter(|u| is_repository_url(u)).map(|_| VersionHint::AcceptedManuscript)
+}
+
+/// Which PDF to download when the sources link several versions, picked with --prefer-version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum VersionPreference {
+    /// The version of record, when any source links one
+    Published,
+    /// Whichever PDF the merge kept (the default)
+    #[default]
+    Any,
+}
+
+/// Swaps the PDF for a published-version copy from `alternate_pdfs` when `preference` asks
+/// for one and the current PDF isn't known to be published; the replaced URL becomes an
+/// alternate. Returns true if the PDF changed.
+pub fn prefer_version(paper: &mut PaperMetadata, preference: VersionPreference) -> bool {
+    if preference == VersionPreference::Any || paper.version_hint == Some(VersionHint::PublishedVersion) {
+        return false;
+    }
+    let Some(i) = paper.alternate_pdfs.iter().position(|c| c.version_hint == Some(VersionHint::PublishedVersion)) else {
+        return false;
+    };
+    let published = paper.alternate_pdfs.remove(i);
+    tracing::info!("Using the published version of '{}' at {}", paper.title, published.url);
+    if let Some(url) = paper.pdf_url.replace(published.url) {
+        paper.alternate_pdfs.insert(0, PdfCopy { url, version_hint: paper.version_hint });
+    }
+    paper.version_hint = published.version_hint;
+    true
+}
+
+/// Builds the arXiv PDF URL from either a bare ID or an abs-page URL.
+pub fn arxiv_pdf_url(arxiv_id: &str) -> String {
+    if arxiv_id.starts_with("http") {
+        arxiv_id.replacen("/abs/", "/pdf/", 1)
+    } else {
+        ArxivId::parse(arxiv_id)
+            .map(|id| id.pdf_url())
+            .unwrap_or_else(|| format!("https://arxiv.org/pdf/{}", arxiv_id))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::layers::PaperMetadata;
+    use crate::layers::merge::Merger;
 
     fn create_paper(is_oa: bool) -> PaperMetadata {
          PaperMetadata {
@@ -29,6 +228,7 @@ mod tests {
             pdf_url: None,
             is_oa,
             categories: vec![],
+            ..Default::default()
         }
     }
 
@@ -43,4 +243,204 @@ mod tests {
         let paper = create_paper(false);
         assert!(!LegalityChecker::is_legally_downloadable(&paper));
     }
+
+    #[test]
+    fn test_open_copy_hosts() {
+        for url in [
+            "https://arxiv.org/pdf/1706.03762",
+            "http://export.arxiv.org/pdf/1706.03762v7",
+            "https://aclanthology.org/N19-1423.pdf",
+            "https://openreview.net/pdf?id=rJ4km2R5t7",
+        ] {
+            assert!(is_open_copy_host(url), "{} should be an open host", url);
+        }
+        for url in [
+            "https://someone.github.io/papers/camera-ready.pdf",
+            "https://arxiv.org.mirror.example.com/pdf/1706.03762",
+            "https://notarxiv.org/pdf/1706.03762",
+            "ftp://arxiv.org/pdf/1706.03762",
+            "https://dl.acm.org/doi/pdf/10.1145/3292500.3330701",
+            "not a url",
+        ] {
+            assert!(!is_open_copy_host(url), "{} should not be an open host", url);
+        }
+    }
+
+    #[test]
+    fn test_verdict_is_per_url() {
+        let closed = PaperMetadata { arxiv_id: Some("1810.04805".to_string()), ..create_paper(false) };
+        assert_eq!(LegalityChecker::url_basis(&closed, "https://arxiv.org/pdf/1810.04805"), Some(AccessBasis::Green));
+        assert_eq!(LegalityChecker::url_basis(&closed, "https://dl.acm.org/doi/pdf/10.1145/1"), None);
+        // An OA paper may be fetched from its publisher too
+        assert_eq!(LegalityChecker::url_basis(&create_paper(true), "https://dl.acm.org/doi/pdf/10.1145/1"), Some(AccessBasis::OpenAccess));
+    }
+
+    #[test]
+    fn test_closed_paper_with_an_open_copy() {
+        // The publisher PDF is passed over for the arXiv preprint; the paper itself stays closed
+        let mut paper = PaperMetadata {
+            pdf_url: Some("https://dl.acm.org/doi/pdf/10.1145/3292500.3330701".to_string()),
+            arxiv_id: Some("1905.12345".to_string()),
+            ..create_paper(false)
+        };
+        assert_eq!(LegalityChecker::open_copy(&paper).as_deref(), Some("https://arxiv.org/pdf/1905.12345"));
+        assert_eq!(LegalityChecker::access_basis(&paper, false), Some(AccessBasis::Green));
+        // Preferred over the proxy
+        assert_eq!(LegalityChecker::access_basis(&paper, true), Some(AccessBasis::Green));
+        assert!(!LegalityChecker::is_legally_downloadable(&paper));
+
+        // A PDF URL already on an open host is used as is
+        paper.pdf_url = Some("https://aclanthology.org/P19-1001.pdf".to_string());
+        assert_eq!(LegalityChecker::open_copy(&paper).as_deref(), Some("https://aclanthology.org/P19-1001.pdf"));
+
+        let publisher_only = PaperMetadata { pdf_url: Some("https://someone.github.io/paper.pdf".to_string()), ..create_paper(false) };
+        assert_eq!(LegalityChecker::open_copy(&publisher_only), None);
+        assert_eq!(LegalityChecker::access_basis(&publisher_only, false), None);
+        assert_eq!(LegalityChecker::access_basis(&publisher_only, true), Some(AccessBasis::InstitutionalEntitlement));
+        // OA papers don't need an open copy
+        assert_eq!(LegalityChecker::open_copy(&create_paper(true)), None);
+        assert_eq!(LegalityChecker::access_basis(&create_paper(true), true), Some(AccessBasis::OpenAccess));
+    }
+
+    fn default_heuristic() -> PaywallHeuristic {
+        PaywallHeuristic::new(DEFAULT_PAYWALLED_HOSTS.iter().map(|h| h.to_string()).collect())
+    }
+
+    #[test]
+    fn test_paywalled_host_is_suspicious() {
+        let mut paper = create_paper(true);
+        paper.pdf_url = Some("https://link.springer.com/content/pdf/10.1007/s10994-021-05946-3.pdf".to_string());
+        assert!(default_heuristic().is_suspicious(&paper));
+
+        paper.pdf_url = Some("https://arxiv.org/pdf/2103.00020".to_string());
+        assert!(!default_heuristic().is_suspicious(&paper));
+    }
+
+    #[test]
+    fn test_closed_paper_is_not_flagged() {
+        let mut paper = create_paper(false);
+        paper.pdf_url = Some("https://ieeexplore.ieee.org/stamp/stamp.jsp?arnumber=7780459".to_string());
+        assert!(!default_heuristic().is_suspicious(&paper));
+    }
+
+    #[test]
+    fn test_configured_hosts_match_subdomains() {
+        let heuristic = PaywallHeuristic::new(vec![" Example.org ".to_string()]);
+        let mut paper = create_paper(true);
+        paper.pdf_url = Some("https://pdfs.example.org/paper.pdf".to_string());
+        assert!(heuristic.is_suspicious(&paper));
+
+        paper.pdf_url = Some("https://notexample.org/paper.pdf".to_string());
+        assert!(!heuristic.is_suspicious(&paper));
+    }
+
+    #[test]
+    fn test_flagged_candidate_picks_up_arxiv_alternate() {
+        let mut paper = create_paper(true);
+        paper.pdf_url = Some("https://dl.acm.org/doi/pdf/10.1145/3292500.3330701".to_string());
+        paper.arxiv_id = Some("1905.12345".to_string());
+
+        let heuristic = default_heuristic();
+        assert!(heuristic.resolve_alternate(&mut paper));
+        assert_eq!(paper.pdf_url.as_deref(), Some("https://arxiv.org/pdf/1905.12345"));
+        assert!(!heuristic.is_suspicious(&paper));
+    }
+
+    #[test]
+    fn test_flagged_candidate_without_alternate_stays_flagged() {
+        let mut paper = create_paper(true);
+        paper.pdf_url = Some("https://dl.acm.org/doi/pdf/10.1145/3292500.3330701".to_string());
+
+        let heuristic = default_heuristic();
+        assert!(!heuristic.resolve_alternate(&mut paper));
+        assert!(heuristic.is_suspicious(&paper));
+    }
+
+    #[test]
+    fn test_repository_url_classification() {
+        let repository = [
+            "https://dspace.mit.edu/bitstream/handle/1721.1/12345/paper.pdf",
+            "https://eprints.whiterose.ac.uk/123456/1/accepted.pdf",
+            "https://hal.science/hal-01234567/document",
+            "https://europepmc.org/articles/PMC1234567?pdf=render",
+            "https://research.example.edu/bitstream/123/4/manuscript.pdf",
+        ];
+        let publisher = [
+            "https://www.nature.com/articles/nature14539.pdf",
+            "https://link.springer.com/content/pdf/10.1007/s10994-021-05946-3.pdf",
+            "https://arxiv.org/pdf/1706.03762",
+            "not a url",
+        ];
+        for url in repository {
+            assert!(is_repository_url(url), "{} should be a repository", url);
+        }
+        for url in publisher {
+            assert!(!is_repository_url(url), "{} should not be a repository", url);
+        }
+    }
+
+    #[test]
+    fn test_classify_version_precedence() {
+        // Explicit location version beats oa_status and host
+        assert_eq!(
+            classify_version(Some("green"), Some("publishedVersion"), Some("https://eprints.example.ac.uk/1/paper.pdf")),
+            Some(VersionHint::PublishedVersion)
+        );
+        assert_eq!(classify_version(Some("gold"), Some("acceptedVersion"), None), Some(VersionHint::AcceptedManuscript));
+        assert_eq!(classify_version(Some("green"), Some("submittedVersion"), None), Some(VersionHint::Preprint));
+        assert_eq!(classify_version(Some("green"), None, Some("https://www.nature.com/articles/x.pdf")), Some(VersionHint::AcceptedManuscript));
+        assert_eq!(classify_version(Some("hybrid"), None, None), Some(VersionHint::PublishedVersion));
+        // Host heuristic only when nothing else is known
+        assert_eq!(classify_version(None, None, Some("https://dspace.mit.edu/bitstream/1/2/x.pdf")), Some(VersionHint::AcceptedManuscript));
+        assert_eq!(classify_version(None, None, Some("https://www.nature.com/articles/x.pdf")), None);
+        assert_eq!(classify_version(Some("closed"), None, None), None);
+    }
+
+    // One paper as three sources link it: arXiv's preprint, a repository's accepted manuscript
+    // and the publisher's version of record, merged in discovery order
+    fn merged_copies() -> PaperMetadata {
+        let record = |url: &str, version_hint| PaperMetadata {
+            doi: Some("10.1000/xyz".to_string()),
+            pdf_url: Some(url.to_string()),
+            version_hint,
+            ..create_paper(true)
+        };
+        Merger::merge(vec![
+            record("https://arxiv.org/pdf/1706.03762", None),
+            record("https://eprints.example.ac.uk/1/accepted.pdf", Some(VersionHint::AcceptedManuscript)),
+            record("https://www.nature.com/articles/x.pdf", Some(VersionHint::PublishedVersion)),
+            record("https://arxiv.org/pdf/1706.03762", None),
+        ]).remove(0)
+    }
+
+    #[test]
+    fn test_prefer_version_ordering() {
+        let mut paper = merged_copies();
+        let alternates = |p: &PaperMetadata| p.alternate_pdfs.iter().map(|c| c.url.clone()).collect::<Vec<_>>();
+        assert_eq!(paper.pdf_url.as_deref(), Some("https://arxiv.org/pdf/1706.03762"));
+        assert_eq!(alternates(&paper), ["https://eprints.example.ac.uk/1/accepted.pdf", "https://www.nature.com/articles/x.pdf"]);
+
+        // `any` keeps the PDF the merge picked
+        assert!(!prefer_version(&mut paper, VersionPreference::Any));
+        assert_eq!(paper.pdf_url.as_deref(), Some("https://arxiv.org/pdf/1706.03762"));
+
+        assert!(prefer_version(&mut paper, VersionPreference::Published));
+        assert_eq!(paper.pdf_url.as_deref(), Some("https://www.nature.com/articles/x.pdf"));
+        assert_eq!(paper.version_hint, Some(VersionHint::PublishedVersion));
+        assert_eq!(alternates(&paper), ["https://arxiv.org/pdf/1706.03762", "https://eprints.example.ac.uk/1/accepted.pdf"]);
+        assert!(!prefer_version(&mut paper, VersionPreference::Published));
+
+        // Without a published copy the PDF stays, whatever the preference
+        let mut unpublished = merged_copies();
+        unpublished.alternate_pdfs.retain(|c| c.version_hint != Some(VersionHint::PublishedVersion));
+        assert!(!prefer_version(&mut unpublished, VersionPreference::Published));
+        assert_eq!(unpublished.pdf_url.as_deref(), Some("https://arxiv.org/pdf/1706.03762"));
+    }
+
+    #[test]
+    fn test_arxiv_pdf_url() {
+        assert_eq!(arxiv_pdf_url("2301.01234v2"), "https://arxiv.org/pdf/2301.01234v2");
+        assert_eq!(arxiv_pdf_url("http://arxiv.org/abs/2301.01234v2"), "http://arxiv.org/pdf/2301.01234v2");
+        assert_eq!(arxiv_pdf_url("arXiv:cs.DS/0301001v1"), "https://arxiv.org/pdf/cs/0301001v1");
+    }
 }
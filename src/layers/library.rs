@@ -0,0 +1,167 @@
+// "Do I already have this?" checks against the download manifest
+use crate::layers::PaperMetadata;
+use crate::layers::download::{ManifestEntry, RemovedRecord};
+use crate::layers::normalize::{normalize_title, surname, TitleFolding};
+use crate::layers::resolution::{normalized_title_similarity, same_surname, Similarity, DEFAULT_THRESHOLD_RATIO};
+
+struct IndexedEntry<T> {
+    entry: T,
+    normalized_title: String,
+    author_surname: Option<String>,
+}
+
+impl<T> IndexedEntry<T> {
+    fn new(entry: T, title: &str, first_author: &str) -> Self {
+        Self {
+            normalized_title: normalize_title(title, &TitleFolding::default()),
+            // The manifest uses "Unknown" when a paper had no authors
+            author_surname: Some(first_author).filter(|a| *a != "Unknown").and_then(surname),
+            entry,
+        }
+    }
+}
+
+/// Manifest entries with titles and authors normalized once up front,
+/// so checking each candidate is a cheap linear scan even for large libraries.
+pub struct LibraryIndex {
+    entries: Vec<IndexedEntry<ManifestEntry>>,
+    removed: Vec<IndexedEntry<RemovedRecord>>,
+}
+
+// The most similar entry that shares an author with the candidate, if it is as close as a
+// search match must be by default; titles and surnames are compared as the resolver does
+fn best_match<'a, T>(entries: &'a [IndexedEntry<T>], paper: &PaperMetadata) -> Option<(&'a T, Similarity)> {
+    let title = normalize_title(&paper.title, &TitleFolding::default());
+    let surnames: Vec<String> = paper.authors.iter().filter_map(|a| surname(a)).collect();
+    let min_similarity = Similarity::from_ratio(DEFAULT_THRESHOLD_RATIO);
+
+    entries.iter()
+        .filter(|e| e.author_surname.as_ref().is_some_and(|s| surnames.iter().any(|n| same_surname(n, s))))
+        .map(|e| (e, normalized_title_similarity(&title, &e.normalized_title)))
+        .filter(|(_, score)| *score >= min_similarity)
+        .max_by_key(|(_, score)| *score)
+        .map(|(e, score)| (&e.entry, score))
+}
+
+impl LibraryIndex {
+    pub fn new(entries: Vec<ManifestEntry>) -> Self {
+        Self {
+            entries: entries.into_iter().map(|entry| {
+                let (title, first_author) = (entry.title.clone(), entry.first_author.clone());
+                IndexedEntry::new(entry, &title, &first_author)
+            }).collect(),
+            removed: Vec::new(),
+        }
+    }
+
+    /// Also remember papers taken out with `remove`, for `previously_removed`.
+    pub fn with_removed(mut self, removed: Vec<RemovedRecord>) -> Self {
+        self.removed = removed.into_iter().map(|record| {
+            let (title, first_author) = (record.entry.title.clone(), record.entry.first_author.clone());
+            IndexedEntry::new(record, &title, &first_author)
+        }).collect();
+        self
+    }
+
+    /// The most similar library entry that shares an author with the candidate, if it is close enough.
+    pub fn near_match(&self, paper: &PaperMetadata) -> Option<(&ManifestEntry, Similarity)> {
+        best_match(&self.entries, paper)
+    }
+
+    /// The removal record for a candidate that matches a paper removed earlier, by the same rule as `near_match`.
+    pub fn previously_removed(&self, paper: &PaperMetadata) -> Option<&RemovedRecord> {
+        best_match(&self.removed, paper).map(|(record, _)| record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, first_author: &str, year: u32) -> ManifestEntry {
+        ManifestEntry {
+            title: title.to_string(),
+            first_author: first_author.to_string(),
+            year: Some(year),
+            id: title.to_lowercase().replace(' ', "_"),
+            relative_path: format!("{}/paper.pdf", title.to_lowercase().replace(' ', "_")),
+            downloaded_at: "2026-01-01T00:00:00+00:00".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn candidate(title: &str, authors: &[&str]) -> PaperMetadata {
+        PaperMetadata {
+            title: title.to_string(),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn library() -> LibraryIndex {
+        LibraryIndex::new(vec![
+            entry("Neural Machine Translation by Jointly Learning to Align and Translate", "Dzmitry Bahdanau", 2014),
+            entry("Deep Residual Learning for Image Recognition", "Kaiming He", 2016),
+            entry("Untitled Notes", "Unknown", 2020),
+        ])
+    }
+
+    #[test]
+    fn test_earlier_version_with_same_author_matches() {
+        let paper = candidate("Neural machine translation by jointly learning to align and translate.", &["D. Bahdanau", "K. Cho"]);
+        let library = library();
+        let (found, score) = library.near_match(&paper).unwrap();
+        assert_eq!(found.year, Some(2014));
+        assert!(score >= Similarity::from_ratio(DEFAULT_THRESHOLD_RATIO));
+
+        // A surname spelled slightly differently is still the same author, as in an --author search
+        let transliterated = candidate("Neural Machine Translation by Jointly Learning to Align and Translate", &["D. Bahdanaw"]);
+        assert!(library.near_match(&transliterated).is_some());
+    }
+
+    #[test]
+    fn test_similar_title_different_authors_is_not_a_match() {
+        let paper = candidate("Deep Residual Learning for Image Recognition", &["Someone Else"]);
+        assert!(library().near_match(&paper).is_none());
+    }
+
+    #[test]
+    fn test_same_author_different_paper_is_not_a_match() {
+        let paper = candidate("Mask R-CNN", &["Kaiming He"]);
+        assert!(library().near_match(&paper).is_none());
+    }
+
+    #[test]
+    fn test_threshold_boundary() {
+        // A subtitle added to the title drags similarity well below the threshold
+        let paper = candidate("Deep Residual Learning for Image Recognition: Extended Analysis and Ablations", &["Kaiming He"]);
+        assert!(library().near_match(&paper).is_none());
+
+        let typo = candidate("Deep Residual Learnng for Image Recognition", &["Kaiming He"]);
+        assert!(library().near_match(&typo).is_some());
+    }
+
+    #[test]
+    fn test_unknown_author_entries_never_match() {
+        let paper = candidate("Untitled Notes", &["Unknown"]);
+        assert!(library().near_match(&paper).is_none());
+    }
+
+    #[test]
+    fn test_previously_removed_paper_is_recognized() {
+        let removed = RemovedRecord {
+            removed_at: "2026-03-01T09:30:00+00:00".to_string(),
+            reason: Some("retracted".to_string()),
+            entry: entry("Deep Residual Learning for Image Recognition", "Kaiming He", 2016),
+            metadata: None,
+        };
+        let library = LibraryIndex::new(Vec::new()).with_removed(vec![removed]);
+
+        let found = library.previously_removed(&candidate("Deep residual learning for image recognition", &["K. He"])).unwrap();
+        assert_eq!(found.removed_on(), "2026-03-01");
+        assert_eq!(found.reason.as_deref(), Some("retracted"));
+        // Removed papers aren't in the library any more
+        assert!(library.near_match(&candidate("Deep Residual Learning for Image Recognition", &["Kaiming He"])).is_none());
+        assert!(library.previously_removed(&candidate("Mask R-CNN", &["Kaiming He"])).is_none());
+    }
+}
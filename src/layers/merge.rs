@@ -0,0 +1,463 @@
+// Collapses the same paper reported by several sources into one candidate
+use std::collections::HashMap;
+use crate::layers::{PaperMetadata, PdfCopy};
+use crate::layers::arxiv_id::ArxivId;
+use crate::layers::enrichment::normalize_doi;
+use crate::layers::normalize::{normalize_title, surname, TitleFolding};
+use crate::layers::year;
+
+pub struct Merger;
+
+// Years at least this far apart are different records, not print vs online-first (see year::reconcile)
+pub const CONFLICTING_YEAR_GAP: u32 = 3;
+
+/// How two records sharing an identifier disagree too much to be merged into one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    Years(u32, u32),
+    // Both list authors and no surname appears in both
+    Authors,
+}
+
+/// The material disagreement between two records linked by a key, if any. Missing years or
+/// author lists never conflict, nor do truncated lists, which may have cut the shared names.
+pub fn conflict(a: &PaperMetadata, b: &PaperMetadata) -> Option<Conflict> {
+    if let (Some(x), Some(y)) = (a.year, b.year) {
+        if x.abs_diff(y) >= CONFLICTING_YEAR_GAP {
+            return Some(Conflict::Years(x, y));
+        }
+    }
+    let surnames = |p: &PaperMetadata| p.authors.iter().filter_map(|a| surname(a)).collect::<Vec<_>>();
+    if a.authors_truncated || b.authors_truncated {
+        return None;
+    }
+    let (left, right) = (surnames(a), surnames(b));
+    if !left.is_empty() && !right.is_empty() && !left.iter().any(|s| right.contains(s)) {
+        return Some(Conflict::Authors);
+    }
+    None
+}
+
+// How many of the fields a download's metadata.json and README draw on the record fills
+fn richness(paper: &PaperMetadata) -> usize {
+    let filled = [
+        paper.doi.is_some(), paper.year.is_some(), paper.venue.is_some(), paper.abstract_text.is_some(),
+        paper.pdf_url.is_some(), paper.volume.is_some(), paper.issue.is_some(), paper.pages.is_some(),
+        paper.publisher.is_some(), paper.citation_count.is_some(),
+    ];
+    filled.iter().filter(|f| **f).count() + paper.authors.len().min(10)
+}
+
+/// The candidate's own record followed by its conflicting ones, each without the links.
+pub fn records(paper: &PaperMetadata) -> Vec<PaperMetadata> {
+    let primary = PaperMetadata { conflicting_records: Vec::new(), ..paper.clone() };
+    std::iter::once(primary).chain(paper.conflicting_records.iter().cloned()).collect()
+}
+
+/// Index of the record in `records` that fills the most fields; the first among ties.
+pub fn richest(records: &[PaperMetadata]) -> usize {
+    records.iter().enumerate().rev().max_by_key(|(_, r)| richness(r)).map_or(0, |(i, _)| i)
+}
+
+/// `records[pick]` to download. A record without a PDF keeps the listed candidate's, since
+/// that is what made it downloadable.
+pub fn trusted(mut records: Vec<PaperMetadata>, pick: usize) -> PaperMetadata {
+    let listed = records[0].clone();
+    let mut chosen = records.swap_remove(pick.min(records.len() - 1));
+    if chosen.pdf_url.is_none() {
+        chosen.pdf_url = listed.pdf_url;
+        chosen.is_oa = listed.is_oa;
+        chosen.version_hint = listed.version_hint;
+    }
+    chosen
+}
+
+/// Keys a candidate can be matched on; any one in common puts two candidates in a group.
+pub fn keys(paper: &PaperMetadata) -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Some(doi) = paper.doi.as_deref().map(normalize_doi).filter(|d| !d.is_empty()) {
+        keys.push(format!("doi:{}", doi));
+    }
+    if let Some(arxiv) = paper.arxiv_id.as_deref() {
+        // Versions of one preprint are the same paper
+        let base = ArxivId::parse(arxiv).map(|id| id.base_id()).unwrap_or_else(|| arxiv.trim().to_lowercase());
+        keys.push(format!("arxiv:{}", base));
+    }
+    for title in std::iter::once(&paper.title).chain(&paper.alternate_titles) {
+        let title = normalize_title(title, &TitleFolding::default());
+        // One- and two-word titles ("Introduction", "Book Review") are too generic to identify a paper
+        if title.split(' ').count() >= 3 {
+            keys.push(format!("title:{}", title));
+        }
+    }
+    keys
+}
+
+// Keeps `title` as an alternate unless it normalizes to a title the paper already has
+fn add_alternate(into: &mut PaperMetadata, title: &str) {
+    let folding = TitleFolding::default();
+    let normalized = normalize_title(title, &folding);
+    let known = std::iter::once(&into.title).chain(&into.alternate_titles)
+        .any(|t| normalize_title(t, &folding) == normalized);
+    if !known && !normalized.is_empty() {
+        into.alternate_titles.push(title.to_string());
+    }
+}
+
+// Keeps `copy` as an alternate PDF unless the paper already links that URL
+fn add_pdf_copy(into: &mut PaperMetadata, copy: PdfCopy) {
+    let known = into.pdf_url.as_deref() == Some(copy.url.as_str()) || into.alternate_pdfs.iter().any(|c| c.url == copy.url);
+    if !known {
+        into.alternate_pdfs.push(copy);
+    }
+}
+
+fn first<T: Clone>(into: &mut Option<T>, other: &Option<T>) {
+    if into.is_none() {
+        *into = other.clone();
+    }
+}
+
+fn longer(into: &mut Option<String>, other: &Option<String>) {
+    let other_len = other.as_deref().map(str::len).unwrap_or(0);
+    if other_len > into.as_deref().map(str::len).unwrap_or(0) {
+        *into = other.clone();
+    }
+}
+
+/// `Merger::merge` one candidate at a time, for results that arrive as a stream. Only the
+/// distinct papers are held; a duplicate is folded into its group as soon as it arrives.
+#[derive(Default)]
+pub struct MergeIndex {
+    merged: Vec<PaperMetadata>,
+    index: HashMap<String, usize>,
+    seen: usize,
+}
+
+impl MergeIndex {
+    pub fn push(&mut self, paper: PaperMetadata) {
+        self.seen += 1;
+        let paper_keys = keys(&paper);
+        match paper_keys.iter().find_map(|k| self.index.get(k).copied()) {
+            Some(i) => match conflict(&self.merged[i], &paper) {
+                Some(found) => {
+                    tracing::info!("Not merging '{}' into '{}': {:?}", paper.title, self.merged[i].title, found);
+                    let rivals = &mut self.merged[i].conflicting_records;
+                    // A record that agrees with an earlier rival is that rival's duplicate
+                    match rivals.iter_mut().find(|r| conflict(r, &paper).is_none()) {
+                        Some(rival) => Merger::merge_into(rival, paper),
+                        None => rivals.push(paper),
+                    }
+                }
+                None => {
+                    Merger::merge_into(&mut self.merged[i], paper);
+                    // The merged paper may have gained keys (e.g. a DOI from OpenAlex)
+                    for key in keys(&self.merged[i]) {
+                        self.index.entry(key).or_insert(i);
+                    }
+                }
+            },
+            None => {
+                for key in paper_keys {
+                    self.index.entry(key).or_insert(self.merged.len());
+                }
+                self.merged.push(paper);
+            }
+        }
+    }
+
+    /// Candidates pushed so far, duplicates included.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    pub fn into_papers(self) -> Vec<PaperMetadata> {
+        self.merged
+    }
+}
+
+impl Merger {
+    /// Groups candidates sharing a DOI, an arXiv ID (ignoring version), or a normalized title,
+    /// and merges each group into one. Order follows each group's first appearance.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn merge(candidates: Vec<PaperMetadata>) -> Vec<PaperMetadata> {
+        let mut index = MergeIndex::default();
+        for paper in candidates {
+            index.push(paper);
+        }
+        index.into_papers()
+    }
+
+    /// Fills gaps in `into` from `other`. Where both have a value, the richer one wins:
+    /// the longer author list and abstract, a publisher venue over "arXiv", any OA claim.
+    pub fn merge_into(into: &mut PaperMetadata, other: PaperMetadata) {
+        tracing::debug!("Merging duplicate '{}' into '{}'", other.title, into.title);
+        if into.garbled_title && !other.garbled_title {
+            into.title = other.title.clone();
+            into.garbled_title = false;
+        } else if !other.garbled_title {
+            add_alternate(into, &other.title);
+        }
+        for title in &other.alternate_titles {
+            add_alternate(into, title);
+        }
+        into.conflicting_records.extend(other.conflicting_records.iter().cloned());
+        // A truncated list from a source that saw 500 authors beats a full one of 3
+        if (other.total_authors(), other.authors.len()) > (into.total_authors(), into.authors.len()) {
+            into.authors = other.authors.clone();
+            into.authors_truncated = other.authors_truncated;
+            into.author_count = other.author_count;
+        }
+        into.year = year::reconcile(into.year, other.year);
+        for raw in &other.rejected_years {
+            if !into.rejected_years.contains(raw) {
+                into.rejected_years.push(raw.clone());
+            }
+        }
+        first(&mut into.doi, &other.doi);
+        first(&mut into.arxiv_id, &other.arxiv_id);
+        first(&mut into.semantic_scholar_id, &other.semantic_scholar_id);
+        first(&mut into.open_alex_id, &other.open_alex_id);
+        first(&mut into.core_id, &other.core_id);
+        first(&mut into.dblp_key, &other.dblp_key);
+        first(&mut into.europe_pmc_id, &other.europe_pmc_id);
+        for (key, value) in &other.external_ids {
+            into.external_ids.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        if into.venue.is_none() || (into.venue.as_deref() == Some("arXiv") && other.venue.is_some()) {
+            into.venue = other.venue.clone();
+        }
+        first(&mut into.publication_type, &other.publication_type);
+        longer(&mut into.abstract_text, &other.abstract_text);
+        into.citation_count = into.citation_count.max(other.citation_count);
+        // The version hint describes the PDF, so it travels with it
+        if into.pdf_url.is_none() {
+            into.pdf_url = other.pdf_url.clone();
+            into.version_hint = other.version_hint;
+        } else if let Some(url) = &other.pdf_url {
+            add_pdf_copy(into, PdfCopy { url: url.clone(), version_hint: other.version_hint });
+        }
+        for copy in &other.alternate_pdfs {
+            add_pdf_copy(into, copy.clone());
+        }
+        into.is_oa |= other.is_oa;
+        for category in other.categories {
+            if !into.categories.iter().any(|c| c.eq_ignore_ascii_case(&category)) {
+                into.categories.push(category);
+            }
+        }
+        first(&mut into.volume, &other.volume);
+        first(&mut into.issue, &other.issue);
+        first(&mut into.pages, &other.pages);
+        first(&mut into.publisher, &other.publisher);
+        first(&mut into.container_title, &other.container_title);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn semantic_scholar() -> PaperMetadata {
+        PaperMetadata {
+            title: "Attention Is All You Need".to_string(),
+            authors: vec!["Ashish Vaswani".to_string(), "Noam Shazeer".to_string()],
+            year: Some(2017),
+            doi: None,
+            arxiv_id: Some("1706.03762".to_string()),
+            semantic_scholar_id: Some("204e3073870fae3d05bcbc2f6a8e263d9b72e776".to_string()),
+            venue: Some("Neural Information Processing Systems".to_string()),
+            is_oa: false,
+            categories: vec!["s2:Computer Science".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn arxiv() -> PaperMetadata {
+        PaperMetadata {
+            title: "Attention Is All You Need".to_string(),
+            authors: vec!["Ashish Vaswani".into(), "Noam Shazeer".into(), "Niki Parmar".into()],
+            year: Some(2017),
+            arxiv_id: Some("1706.03762v7".to_string()),
+            venue: Some("arXiv".to_string()),
+            abstract_text: Some("The dominant sequence transduction models are based on complex recurrent networks.".to_string()),
+            pdf_url: Some("http://arxiv.org/pdf/1706.03762v7".to_string()),
+            is_oa: true,
+            categories: vec!["cs.CL".to_string(), "S2:computer science".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn openalex() -> PaperMetadata {
+        PaperMetadata {
+            title: "Attention is All you Need".to_string(),
+            authors: vec!["Ashish Vaswani".to_string()],
+            year: Some(2017),
+            doi: Some("https://doi.org/10.48550/ARXIV.1706.03762".to_string()),
+            open_alex_id: Some("https://openalex.org/W2963403868".to_string()),
+            abstract_text: Some("Short.".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_same_paper_from_three_sources_merges() {
+        let merged = Merger::merge(vec![semantic_scholar(), arxiv(), openalex()]);
+        assert_eq!(merged.len(), 1);
+        let paper = &merged[0];
+        assert_eq!(paper.authors.len(), 3);
+        assert_eq!(paper.arxiv_id.as_deref(), Some("1706.03762"));
+        assert_eq!(paper.doi.as_deref(), Some("https://doi.org/10.48550/ARXIV.1706.03762"));
+        assert_eq!(paper.open_alex_id.as_deref(), Some("https://openalex.org/W2963403868"));
+        assert_eq!(paper.venue.as_deref(), Some("Neural Information Processing Systems"));
+        assert!(paper.abstract_text.as_deref().unwrap().starts_with("The dominant"));
+        assert_eq!(paper.pdf_url.as_deref(), Some("http://arxiv.org/pdf/1706.03762v7"));
+        assert!(paper.is_oa);
+        assert_eq!(paper.categories, vec!["s2:Computer Science", "cs.CL"]);
+    }
+
+    #[test]
+    fn test_garbled_title_is_replaced_by_a_clean_one() {
+        let garbled = PaperMetadata { title: "Attention Is All You Need \u{FFFD}".to_string(), garbled_title: true, ..openalex() };
+        let merged = Merger::merge(vec![garbled, arxiv()]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].title, arxiv().title);
+        assert!(!merged[0].garbled_title);
+    }
+
+    #[test]
+    fn test_truncated_author_list_survives_merge() {
+        let mut truncated = PaperMetadata { authors: (0..600).map(|i| format!("A{}", i)).collect(), ..openalex() };
+        truncated.truncate_authors(50, None);
+        let merged = Merger::merge(vec![arxiv(), truncated]);
+        assert_eq!(merged[0].authors.len(), 50);
+        assert!(merged[0].authors_truncated);
+        assert_eq!(merged[0].total_authors(), 600);
+    }
+
+    #[test]
+    fn test_doi_prefix_and_case_are_ignored() {
+        let a = PaperMetadata { title: "A".to_string(), doi: Some("10.1038/Nature14539".to_string()), ..Default::default() };
+        let b = PaperMetadata { title: "B".to_string(), doi: Some("https://doi.org/10.1038/nature14539".to_string()), ..Default::default() };
+        let c = PaperMetadata { title: "C".to_string(), doi: Some("doi:10.1038/NATURE14539".to_string()), ..Default::default() };
+        assert_eq!(Merger::merge(vec![a, b, c]).len(), 1);
+    }
+
+    #[test]
+    fn test_arxiv_versions_merge() {
+        let a = PaperMetadata { title: "A".to_string(), arxiv_id: Some("1512.03385v1".to_string()), ..Default::default() };
+        let b = PaperMetadata { title: "B".to_string(), arxiv_id: Some("arXiv:1512.03385".to_string()), ..Default::default() };
+        let c = PaperMetadata { title: "C".to_string(), arxiv_id: Some("1512.03386".to_string()), ..Default::default() };
+        let merged = Merger::merge(vec![a, b, c]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].title, "A");
+    }
+
+    #[test]
+    fn test_titles_merge_only_when_near_identical() {
+        let a = PaperMetadata { title: "Deep Residual Learning for Image Recognition".to_string(), ..Default::default() };
+        let b = PaperMetadata { title: "Deep residual learning for image recognition.".to_string(), ..Default::default() };
+        let c = PaperMetadata { title: "Deep Residual Learning for Image Recognition: A Survey".to_string(), ..Default::default() };
+        let short_a = PaperMetadata { title: "Introduction".to_string(), ..Default::default() };
+        let short_b = PaperMetadata { title: "Introduction".to_string(), ..Default::default() };
+        assert_eq!(Merger::merge(vec![a, b, c, short_a, short_b]).len(), 4);
+    }
+
+    #[test]
+    fn test_titles_merge_across_markup() {
+        let arxiv = PaperMetadata { title: "Sorting in $O(n \\log n)$ Time:\n  Graphs &amp; Trees".to_string(), ..Default::default() };
+        let s2 = PaperMetadata { title: "Sorting in O(n log n) time: graphs & trees".to_string(), ..Default::default() };
+        let merged = Merger::merge(vec![arxiv.clone(), s2]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].title, arxiv.title);
+        // Spellings of the same title aren't alternates
+        assert!(merged[0].alternate_titles.is_empty());
+    }
+
+    #[test]
+    fn test_merge_collects_alternate_titles() {
+        let s2 = PaperMetadata { title: "BERT".to_string(), doi: Some("10.18653/v1/N19-1423".to_string()), ..Default::default() };
+        let openalex = PaperMetadata {
+            title: "BERT: Pre-training of Deep Bidirectional Transformers for Language Understanding".to_string(),
+            ..s2.clone()
+        };
+        let crossref = PaperMetadata { title: "BERT: pre-training of deep bidirectional transformers for language understanding.".to_string(), ..Default::default() };
+        let merged = Merger::merge(vec![s2, openalex.clone(), crossref]);
+        // The third shares no key with the first, only the alternate title gained from the second
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].title, "BERT");
+        assert_eq!(merged[0].alternate_titles, [openalex.title]);
+    }
+
+    #[test]
+    fn test_keys_gained_by_merging_link_later_candidates() {
+        // The title links S2 and OpenAlex; OpenAlex's DOI then links a title-less Crossref-style hit
+        let s2 = PaperMetadata { title: "Generative Adversarial Networks".to_string(), ..Default::default() };
+        let oa = PaperMetadata { doi: Some("10.1145/3422622".to_string()), ..s2.clone() };
+        let other = PaperMetadata { title: "GANs".to_string(), doi: Some("10.1145/3422622".to_string()), ..Default::default() };
+        assert_eq!(Merger::merge(vec![s2, oa, other]).len(), 1);
+    }
+
+    // Same DOI as `openalex()`, but a different paper's metadata
+    fn mislabelled(year: Option<u32>, authors: &[&str]) -> PaperMetadata {
+        PaperMetadata {
+            title: "Attention Is All You Need".to_string(),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            year,
+            doi: Some("10.48550/arxiv.1706.03762".to_string()),
+            venue: Some("Journal of Mixups".to_string()),
+            volume: Some("12".to_string()),
+            pages: Some("1-9".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_conflict_thresholds() {
+        let base = openalex();
+        assert_eq!(conflict(&base, &mislabelled(Some(2014), &["A. Vaswani"])), Some(Conflict::Years(2017, 2014)));
+        // Two years apart is still print vs preprint
+        assert_eq!(conflict(&base, &mislabelled(Some(2015), &["A. Vaswani"])), None);
+        assert_eq!(conflict(&base, &mislabelled(Some(2017), &["J. Smith", "K. Lee"])), Some(Conflict::Authors));
+        // Missing data isn't a disagreement
+        assert_eq!(conflict(&base, &mislabelled(None, &[])), None);
+        let truncated = PaperMetadata { authors_truncated: true, ..mislabelled(Some(2017), &["J. Smith"]) };
+        assert_eq!(conflict(&base, &truncated), None);
+    }
+
+    #[test]
+    fn test_conflicting_records_stay_unmerged_but_linked() {
+        let rival = mislabelled(Some(2013), &["J. Smith"]);
+        let rival_again = PaperMetadata { issue: Some("4".to_string()), ..mislabelled(Some(2013), &["John Smith"]) };
+        let merged = Merger::merge(vec![openalex(), rival, arxiv(), rival_again]);
+        assert_eq!(merged.len(), 1);
+        let paper = &merged[0];
+        // arXiv agrees with OpenAlex and merges as usual
+        assert_eq!(paper.year, Some(2017));
+        assert_eq!(paper.venue.as_deref(), Some("arXiv"));
+        assert_eq!(paper.pdf_url.as_deref(), Some("http://arxiv.org/pdf/1706.03762v7"));
+        // The rival reported twice is one linked record, merged with itself
+        assert_eq!(paper.conflicting_records.len(), 1);
+        assert_eq!(paper.conflicting_records[0].year, Some(2013));
+        assert_eq!(paper.conflicting_records[0].issue.as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn test_trusting_a_conflicting_record() {
+        let merged = Merger::merge(vec![PaperMetadata { pdf_url: Some("https://example.org/a.pdf".to_string()), is_oa: true, ..openalex() },
+                                        mislabelled(Some(2013), &["J. Smith"])]);
+        let records = records(&merged[0]);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.conflicting_records.is_empty()));
+        // The rival fills venue, volume and pages
+        assert_eq!(richest(&records), 1);
+        let chosen = trusted(records.clone(), 1);
+        assert_eq!(chosen.year, Some(2013));
+        // It had no PDF, so the listed one is downloaded
+        assert_eq!(chosen.pdf_url.as_deref(), Some("https://example.org/a.pdf"));
+        assert!(chosen.is_oa);
+        assert_eq!(trusted(records, 0).year, Some(2017));
+        // Ties go to the listed record
+        assert_eq!(richest(&[openalex(), openalex()]), 0);
+    }
+}
@@ -0,0 +1,190 @@
+// Upgrades persisted JSON documents to the current schema on load.
+// Each file kind has its own version counter; version 0 is the unversioned layout
+// written before schema_version existed. Add a step function per bump and never
+// edit an existing one, so files from any older release still load.
+use anyhow::{Result, anyhow};
+use serde_json::{Map, Value};
+
+pub const MANIFEST_VERSION: u32 = 1;
+pub const UNAVAILABLE_VERSION: u32 = 1;
+pub const METADATA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Manifest,
+    Unavailable,
+    Metadata,
+}
+
+impl DocumentKind {
+    fn file_name(self) -> &'static str {
+        match self {
+            DocumentKind::Manifest => "manifest.json",
+            DocumentKind::Unavailable => "unavailable.json",
+            DocumentKind::Metadata => "metadata.json",
+        }
+    }
+
+    fn current_version(self) -> u32 {
+        match self {
+            DocumentKind::Manifest => MANIFEST_VERSION,
+            DocumentKind::Unavailable => UNAVAILABLE_VERSION,
+            DocumentKind::Metadata => METADATA_VERSION,
+        }
+    }
+}
+
+pub fn schema_version(value: &Value) -> u32 {
+    value.get("schema_version").and_then(|v| v.as_u64()).map(|v| v as u32).unwrap_or(0)
+}
+
+/// Brings `value` up to the current schema for `kind`, one step at a time.
+/// Files from a newer release are rejected rather than guessed at.
+pub fn migrate(kind: DocumentKind, mut value: Value) -> Result<Value> {
+    let current = kind.current_version();
+    let mut version = schema_version(&value);
+    if version > current {
+        return Err(anyhow!(
+            "{} uses schema version {}, but this build only understands up to {}. \
+             It was written by a newer release; upgrade the tool or move the file aside.",
+            kind.file_name(), version, current
+        ));
+    }
+
+    while version < current {
+        value = match (kind, version) {
+            (DocumentKind::Manifest, 0) => manifest_v0_to_v1(value)?,
+            (DocumentKind::Unavailable, 0) => unavailable_v0_to_v1(value)?,
+            (DocumentKind::Metadata, 0) => metadata_v0_to_v1(value)?,
+            _ => return Err(anyhow!("No migration for {} from version {}", kind.file_name(), version)),
+        };
+        version += 1;
+        tracing::info!("Migrated {} to schema version {}", kind.file_name(), version);
+    }
+    Ok(value)
+}
+
+// v0 was a bare array of entries
+fn manifest_v0_to_v1(value: Value) -> Result<Value> {
+    match value {
+        Value::Array(entries) => {
+            let mut root = Map::new();
+            root.insert("schema_version".to_string(), Value::from(1));
+            root.insert("entries".to_string(), Value::Array(entries));
+            Ok(Value::Object(root))
+        }
+        other => Err(anyhow!("manifest.json v0 should be an array, found {}", type_name(&other))),
+    }
+}
+
+// v0 was the query-keyed tree at the top level
+fn unavailable_v0_to_v1(value: Value) -> Result<Value> {
+    match value {
+        Value::Object(tree) => {
+            let mut root = Map::new();
+            root.insert("schema_version".to_string(), Value::from(1));
+            root.insert("searches".to_string(), Value::Object(tree));
+            Ok(Value::Object(root))
+        }
+        other => Err(anyhow!("unavailable.json v0 should be an object, found {}", type_name(&other))),
+    }
+}
+
+// v1 only adds the version marker next to the paper fields
+fn metadata_v0_to_v1(value: Value) -> Result<Value> {
+    match value {
+        Value::Object(mut fields) => {
+            fields.insert("schema_version".to_string(), Value::from(1));
+            Ok(Value::Object(fields))
+        }
+        other => Err(anyhow!("metadata.json v0 should be an object, found {}", type_name(&other))),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::PaperMetadata;
+    use crate::layers::download::{ManifestFile, UnavailableFile, MetadataFile};
+
+    fn fixture(name: &str) -> Value {
+        let path = format!("{}/tests/fixtures/schema/{}", env!("CARGO_MANIFEST_DIR"), name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_manifest_v0_to_v1() {
+        let migrated = manifest_v0_to_v1(serde_json::json!([{"id": "a"}])).unwrap();
+        assert_eq!(migrated, serde_json::json!({"schema_version": 1, "entries": [{"id": "a"}]}));
+        assert!(manifest_v0_to_v1(serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_unavailable_v0_to_v1() {
+        let migrated = unavailable_v0_to_v1(serde_json::json!({"MIT": {"ML": []}})).unwrap();
+        assert_eq!(migrated, serde_json::json!({"schema_version": 1, "searches": {"MIT": {"ML": []}}}));
+        assert!(unavailable_v0_to_v1(serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn test_metadata_v0_to_v1() {
+        let migrated = metadata_v0_to_v1(serde_json::json!({"title": "T"})).unwrap();
+        assert_eq!(migrated, serde_json::json!({"title": "T", "schema_version": 1}));
+    }
+
+    #[test]
+    fn test_every_manifest_fixture_loads() {
+        for name in ["manifest_v0.json", "manifest_v1.json"] {
+            let value = migrate(DocumentKind::Manifest, fixture(name)).unwrap();
+            let file: ManifestFile = serde_json::from_value(value).unwrap();
+            assert_eq!(file.schema_version, MANIFEST_VERSION);
+            assert_eq!(file.entries.len(), 2, "{}", name);
+            assert_eq!(file.entries[0].title, "Attention Is All You Need");
+        }
+    }
+
+    #[test]
+    fn test_every_unavailable_fixture_loads() {
+        for name in ["unavailable_v0.json", "unavailable_v1.json"] {
+            let value = migrate(DocumentKind::Unavailable, fixture(name)).unwrap();
+            let file: UnavailableFile = serde_json::from_value(value).unwrap();
+            assert_eq!(file.schema_version, UNAVAILABLE_VERSION);
+            assert!(file.searches["Stanford University"]["ML"].is_array(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_every_metadata_fixture_loads() {
+        for name in ["metadata_v0.json", "metadata_v1.json"] {
+            let value = migrate(DocumentKind::Metadata, fixture(name)).unwrap();
+            let file: MetadataFile<PaperMetadata> = serde_json::from_value(value).unwrap();
+            assert_eq!(file.schema_version, METADATA_VERSION);
+            assert_eq!(file.paper.title, "Deep learning", "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected_with_guidance() {
+        let err = migrate(DocumentKind::Manifest, serde_json::json!({"schema_version": 99, "entries": []})).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("manifest.json"));
+        assert!(message.contains("newer release"));
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let value = serde_json::json!({"schema_version": 1, "entries": []});
+        assert_eq!(migrate(DocumentKind::Manifest, value.clone()).unwrap(), value);
+    }
+}
@@ -0,0 +1,146 @@
+// A local institutional mirror consulted before the paper's external PDF URL
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use crate::layers::PaperMetadata;
+use crate::layers::arxiv_id::ArxivId;
+use crate::layers::enrichment::normalize_doi;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalMirror {
+    /// A URL with `{doi}` and/or `{arxiv_id}` placeholders, e.g. `https://repo.example.edu/pdf/{doi}`.
+    UrlTemplate(String),
+    /// A directory of PDFs named after the DOI (`10.1234_abc.pdf`) or its SHA-256 (`<hex>.pdf`).
+    Directory(PathBuf),
+}
+
+/// Where a PDF is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PdfSource {
+    Url(String),
+    File(PathBuf),
+}
+
+impl PdfSource {
+    pub fn location(&self) -> String {
+        match self {
+            PdfSource::Url(url) => url.clone(),
+            PdfSource::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+impl LocalMirror {
+    /// `LOCAL_MIRROR` holds either an http(s) URL template or a directory path.
+    pub fn from_env() -> Option<Self> {
+        std::env::var("LOCAL_MIRROR").ok()
+            .map(|spec| spec.trim().to_string())
+            .filter(|spec| !spec.is_empty())
+            .map(|spec| Self::parse(&spec))
+    }
+
+    pub fn parse(spec: &str) -> Self {
+        if spec.starts_with("http://") || spec.starts_with("https://") {
+            LocalMirror::UrlTemplate(spec.to_string())
+        } else {
+            LocalMirror::Directory(PathBuf::from(spec))
+        }
+    }
+
+    /// The mirror's copy of the paper, if it could have one. URL templates can't be checked
+    /// without fetching, so they yield a candidate URL; directories are probed on disk.
+    pub fn locate(&self, paper: &PaperMetadata) -> Option<PdfSource> {
+        match self {
+            LocalMirror::UrlTemplate(template) => expand_template(template, paper).map(PdfSource::Url),
+            LocalMirror::Directory(dir) => probe_directory(dir, paper).map(PdfSource::File),
+        }
+    }
+}
+
+/// Fills `{doi}` (normalized, slashes kept) and `{arxiv_id}` (without version).
+/// Returns None when the template needs an identifier the paper doesn't have.
+pub fn expand_template(template: &str, paper: &PaperMetadata) -> Option<String> {
+    let mut url = template.to_string();
+    if url.contains("{doi}") {
+        let doi = paper.doi.as_deref().map(normalize_doi).filter(|d| !d.is_empty())?;
+        url = url.replace("{doi}", &doi);
+    }
+    if url.contains("{arxiv_id}") {
+        let arxiv = paper.arxiv_id.as_deref().and_then(ArxivId::parse)?;
+        url = url.replace("{arxiv_id}", &arxiv.base_id());
+    }
+    Some(url)
+}
+
+// "10.1234/ABC" -> "10.1234_abc"; the DOI prefix's dot is kept so names stay readable
+pub fn doi_file_stem(doi: &str) -> String {
+    normalize_doi(doi).replace(|c: char| !c.is_alphanumeric() && c != '.' && c != '-', "_")
+}
+
+pub fn doi_hash(doi: &str) -> String {
+    Sha256::digest(normalize_doi(doi).as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn probe_directory(dir: &Path, paper: &PaperMetadata) -> Option<PathBuf> {
+    let doi = paper.doi.as_deref()?;
+    [doi_file_stem(doi), doi_hash(doi)].into_iter()
+        .map(|stem| dir.join(format!("{}.pdf", stem)))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn paper(doi: Option<&str>, arxiv_id: Option<&str>) -> PaperMetadata {
+        PaperMetadata {
+            title: "Mirrored Paper".to_string(),
+            doi: doi.map(str::to_string),
+            arxiv_id: arxiv_id.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(LocalMirror::parse("https://repo.example.edu/pdf/{doi}"), LocalMirror::UrlTemplate("https://repo.example.edu/pdf/{doi}".to_string()));
+        assert_eq!(LocalMirror::parse("/srv/mirror"), LocalMirror::Directory(PathBuf::from("/srv/mirror")));
+    }
+
+    #[test]
+    fn test_template_expansion() {
+        let p = paper(Some("https://doi.org/10.1109/CVPR.2016.90"), Some("1512.03385v1"));
+        assert_eq!(expand_template("https://repo.example.edu/pdf/{doi}", &p).as_deref(), Some("https://repo.example.edu/pdf/10.1109/cvpr.2016.90"));
+        assert_eq!(expand_template("https://repo.example.edu/arxiv/{arxiv_id}.pdf", &p).as_deref(), Some("https://repo.example.edu/arxiv/1512.03385.pdf"));
+        assert_eq!(expand_template("https://repo.example.edu/static.pdf", &p).as_deref(), Some("https://repo.example.edu/static.pdf"));
+    }
+
+    #[test]
+    fn test_template_needs_its_identifiers() {
+        let arxiv_only = paper(None, Some("1512.03385"));
+        assert_eq!(expand_template("https://repo.example.edu/pdf/{doi}", &arxiv_only), None);
+        let doi_only = paper(Some("10.1/x"), None);
+        assert_eq!(expand_template("https://repo.example.edu/{doi}/{arxiv_id}", &doi_only), None);
+    }
+
+    #[test]
+    fn test_directory_probe_finds_doi_named_file() {
+        let dir = temp_dir("mirror-doi-named");
+        std::fs::write(dir.join("10.1038_nature14539.pdf"), b"%PDF").unwrap();
+        let mirror = LocalMirror::Directory(dir.clone());
+        assert_eq!(mirror.locate(&paper(Some("10.1038/Nature14539"), None)), Some(PdfSource::File(dir.join("10.1038_nature14539.pdf"))));
+        assert_eq!(mirror.locate(&paper(Some("10.1038/other"), None)), None);
+        assert_eq!(mirror.locate(&paper(None, Some("1512.03385"))), None);
+    }
+
+    #[test]
+    fn test_directory_probe_finds_hash_named_file() {
+        let dir = temp_dir("mirror-hash-named");
+        let hash = doi_hash("10.1038/nature14539");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, doi_hash("https://doi.org/10.1038/NATURE14539"));
+        std::fs::write(dir.join(format!("{}.pdf", hash)), b"%PDF").unwrap();
+        let mirror = LocalMirror::Directory(dir.clone());
+        assert_eq!(mirror.locate(&paper(Some("10.1038/nature14539"), None)), Some(PdfSource::File(dir.join(format!("{}.pdf", hash)))));
+    }
+}
@@ -1,21 +1,177 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, schemars::JsonSchema)]
 pub struct PaperMetadata {
     pub title: String,
+    // The title has replacement characters: a source sent bytes that could not be decoded
+    #[serde(default)]
+    pub garbled_title: bool,
+    // Other titles the sources gave this paper when merged (a display title, a full subtitle)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternate_titles: Vec<String>,
+    // Records that share an identifier with this one but disagree on its year or authors,
+    // kept unmerged so the user can pick which to trust (see merge::conflict)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicting_records: Vec<PaperMetadata>,
     pub authors: Vec<String>,
+    // Set when `authors` was cut down to the cap; `author_count` then holds the real number
+    #[serde(default)]
+    pub authors_truncated: bool,
+    #[serde(default)]
+    pub author_count: Option<usize>,
     pub year: Option<u32>,
+    // Years a source reported that failed validation, kept as given for provenance
+    #[serde(default)]
+    pub rejected_years: Vec<String>,
     pub doi: Option<String>,
     pub arxiv_id: Option<String>,
     pub semantic_scholar_id: Option<String>,
     pub open_alex_id: Option<String>,
+    pub core_id: Option<String>,
+    // "MED:34265844", "PPR:PPR402115": Europe PMC's source and ID
+    pub europe_pmc_id: Option<String>,
+    pub dblp_key: Option<String>,
+    // Other identifiers a source reported, by its own key (Semantic Scholar's "PubMed", "DBLP", ...)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub external_ids: HashMap<String, String>,
     pub venue: Option<String>,
+    // The source's work type, e.g. OpenAlex's "article" or "preprint"
+    #[serde(default)]
+    pub publication_type: Option<String>,
     pub abstract_text: Option<String>,
+    // As counted by the source that reported it; sources differ, so merging keeps the highest
+    #[serde(default)]
+    pub citation_count: Option<u32>,
     pub pdf_url: Option<String>,
     pub is_oa: bool,
     pub categories: Vec<String>,
+    // Bibliographic detail filled in by Crossref enrichment
+    pub volume: Option<String>,
+    pub issue: Option<String>,
+    pub pages: Option<String>,
+    pub publisher: Option<String>,
+    pub container_title: Option<String>,
+    pub version_hint: Option<VersionHint>,
+    // PDFs other sources linked when merged; `pdf_url` is the one downloaded (see --prefer-version)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternate_pdfs: Vec<PdfCopy>,
+    // License of the OA copy, when a source reports one (e.g. "cc-by")
+    pub license: Option<String>,
 }
 
+// Large collaborations list hundreds of authors; past this many only the first are kept
+pub const DEFAULT_MAX_AUTHORS: usize = 50;
+
+// Marks categories that came from Semantic Scholar's fields of study rather than arXiv
+pub const S2_FIELD_PREFIX: &str = "s2:";
+
+impl PaperMetadata {
+    /// Semantic Scholar fields of study, without the `s2:` prefix.
+    pub fn s2_fields(&self) -> impl Iterator<Item = &str> {
+        self.categories.iter().filter_map(|c| c.strip_prefix(S2_FIELD_PREFIX))
+    }
+
+    /// The first arXiv category (anything not tagged `s2:`); arXiv lists its primary category first.
+    pub fn primary_arxiv_category(&self) -> Option<&str> {
+        self.categories.iter().map(String::as_str).find(|c| !c.starts_with(S2_FIELD_PREFIX))
+    }
+
+    /// The number of authors the source reported, including any truncated away.
+    pub fn total_authors(&self) -> usize {
+        self.author_count.unwrap_or(self.authors.len())
+    }
+
+    /// Keeps the first `max` authors. An author matching `queried` past the cap is kept
+    /// as well, so searching for the 300th name of a collaboration still finds the paper.
+    pub fn truncate_authors(&mut self, max: usize, queried: Option<&str>) {
+        if self.authors.len() <= max {
+            return;
+        }
+        // Every word of the query must be a word of the name: "vaswani" matches "Ashish Vaswani"
+        let queried = queried.map(normalize::comparable_title).filter(|q| !q.is_empty());
+        let matches = |name: &String| queried.as_ref().is_some_and(|q| {
+            let name = normalize::comparable_title(name);
+            q.split(' ').all(|word| name.split(' ').any(|w| w == word))
+        });
+        let beyond_cap = if self.authors[..max].iter().any(matches) {
+            None
+        } else {
+            self.authors.iter().skip(max).position(matches).map(|i| max + i)
+        };
+
+        self.author_count = Some(self.authors.len());
+        self.authors_truncated = true;
+        if let Some(i) = beyond_cap {
+            self.authors.swap(max, i);
+            self.authors.truncate(max + 1);
+        } else {
+            self.authors.truncate(max);
+        }
+    }
+}
+
+/// Which version of the paper the PDF most likely is.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+pub enum VersionHint {
+    // Author manuscript from a repository (green OA); may differ from the version of record
+    AcceptedManuscript,
+    PublishedVersion,
+    // The version submitted for review (OpenAlex's "submittedVersion"), before peer review
+    Preprint,
+}
+
+/// A PDF link for the paper and the version it most likely is.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema)]
+pub struct PdfCopy {
+    pub url: String,
+    pub version_hint: Option<VersionHint>,
+}
+
+/// Returned (inside anyhow) when a pipeline stage stops because its CancellationToken fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Cancelled>().is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiscoverySource {
+    SemanticScholar,
+    Arxiv,
+    OpenAlex,
+    Core,
+    Dblp,
+    EuropePmc,
+}
+
+impl DiscoverySource {
+    /// The most results the API documents for one request.
+    pub fn max_results(self) -> usize {
+        match self {
+            // Bulk search (no title) pages through this many in batches of 1000; relevance
+            // search stops at the 1000 it serves
+            DiscoverySource::SemanticScholar => 10_000,
+            DiscoverySource::Arxiv => 2000,
+            // Fetched in pages of 200; the cap keeps a broad search from walking the whole corpus
+            DiscoverySource::OpenAlex => 2000,
+            DiscoverySource::Core => 100,
+            DiscoverySource::Dblp => 1000,
+            DiscoverySource::EuropePmc => 1000,
+        }
+    }
+}
+
+#[derive(Default)]
 pub struct DiscoveryQuery {
     pub title: Option<String>,
     pub author: Option<String>,
@@ -23,9 +179,128 @@ pub struct DiscoveryQuery {
     pub university: Option<String>,
     pub category: Option<String>,
     pub limit: usize,
+    pub year_from: Option<u32>,
+    pub year_to: Option<u32>,
+    pub publication_filter: Option<PublicationFilter>,
+    // Ask sources that support it to return only papers with a downloadable PDF
+    pub oa_only: bool,
+    // Overrides `limit` for individual sources
+    pub source_limits: HashMap<DiscoverySource, usize>,
+    // Cap on authors kept per candidate; None keeps them all
+    pub max_authors: Option<usize>,
+    pub arxiv_sort: ArxivSort,
+}
+
+impl DiscoveryQuery {
+    /// How many results to request from `source`: its own limit if set, else the global one.
+    /// Clamped to what the source returns in a single request.
+    pub fn limit_for(&self, source: DiscoverySource) -> usize {
+        self.source_limits.get(&source).copied().unwrap_or(self.limit).clamp(1, source.max_results())
+    }
+}
+
+/// The order arXiv returns its results in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ArxivSort {
+    /// Best match first
+    #[default]
+    Relevance,
+    /// Most recently submitted first
+    Newest,
+    /// Earliest submitted first
+    Oldest,
+    /// Most recently revised first
+    Updated,
+}
+
+impl ArxivSort {
+    /// arXiv's `sortBy` and `sortOrder`; None for its default, relevance.
+    pub fn params(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            ArxivSort::Relevance => None,
+            ArxivSort::Newest => Some(("submittedDate", "descending")),
+            ArxivSort::Oldest => Some(("submittedDate", "ascending")),
+            ArxivSort::Updated => Some(("lastUpdatedDate", "descending")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PublicationFilter {
+    /// Journal articles and conference papers
+    Published,
 }
 
 pub mod discovery;
 pub mod resolution;
 pub mod download;
 pub mod legality;
+pub mod enrichment;
+pub mod verify;
+pub mod integrity;
+pub mod filter;
+pub mod library;
+pub mod migrations;
+pub mod arxiv_id;
+pub mod diagnostics;
+pub mod normalize;
+pub mod merge;
+pub mod mirror;
+pub mod proxy;
+pub mod landing;
+pub mod encoding;
+pub mod http;
+pub mod estimate;
+pub mod readme;
+pub mod year;
+pub mod stats;
+pub mod dedupe;
+// Only the ONNX embedder drives the re-ranker, so without it just the tests do
+#[cfg_attr(not(feature = "semantic"), allow(dead_code))]
+pub mod rerank;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_authors(count: usize) -> PaperMetadata {
+        PaperMetadata {
+            title: "Observation of a new boson".to_string(),
+            authors: (1..=count).map(|i| format!("Author Number{}", i)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_truncate_authors_keeps_count() {
+        let mut paper = with_authors(500);
+        paper.truncate_authors(DEFAULT_MAX_AUTHORS, None);
+        assert_eq!(paper.authors.len(), 50);
+        assert_eq!(paper.authors[49], "Author Number50");
+        assert!(paper.authors_truncated);
+        assert_eq!(paper.total_authors(), 500);
+    }
+
+    #[test]
+    fn test_short_author_lists_are_untouched() {
+        let mut paper = with_authors(50);
+        paper.truncate_authors(DEFAULT_MAX_AUTHORS, None);
+        assert_eq!(paper.authors.len(), 50);
+        assert!(!paper.authors_truncated);
+        assert_eq!(paper.author_count, None);
+    }
+
+    #[test]
+    fn test_queried_author_past_the_cap_is_kept() {
+        let mut paper = with_authors(500);
+        paper.truncate_authors(10, Some("Number317"));
+        assert_eq!(paper.authors.len(), 11);
+        assert_eq!(paper.authors[10], "Author Number317");
+        assert_eq!(paper.total_authors(), 500);
+
+        // A queried author already inside the cap adds nothing
+        let mut paper = with_authors(500);
+        paper.truncate_authors(10, Some("Author Number3"));
+        assert_eq!(paper.authors.len(), 10);
+    }
+}
@@ -0,0 +1,304 @@
+// Folds typographic and non-ASCII variants in titles so an ASCII-typed query scores
+// against "Über Maßtheorie" or “curly-quoted” text the same way it would against plain text.
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TitleFolding {
+    // Spell out Greek letters (α -> alpha); off by default since it changes title length
+    pub transliterate_greek: bool,
+}
+
+// Letters NFKD leaves alone but that users type as ASCII digraphs
+fn expand_ligature(c: char) -> Option<&'static str> {
+    Some(match c {
+        'œ' => "oe", 'Œ' => "OE",
+        'æ' => "ae", 'Æ' => "AE",
+        'ß' => "ss", 'ẞ' => "SS",
+        'ø' => "o", 'Ø' => "O",
+        'ł' => "l", 'Ł' => "L",
+        'đ' => "d", 'Đ' => "D",
+        'þ' => "th", 'Þ' => "TH",
+        'ı' => "i",
+        _ => return None,
+    })
+}
+
+fn fold_punctuation(c: char) -> Option<char> {
+    Some(match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' | '\u{00B4}' => '\'',
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' | '\u{00AB}' | '\u{00BB}' => '"',
+        '\u{2010}'..='\u{2015}' | '\u{2212}' => '-',
+        '\u{00A0}' | '\u{2002}'..='\u{200A}' | '\u{202F}' => ' ',
+        _ => return None,
+    })
+}
+
+fn greek_name(c: char) -> Option<&'static str> {
+    const LOWER: [&str; 25] = [
+        "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota", "kappa",
+        "lambda", "mu", "nu", "xi", "omicron", "pi", "rho", "sigma", "sigma", "tau",
+        "upsilon", "phi", "chi", "psi", "omega",
+    ];
+    match c {
+        // ς (final sigma) sits between ρ and σ, hence the repeated "sigma"
+        'α'..='ω' => Some(LOWER[c as usize - 'α' as usize]),
+        'Α'..='Ρ' => Some(LOWER[c as usize - 'Α' as usize]),
+        'Σ'..='Ω' => Some(LOWER[c as usize - 'Α' as usize]),
+        'ϵ' => Some("epsilon"),
+        'ϑ' => Some("theta"),
+        'ϕ' => Some("phi"),
+        _ => None,
+    }
+}
+
+/// Applies compatibility decomposition (NFKD) and drops combining marks, expands ligatures
+/// (œ -> oe, ß -> ss), folds typographic quotes, dashes and spaces to ASCII, and optionally
+/// spells out Greek letters. Case is preserved.
+pub fn fold_title(title: &str, options: &TitleFolding) -> String {
+    let mut out = String::with_capacity(title.len());
+    // Greek is checked before decomposition so accented forms (ά) keep their base letter
+    for c in title.chars() {
+        if options.transliterate_greek {
+            let base = c.to_string().nfd().next().unwrap_or(c);
+            if let Some(name) = greek_name(base) {
+                out.push_str(name);
+                continue;
+            }
+        }
+        if let Some(expanded) = expand_ligature(c) {
+            out.push_str(expanded);
+            continue;
+        }
+        for d in std::iter::once(c).nfkd() {
+            if is_combining_mark(d) {
+                continue;
+            }
+            match (expand_ligature(d), fold_punctuation(d)) {
+                (Some(expanded), _) => out.push_str(expanded),
+                (None, Some(folded)) => out.push(folded),
+                (None, None) => out.push(d),
+            }
+        }
+    }
+    out
+}
+
+// Lowercased alphanumeric words joined by single spaces
+fn words(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Folded, lowercased, alphanumeric words joined by single spaces, for deciding whether two
+/// names (or plain titles) are the same text: "Attention is all you need." == "Attention Is All You Need".
+pub fn comparable_title(title: &str) -> String {
+    words(&fold_title(title, &TitleFolding::default()))
+}
+
+/// The family name in `name`, comparable across spellings: "A. Vaswani", "Ashish Vaswani" and
+/// "Vaswani, Ashish" all give "vaswani".
+pub fn surname(name: &str) -> Option<String> {
+    let family = match name.split_once(',') {
+        Some((family, _)) => family,
+        None => name,
+    };
+    comparable_title(family).split(' ').next_back().filter(|s| !s.is_empty()).map(|s| s.to_string())
+}
+
+/// `comparable_title` for titles as the sources send them: HTML entities are decoded and LaTeX
+/// markup is reduced to its text first, so "Sorting in $O(n \log n)$ Time", a line-wrapped
+/// arXiv title and "Graphs &amp; Trees" compare equal to their plain spellings. For matching
+/// only; the title shown to the user is left as it was.
+pub fn normalize_title(title: &str, options: &TitleFolding) -> String {
+    words(&fold_title(&strip_latex(&decode_entities(title)), options))
+}
+
+// What HTML-escaped titles carry: the XML five, non-breaking spaces and numeric references.
+// Anything else is left as typed.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..].find(';').filter(|end| *end <= 8).and_then(|end| {
+            let decoded = match &rest[1..=end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                name => {
+                    let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => name.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)?
+                }
+            };
+            Some((decoded, end + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Commands that only style their argument
+const LATEX_STYLE: [&str; 14] = [
+    "mathcal", "mathbf", "mathrm", "mathit", "mathbb", "mathsf", "mathtt", "mathfrak",
+    "textbf", "textit", "textrm", "texttt", "emph", "text",
+];
+
+// Accents, as in "Erd\H{o}s" and "R\'enyi"; the letter they sit on is kept
+fn is_latex_accent(command: &str) -> bool {
+    matches!(command, "'" | "\"" | "`" | "^" | "~" | "=" | "." | "H" | "c" | "v" | "u" | "k" | "r" | "d" | "b" | "t")
+}
+
+// Math delimiters and braces go (they are punctuation to `words` anyway), style commands keep
+// only their argument, accents keep their letter, and other commands become their name:
+// "$O(n \log n)$" -> "$O(n log n)$", "\mathcal{O}(1)" -> "{O}(1)".
+fn strip_latex(text: &str) -> String {
+    if !text.contains('\\') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let mut command = String::new();
+        while let Some(&next) = chars.peek().filter(|n| n.is_ascii_alphabetic()) {
+            command.push(next);
+            chars.next();
+        }
+        if command.is_empty() {
+            match chars.next() {
+                Some(symbol) if is_latex_accent(&symbol.to_string()) => command.push(symbol),
+                // \( \) \[ \] open and close math; \& \% \_ are escaped characters
+                Some('(' | ')' | '[' | ']') | None => continue,
+                Some(escaped) => {
+                    out.push(escaped);
+                    continue;
+                }
+            }
+        }
+        if is_latex_accent(&command) {
+            // The accented letter, with or without braces around it
+            if chars.peek() == Some(&'{') {
+                chars.next();
+            }
+            if let Some(letter) = chars.next() {
+                out.push(letter);
+            }
+            if chars.peek() == Some(&'}') {
+                chars.next();
+            }
+        } else if !LATEX_STYLE.contains(&command.as_str()) {
+            out.push(' ');
+            out.push_str(&command);
+            out.push(' ');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold(s: &str) -> String {
+        fold_title(s, &TitleFolding::default())
+    }
+
+    #[test]
+    fn test_diacritics_are_stripped() {
+        assert_eq!(fold("Über die Grundlagen der Mengenlehre"), "Uber die Grundlagen der Mengenlehre");
+        assert_eq!(fold("Réseaux de neurones élémentaires"), "Reseaux de neurones elementaires");
+        assert_eq!(fold("Erdős–Rényi random graphs"), "Erdos-Renyi random graphs");
+    }
+
+    #[test]
+    fn test_ligatures_are_expanded() {
+        assert_eq!(fold("Œuvres complètes de Laplace"), "OEuvres completes de Laplace");
+        assert_eq!(fold("Maßtheorie und Integrationstheorie"), "Masstheorie und Integrationstheorie");
+        assert_eq!(fold("Eﬃcient Estimation of Word Representations"), "Efficient Estimation of Word Representations");
+        assert_eq!(fold("Łukasiewicz logics"), "Lukasiewicz logics");
+    }
+
+    #[test]
+    fn test_quotes_and_dashes_are_folded() {
+        assert_eq!(fold("“Why Should I Trust You?”: Explaining the Predictions of Any Classifier"),
+                   "\"Why Should I Trust You?\": Explaining the Predictions of Any Classifier");
+        assert_eq!(fold("Don’t Stop Pretraining"), "Don't Stop Pretraining");
+        assert_eq!(fold("« L’apprentissage profond »"), "\" L'apprentissage profond \"");
+        assert_eq!(fold("Sequence\u{2011}to\u{2011}Sequence Learning"), "Sequence-to-Sequence Learning");
+    }
+
+    #[test]
+    fn test_greek_transliteration_is_opt_in() {
+        let title = "β-VAE: Learning Basic Visual Concepts";
+        assert_eq!(fold(title), title);
+        let greek = TitleFolding { transliterate_greek: true };
+        assert_eq!(fold_title(title, &greek), "beta-VAE: Learning Basic Visual Concepts");
+        assert_eq!(fold_title("Σ-protocols and λ-calculus", &greek), "sigma-protocols and lambda-calculus");
+        assert_eq!(fold_title("ε-greedy exploration, Ω(n log n)", &greek), "epsilon-greedy exploration, omega(n log n)");
+    }
+
+    fn normalized(s: &str) -> String {
+        normalize_title(s, &TitleFolding::default())
+    }
+
+    #[test]
+    fn test_normalize_line_wrapped_arxiv_titles() {
+        assert_eq!(normalized("BERT: Pre-training of Deep Bidirectional Transformers for Language\n  Understanding"),
+                   "bert pre training of deep bidirectional transformers for language understanding");
+        assert_eq!(normalized("  ATTENTION IS ALL\tYOU NEED.  "), normalized("Attention Is All You Need"));
+    }
+
+    #[test]
+    fn test_normalize_decodes_html_entities() {
+        assert_eq!(normalized("Graphs &amp; Networks"), normalized("Graphs & Networks"));
+        assert_eq!(normalized("Don&#8217;t Stop Pretraining"), "don t stop pretraining");
+        assert_eq!(normalized("Caf&#xE9; &lt;Society&gt;"), "cafe society");
+        // Not an entity, so kept as typed
+        assert_eq!(normalized("R&D at scale; a survey"), "r d at scale a survey");
+    }
+
+    #[test]
+    fn test_normalize_strips_latex() {
+        assert_eq!(normalized("Sorting in $O(n \\log n)$ Time"), normalized("Sorting in O(n log n) time"));
+        assert_eq!(normalized("$\\mathcal{O}(1)$ Amortized Updates"), "o 1 amortized updates");
+        assert_eq!(normalized("Erd\\H{o}s--R\\'enyi graphs and \\emph{sparse} \\textbf{cuts}"), normalized("Erdős–Rényi graphs and sparse cuts"));
+        assert_eq!(normalized("The $\\beta$-VAE \\& \\(\\alpha\\)-divergences"), "the beta vae alpha divergences");
+    }
+
+    #[test]
+    fn test_surname_accepts_either_name_order() {
+        assert_eq!(surname("A. Vaswani").as_deref(), Some("vaswani"));
+        assert_eq!(surname("Vaswani, Ashish").as_deref(), Some("vaswani"));
+        assert_eq!(surname("Gödel, Kurt").as_deref(), Some("godel"));
+        assert_eq!(surname(" , "), None);
+    }
+
+    #[test]
+    fn test_plain_ascii_is_unchanged() {
+        assert_eq!(fold("Attention Is All You Need"), "Attention Is All You Need");
+    }
+}
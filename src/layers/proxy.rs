@@ -0,0 +1,105 @@
+// An institutional EZproxy, for closed papers the user's library subscribes to. Opt-in:
+// configured with EZPROXY_PREFIX and used only for runs with --via-proxy.
+use std::sync::Arc;
+use reqwest::cookie::Jar;
+use url::Url;
+use crate::layers::PaperMetadata;
+use crate::layers::enrichment::normalize_doi;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstitutionalProxy {
+    // e.g. "https://proxy.example.edu/login?url="; the target URL is appended as is
+    pub prefix: String,
+    // A logged-in session's cookie header ("ezproxy=abc; other=1"), from PROXY_COOKIE
+    pub cookie: Option<String>,
+}
+
+impl InstitutionalProxy {
+    /// `EZPROXY_PREFIX` must be an http(s) URL; `PROXY_COOKIE` is optional.
+    pub fn from_env() -> Option<Self> {
+        let prefix = std::env::var("EZPROXY_PREFIX").ok()?;
+        let cookie = std::env::var("PROXY_COOKIE").ok();
+        Self::new(&prefix, cookie.as_deref())
+    }
+
+    pub fn new(prefix: &str, cookie: Option<&str>) -> Option<Self> {
+        let prefix = prefix.trim();
+        let url = Url::parse(prefix).ok().filter(|u| matches!(u.scheme(), "http" | "https"))?;
+        url.host_str()?;
+        Some(Self {
+            prefix: prefix.to_string(),
+            cookie: cookie.map(str::trim).filter(|c| !c.is_empty()).map(str::to_string),
+        })
+    }
+
+    /// The paper's PDF URL, or its DOI landing page, routed through the proxy.
+    pub fn rewrite(&self, paper: &PaperMetadata) -> Option<String> {
+        let target = paper.pdf_url.clone().or_else(|| {
+            paper.doi.as_deref().map(normalize_doi).filter(|d| !d.is_empty()).map(|doi| format!("https://doi.org/{}", doi))
+        })?;
+        Some(format!("{}{}", self.prefix, target))
+    }
+
+    /// A cookie jar holding the session cookie for the proxy host and its subdomains. EZproxy
+    /// rewrites publisher hosts under its own (`www-nature-com.proxy.example.edu`), so those get
+    /// the cookie; other hosts of the institution's domain don't. Cookies EZproxy sets along the
+    /// way are kept in the same jar.
+    pub fn cookie_jar(&self) -> Arc<Jar> {
+        let jar = Arc::new(Jar::default());
+        let Some(cookie) = &self.cookie else { return jar };
+        let Ok(url) = Url::parse(&self.prefix) else { return jar };
+        // An IP address can't be a cookie domain; the cookie stays on that host alone
+        let domain = match url.host() {
+            Some(url::Host::Domain(host)) => Some(host.to_string()),
+            _ => None,
+        };
+        for pair in cookie.split(';').map(str::trim).filter(|p| p.contains('=')) {
+            let cookie = match &domain {
+                Some(domain) => format!("{}; Domain={}; Path=/", pair, domain),
+                None => format!("{}; Path=/", pair),
+            };
+            jar.add_cookie_str(&cookie, &url);
+        }
+        jar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        let proxy = InstitutionalProxy::new("https://login.proxy.example.edu/login?url=", None).unwrap();
+        let paper = PaperMetadata { doi: Some("https://doi.org/10.1234/CLOSED".to_string()), ..Default::default() };
+        assert_eq!(proxy.rewrite(&paper).as_deref(), Some("https://login.proxy.example.edu/login?url=https://doi.org/10.1234/closed"));
+
+        let with_pdf = PaperMetadata { pdf_url: Some("https://publisher.example.com/pdf/1?x=1".to_string()), ..paper };
+        assert_eq!(proxy.rewrite(&with_pdf).as_deref(), Some("https://login.proxy.example.edu/login?url=https://publisher.example.com/pdf/1?x=1"));
+        assert_eq!(proxy.rewrite(&PaperMetadata::default()), None);
+        assert_eq!(InstitutionalProxy::new("login.proxy.example.edu", None), None);
+    }
+
+    #[test]
+    fn test_cookie_covers_rewritten_publisher_hosts() {
+        use reqwest::cookie::CookieStore;
+        let proxy = InstitutionalProxy::new("https://proxy.example.edu/login?url=", Some("ezproxy=abc; lib=2")).unwrap();
+        let jar = proxy.cookie_jar();
+        for host in ["proxy.example.edu", "www-nature-com.proxy.example.edu"] {
+            let url = Url::parse(&format!("https://{}/articles/x.pdf", host)).unwrap();
+            let mut sent: Vec<String> = jar.cookies(&url).unwrap().to_str().unwrap().split("; ").map(str::to_string).collect();
+            sent.sort();
+            assert_eq!(sent, ["ezproxy=abc", "lib=2"], "{}", host);
+        }
+        assert!(jar.cookies(&Url::parse("https://www.nature.com/articles/x.pdf").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_cookie_stays_off_sibling_hosts() {
+        use reqwest::cookie::CookieStore;
+        let jar = InstitutionalProxy::new("https://proxy.university.edu/login?url=", Some("ezproxy=abc")).unwrap().cookie_jar();
+        for sibling in ["https://mail.university.edu/", "https://university.edu/", "https://other.proxy-university.edu/"] {
+            assert!(jar.cookies(&Url::parse(sibling).unwrap()).is_none(), "{}", sibling);
+        }
+    }
+}
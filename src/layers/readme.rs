@@ -0,0 +1,254 @@
+// A human-readable cover note written next to a downloaded paper
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use crate::layers::{DiscoveryQuery, PaperMetadata, VersionHint};
+use crate::layers::legality::{AccessBasis, LegalityChecker};
+
+pub const README_FILE: &str = "README.md";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryEntry {
+    pub name: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+pub struct PaperReadme<'a> {
+    pub paper: &'a PaperMetadata,
+    pub query: &'a str,
+    // The URL or mirror path the PDF was read from
+    pub source: &'a str,
+    pub from_mirror: bool,
+    pub access: AccessBasis,
+    pub files: &'a [InventoryEntry],
+}
+
+/// "title \"Attention\", author \"Vaswani\"": the search terms, in the order the CLI lists them.
+pub fn describe_query(query: &DiscoveryQuery) -> String {
+    let terms: Vec<String> = [
+        ("title", &query.title),
+        ("author", &query.author),
+        ("category", &query.category),
+        ("university", &query.university),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.as_ref().map(|v| format!("{} \"{}\"", name, v)))
+    .collect();
+    terms.join(", ")
+}
+
+/// Every file in `dir` except the README itself, sorted by name, with its SHA-256.
+pub fn inventory(dir: &Path) -> Result<Vec<InventoryEntry>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == README_FILE || !entry.file_type()?.is_file() {
+            continue;
+        }
+        let bytes = std::fs::read(entry.path())?;
+        files.push(InventoryEntry {
+            name,
+            bytes: bytes.len() as u64,
+            sha256: Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect(),
+        });
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// Writes the cover note for the paper whose files are in `dir`, listing every file there now.
+pub fn write(dir: &Path, paper: &PaperMetadata, query: &str, source: &str, from_mirror: bool, access: AccessBasis) -> Result<()> {
+    let files = inventory(dir)?;
+    let rendered = PaperReadme { paper, query, source, from_mirror, access, files: &files }.render();
+    std::fs::write(dir.join(README_FILE), rendered)?;
+    Ok(())
+}
+
+/// Whether `dir` has a README older than its metadata.json, so the note no longer matches it.
+pub fn is_stale(dir: &Path) -> bool {
+    let modified = |name: &str| std::fs::metadata(dir.join(name)).and_then(|m| m.modified()).ok();
+    matches!((modified(README_FILE), modified("metadata.json")), (Some(readme), Some(metadata)) if metadata > readme)
+}
+
+// Backslash-escapes Markdown syntax and folds newlines, so metadata can't change the layout
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        for c in word.chars() {
+            if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '!') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn or_unknown(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "Unknown".to_string())
+}
+
+impl PaperReadme<'_> {
+    pub fn render(&self) -> String {
+        let paper = self.paper;
+        let mut out = format!("# {}\n\n", escape(&paper.title));
+
+        let mut authors = if paper.authors.is_empty() {
+            "Unknown".to_string()
+        } else {
+            paper.authors.iter().map(|a| escape(a)).collect::<Vec<_>>().join(", ")
+        };
+        if paper.authors_truncated {
+            authors.push_str(&format!(", et al. ({} authors in total)", paper.total_authors()));
+        }
+        out.push_str(&format!("- **Authors:** {}\n", authors));
+        out.push_str(&format!("- **Venue:** {}\n", or_unknown(paper.venue.as_deref().map(escape))));
+        out.push_str(&format!("- **Year:** {}\n", or_unknown(paper.year.map(|y| y.to_string()))));
+        if let Some(doi) = &paper.doi {
+            out.push_str(&format!("- **DOI:** {}\n", escape(doi)));
+        }
+        if let Some(arxiv_id) = &paper.arxiv_id {
+            out.push_str(&format!("- **arXiv:** {}\n", escape(arxiv_id)));
+        }
+
+        out.push_str("\n## Abstract\n\n");
+        match paper.abstract_text.as_deref().map(escape).filter(|a| !a.is_empty()) {
+            Some(abstract_text) => out.push_str(&format!("{}\n", abstract_text)),
+            None => out.push_str("_No abstract available._\n"),
+        }
+
+        out.push_str("\n## Provenance\n\n");
+        let query = if self.query.is_empty() { "Unknown".to_string() } else { escape(self.query) };
+        out.push_str(&format!("- **Found by:** {}\n", query));
+        let origin = if self.from_mirror { "local mirror" } else { "external download" };
+        out.push_str(&format!("- **Downloaded from:** `{}` ({})\n", self.source.replace('`', "%60"), origin));
+        out.push_str(&format!("- **Legality:** {}\n", verdict(paper, self.access)));
+
+        out.push_str("\n## Files\n\n| File | Bytes | SHA-256 |\n| --- | ---: | --- |\n");
+        for file in self.files {
+            out.push_str(&format!("| {} | {} | `{}` |\n", escape(&file.name), file.bytes, file.sha256));
+        }
+        out
+    }
+}
+
+fn verdict(paper: &PaperMetadata, access: AccessBasis) -> String {
+    match access {
+        AccessBasis::InstitutionalEntitlement => {
+            return "Not Open Access; downloaded through the institutional proxy under the library's subscription".to_string();
+        }
+        AccessBasis::Green => {
+            return "Closed at the publisher; downloaded from an open copy on a preprint or open proceedings host".to_string();
+        }
+        AccessBasis::OpenAccess => {}
+    }
+    if !LegalityChecker::is_legally_downloadable(paper) {
+        return "Not reported as Open Access".to_string();
+    }
+    let version = match paper.version_hint {
+        Some(VersionHint::AcceptedManuscript) => " (accepted manuscript; may differ from the version of record)",
+        Some(VersionHint::PublishedVersion) => " (published version)",
+        Some(VersionHint::Preprint) => " (preprint; may differ from the version of record)",
+        None => "",
+    };
+    format!("Open Access{}", version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(name: &str) -> String {
+        std::fs::read_to_string(format!("{}/tests/fixtures/readme/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    fn files() -> Vec<InventoryEntry> {
+        vec![
+            InventoryEntry { name: "metadata.json".to_string(), bytes: 812, sha256: "a".repeat(64) },
+            InventoryEntry { name: "paper.pdf".to_string(), bytes: 2215244, sha256: "b".repeat(64) },
+        ]
+    }
+
+    #[test]
+    fn test_full_readme_snapshot() {
+        let paper = PaperMetadata {
+            title: "Attention Is All You Need".to_string(),
+            authors: vec!["Ashish Vaswani".to_string(), "Noam Shazeer".to_string()],
+            year: Some(2017),
+            doi: Some("10.48550/arXiv.1706.03762".to_string()),
+            arxiv_id: Some("1706.03762".to_string()),
+            venue: Some("Neural Information Processing Systems".to_string()),
+            abstract_text: Some("The dominant sequence transduction models\nare based on complex recurrent networks.".to_string()),
+            is_oa: true,
+            version_hint: Some(VersionHint::PublishedVersion),
+            ..Default::default()
+        };
+        let query = DiscoveryQuery { title: Some("Attention Is All You Need".to_string()), author: Some("Vaswani".to_string()), ..Default::default() };
+        let files = files();
+        let readme = PaperReadme {
+            paper: &paper,
+            query: &describe_query(&query),
+            source: "http://arxiv.org/pdf/1706.03762v7",
+            from_mirror: false,
+            access: AccessBasis::OpenAccess,
+            files: &files,
+        };
+        assert_eq!(readme.render(), snapshot("full.md"));
+    }
+
+    #[test]
+    fn test_minimal_readme_snapshot_escapes_markdown() {
+        let paper = PaperMetadata {
+            title: "On *bold* claims_and [links](http://x) # | tables".to_string(),
+            is_oa: true,
+            version_hint: Some(VersionHint::AcceptedManuscript),
+            ..Default::default()
+        };
+        let files = files();
+        let readme = PaperReadme {
+            paper: &paper,
+            query: "",
+            source: "/srv/mirror/10.1_x.pdf",
+            from_mirror: true,
+            access: AccessBasis::OpenAccess,
+            files: &files,
+        };
+        assert_eq!(readme.render(), snapshot("minimal.md"));
+    }
+
+    #[test]
+    fn test_truncated_authors_end_with_et_al() {
+        let mut paper = PaperMetadata {
+            title: "Observation of a new boson".to_string(),
+            authors: (1..=3000).map(|i| format!("Physicist {}", i)).collect(),
+            ..Default::default()
+        };
+        paper.truncate_authors(2, None);
+        let readme = PaperReadme { paper: &paper, query: "", source: "x", from_mirror: false, access: AccessBasis::OpenAccess, files: &[] };
+        assert!(readme.render().contains("- **Authors:** Physicist 1, Physicist 2, et al. (3000 authors in total)\n"));
+    }
+
+    #[test]
+    fn test_open_copy_of_a_closed_paper() {
+        let paper = PaperMetadata { title: "Closed".to_string(), arxiv_id: Some("1905.12345".to_string()), ..Default::default() };
+        let readme = PaperReadme { paper: &paper, query: "", source: "https://arxiv.org/pdf/1905.12345", from_mirror: false, access: AccessBasis::Green, files: &[] };
+        assert!(readme.render().contains("- **Legality:** Closed at the publisher; downloaded from an open copy"));
+    }
+
+    #[test]
+    fn test_inventory_hashes_files_and_skips_readme() {
+        let dir = crate::test_support::temp_dir("readme-inventory");
+        std::fs::write(dir.join("paper.pdf"), b"abc").unwrap();
+        std::fs::write(dir.join(README_FILE), b"old").unwrap();
+
+        let files = inventory(&dir).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].bytes, 3);
+        assert_eq!(files[0].sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
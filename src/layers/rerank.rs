@@ -0,0 +1,326 @@
+// Optional re-ranking of the top candidates by embedding similarity, for queries that
+// paraphrase a title rather than quote it. The ONNX embedder needs `--features semantic`.
+use anyhow::Result;
+use std::path::Path;
+use crate::layers::PaperMetadata;
+use crate::layers::resolution::Similarity;
+
+/// Whether this build can load an embedding model.
+pub const SEMANTIC_ENABLED: bool = cfg!(feature = "semantic");
+
+pub const DEFAULT_TOP_N: usize = 50;
+pub const DEFAULT_BATCH_SIZE: usize = 16;
+// Share of the blended score taken by cosine similarity; the rest is the match score
+pub const DEFAULT_SEMANTIC_WEIGHT: f64 = 0.7;
+
+pub trait Embedder {
+    /// One vector per text, all of the same length.
+    fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+pub struct SemanticReranker<E: Embedder> {
+    embedder: E,
+    top_n: usize,
+    batch_size: usize,
+    semantic_weight: f64,
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 { 0.0 } else { dot / denominator }
+}
+
+fn embedding_text(paper: &PaperMetadata) -> String {
+    match paper.abstract_text.as_deref().filter(|a| !a.is_empty()) {
+        Some(abstract_text) => format!("{}. {}", paper.title, abstract_text),
+        None => paper.title.clone(),
+    }
+}
+
+impl<E: Embedder> SemanticReranker<E> {
+    pub fn new(embedder: E) -> Self {
+        Self {
+            embedder,
+            top_n: DEFAULT_TOP_N,
+            batch_size: DEFAULT_BATCH_SIZE,
+            semantic_weight: DEFAULT_SEMANTIC_WEIGHT,
+        }
+    }
+
+    /// Only the first `top_n` candidates are embedded and re-ordered; the rest keep their place.
+    // The tuning builders are only set from tests for now; the CLI uses the defaults
+    #[allow(dead_code)]
+    pub fn with_top_n(mut self, top_n: usize) -> Self {
+        self.top_n = top_n;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_semantic_weight(mut self, semantic_weight: f64) -> Self {
+        self.semantic_weight = semantic_weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Re-orders the head of `matches` (already sorted by match score) by the blended score.
+    /// Ties keep their title order, so the result is deterministic. On error `matches` is
+    /// left as it was.
+    pub fn rerank(&mut self, query: &str, matches: &mut Vec<(PaperMetadata, Similarity)>) -> Result<()> {
+        let n = matches.len().min(self.top_n);
+        if n < 2 {
+            return Ok(());
+        }
+        let query_vector = self.embedder.embed(&[query.to_string()])?
+            .into_iter().next()
+            .ok_or_else(|| anyhow::anyhow!("embedder returned no vector for the query"))?;
+
+        let texts: Vec<String> = matches[..n].iter().map(|(paper, _)| embedding_text(paper)).collect();
+        let mut vectors = Vec::with_capacity(n);
+        for batch in texts.chunks(self.batch_size) {
+            let embedded = self.embedder.embed(batch)?;
+            if embedded.len() != batch.len() {
+                return Err(anyhow::anyhow!("embedder returned {} vectors for {} texts", embedded.len(), batch.len()));
+            }
+            vectors.extend(embedded);
+        }
+
+        let mut scored: Vec<(f64, (PaperMetadata, Similarity))> = matches.drain(..n).zip(&vectors)
+            .map(|((paper, similarity), vector)| {
+                let semantic = cosine(&query_vector, vector);
+                let lexical = similarity.ratio();
+                let score = self.semantic_weight * semantic + (1.0 - self.semantic_weight) * lexical;
+                tracing::debug!("Rerank '{}': semantic {:.3}, lexical {:.3}, blended {:.3}", paper.title, semantic, lexical, score);
+                (score, (paper, similarity))
+            })
+            .collect();
+        // Stable, so equal scores stay in title order
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let reordered: Vec<_> = scored.into_iter().map(|(_, m)| m).collect();
+        matches.splice(0..0, reordered);
+        Ok(())
+    }
+}
+
+/// Re-ranks with the sentence-embedding model in `model_dir` (`model.onnx` and `tokenizer.json`).
+#[cfg(feature = "semantic")]
+pub fn rerank_with_model(model_dir: &Path, query: &str, matches: &mut Vec<(PaperMetadata, Similarity)>) -> Result<()> {
+    SemanticReranker::new(onnx::OnnxEmbedder::load(model_dir)?).rerank(query, matches)
+}
+
+#[cfg(not(feature = "semantic"))]
+pub fn rerank_with_model(_model_dir: &Path, _query: &str, _matches: &mut Vec<(PaperMetadata, Similarity)>) -> Result<()> {
+    Err(anyhow::anyhow!("compiled without feature `semantic` (rebuild with --features semantic)"))
+}
+
+#[cfg(feature = "semantic")]
+mod onnx {
+    use anyhow::{Result, anyhow};
+    use ort::session::Session;
+    use ort::value::Tensor;
+    use std::path::Path;
+    use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+    use super::Embedder;
+
+    // Titles plus abstracts rarely need more; longer inputs are cut
+    const MAX_TOKENS: usize = 256;
+
+    pub struct OnnxEmbedder {
+        session: Session,
+        tokenizer: Tokenizer,
+        wants_token_types: bool,
+    }
+
+    impl OnnxEmbedder {
+        pub fn load(dir: &Path) -> Result<Self> {
+            let session = Session::builder()?.commit_from_file(dir.join("model.onnx"))?;
+            let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+                .map_err(|e| anyhow!("Failed to load {:?}: {}", dir.join("tokenizer.json"), e))?;
+            tokenizer.with_padding(Some(PaddingParams::default()));
+            tokenizer.with_truncation(Some(TruncationParams { max_length: MAX_TOKENS, ..Default::default() }))
+                .map_err(|e| anyhow!("Invalid truncation settings: {}", e))?;
+            let wants_token_types = session.inputs().iter().any(|input| input.name() == "token_type_ids");
+            tracing::info!("Loaded embedding model from {:?}", dir);
+            Ok(Self { session, tokenizer, wants_token_types })
+        }
+    }
+
+    impl Embedder for OnnxEmbedder {
+        fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            let encodings = self.tokenizer.encode_batch(texts.to_vec(), true)
+                .map_err(|e| anyhow!("Tokenization failed: {}", e))?;
+            let batch = encodings.len();
+            let seq = encodings.first().map(|e| e.get_ids().len()).unwrap_or(0);
+            let flatten = |f: &dyn Fn(&tokenizers::Encoding) -> &[u32]| -> Vec<i64> {
+                encodings.iter().flat_map(|e| f(e).iter().map(|v| *v as i64)).collect()
+            };
+            let ids = flatten(&|e| e.get_ids());
+            let mask = flatten(&|e| e.get_attention_mask());
+            let types = flatten(&|e| e.get_type_ids());
+
+            let ids_tensor = Tensor::from_array(([batch, seq], ids))?;
+            let mask_tensor = Tensor::from_array(([batch, seq], mask.clone()))?;
+            let outputs = if self.wants_token_types {
+                let types_tensor = Tensor::from_array(([batch, seq], types))?;
+                self.session.run(ort::inputs!["input_ids" => ids_tensor, "attention_mask" => mask_tensor, "token_type_ids" => types_tensor])?
+            } else {
+                self.session.run(ort::inputs!["input_ids" => ids_tensor, "attention_mask" => mask_tensor])?
+            };
+            let (shape, data) = outputs[0].try_extract_tensor::<f32>()?;
+
+            match shape.len() {
+                // Already pooled: [batch, hidden]
+                2 => Ok(data.chunks(shape[1] as usize).map(|v| v.to_vec()).collect()),
+                // Token embeddings [batch, seq, hidden]: mean over the unmasked tokens
+                3 => {
+                    let hidden = shape[2] as usize;
+                    Ok((0..batch).map(|b| {
+                        let mut pooled = vec![0f32; hidden];
+                        let mut count = 0f32;
+                        for t in 0..seq {
+                            if mask[b * seq + t] == 0 {
+                                continue;
+                            }
+                            let offset = (b * seq + t) * hidden;
+                            for (p, v) in pooled.iter_mut().zip(&data[offset..offset + hidden]) {
+                                *p += v;
+                            }
+                            count += 1.0;
+                        }
+                        pooled.iter().map(|p| p / count.max(1.0)).collect()
+                    }).collect())
+                }
+                _ => Err(anyhow!("unexpected embedding output shape {:?}", shape)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bag-of-words vectors over a fixed vocabulary, with synonyms sharing a slot
+    struct StubEmbedder {
+        batches: Vec<usize>,
+        fail: bool,
+    }
+
+    const VOCAB: [&[&str]; 6] = [
+        &["transformer", "transformers", "attention"],
+        &["sparse", "sparsity"],
+        &["fast", "faster", "efficient", "speed"],
+        &["image", "images", "vision"],
+        &["protein", "folding"],
+        &["graph", "graphs"],
+    ];
+
+    impl Embedder for StubEmbedder {
+        fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            if self.fail {
+                return Err(anyhow::anyhow!("model crashed"));
+            }
+            self.batches.push(texts.len());
+            Ok(texts.iter().map(|text| {
+                let lower = text.to_lowercase();
+                let words: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).collect();
+                VOCAB.iter().map(|slot| words.iter().filter(|w| slot.contains(w)).count() as f32).collect()
+            }).collect())
+        }
+    }
+
+    fn stub() -> StubEmbedder {
+        StubEmbedder { batches: Vec::new(), fail: false }
+    }
+
+    fn candidate(title: &str, similarity: f64) -> (PaperMetadata, Similarity) {
+        (PaperMetadata { title: title.to_string(), ..Default::default() }, Similarity::from_ratio(similarity))
+    }
+
+    fn titles(matches: &[(PaperMetadata, Similarity)]) -> Vec<&str> {
+        matches.iter().map(|(p, _)| p.title.as_str()).collect()
+    }
+
+    fn sparse_attention_matches() -> Vec<(PaperMetadata, Similarity)> {
+        // Title similarity puts the unrelated papers first
+        vec![
+            candidate("Image Segmentation at Scale", 0.34),
+            candidate("Protein Folding with Graphs", 0.33),
+            candidate("Efficient Sparse Transformers", 0.26),
+        ]
+    }
+
+    #[test]
+    fn test_paraphrase_moves_to_the_top() {
+        let mut matches = sparse_attention_matches();
+        SemanticReranker::new(stub()).rerank("paper about making transformers faster with sparse attention", &mut matches).unwrap();
+        assert_eq!(titles(&matches)[0], "Efficient Sparse Transformers");
+    }
+
+    #[test]
+    fn test_embeddings_are_batched() {
+        let mut matches: Vec<_> = (0..7).map(|i| candidate(&format!("Paper {}", i), 1.0 - i as f64 / 10.0)).collect();
+        let mut reranker = SemanticReranker::new(stub()).with_batch_size(3);
+        reranker.rerank("query", &mut matches).unwrap();
+        // The query alone, then the candidates in batches of three
+        assert_eq!(reranker.embedder.batches, vec![1, 3, 3, 1]);
+    }
+
+    #[test]
+    fn test_only_top_n_is_reordered() {
+        let mut matches = sparse_attention_matches();
+        matches.push(candidate("Sparse Attention Is Fast", 0.0));
+        SemanticReranker::new(stub()).with_top_n(3).rerank("sparse transformers", &mut matches).unwrap();
+        assert_eq!(titles(&matches)[0], "Efficient Sparse Transformers");
+        assert_eq!(titles(&matches)[3], "Sparse Attention Is Fast");
+        assert_eq!(matches.len(), 4);
+    }
+
+    #[test]
+    fn test_zero_semantic_weight_keeps_lexical_order() {
+        let mut matches = sparse_attention_matches();
+        SemanticReranker::new(stub()).with_semantic_weight(0.0).rerank("sparse transformers", &mut matches).unwrap();
+        assert_eq!(titles(&matches), titles(&sparse_attention_matches()));
+    }
+
+    #[test]
+    fn test_blend_weighs_both_scores() {
+        let query = "sparse transformers";
+        // Semantically identical, so the closer title must win on the lexical share
+        let mut matches = vec![candidate("Transformers that are Sparse", 0.29), candidate("Sparse Transformers", 1.0)];
+        SemanticReranker::new(stub()).rerank(query, &mut matches).unwrap();
+        assert_eq!(titles(&matches), vec!["Sparse Transformers", "Transformers that are Sparse"]);
+    }
+
+    #[test]
+    fn test_ties_are_deterministic() {
+        let tied = || vec![candidate("Alpha", 0.7), candidate("Beta", 0.7), candidate("Gamma", 0.7)];
+        let mut first = tied();
+        let mut second = tied();
+        SemanticReranker::new(stub()).rerank("unrelated", &mut first).unwrap();
+        SemanticReranker::new(stub()).rerank("unrelated", &mut second).unwrap();
+        assert_eq!(titles(&first), vec!["Alpha", "Beta", "Gamma"]);
+        assert_eq!(titles(&first), titles(&second));
+    }
+
+    #[test]
+    fn test_failure_leaves_order_untouched() {
+        let mut matches = sparse_attention_matches();
+        let err = SemanticReranker::new(StubEmbedder { batches: Vec::new(), fail: true }).rerank("x", &mut matches);
+        assert!(err.is_err());
+        assert_eq!(titles(&matches), titles(&sparse_attention_matches()));
+    }
+
+    #[test]
+    fn test_cosine() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert_eq!(cosine(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+}
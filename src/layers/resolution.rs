@@ -1,29 +1,342 @@
-use strsim::levenshtein;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+use anyhow::{Result, anyhow};
+use rayon::prelude::*;
+use strsim::{levenshtein, normalized_levenshtein};
 use crate::layers::PaperMetadata;
+use crate::layers::normalize::{normalize_title, surname, TitleFolding};
+
+// Below this many candidates the thread-pool overhead outweighs the gain
+pub const PARALLEL_THRESHOLD: usize = 1000;
+
+/// How the matches are ordered, picked with --sort-by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SortBy {
+    /// Closest title first (the default)
+    #[default]
+    Similarity,
+    /// Most cited first
+    Citations,
+    /// Newest first
+    Year,
+}
+
+pub const DEFAULT_THRESHOLD_RATIO: f64 = 0.85;
+
+/// How close a candidate's title is to the query: one minus the edit distance over the longer
+/// title's length, so a typo costs a long title less than a short one. Kept in basis points so
+/// it orders and compares exactly. When the query names an author, the match score a
+/// candidate is ranked by blends this with how well its authors match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Similarity(u16);
+
+impl Similarity {
+    pub const EXACT: Similarity = Similarity(10_000);
+
+    pub fn from_ratio(ratio: f64) -> Self {
+        Self((ratio.clamp(0.0, 1.0) * 10_000.0).round() as u16)
+    }
+
+    // Rounded down, so only identical titles are EXACT
+    fn from_distance(distance: usize, query_len: usize, title_len: usize) -> Self {
+        let longest = query_len.max(title_len);
+        if longest == 0 {
+            return Self::EXACT;
+        }
+        Self((longest.saturating_sub(distance) * 10_000 / longest) as u16)
+    }
+
+    pub fn ratio(self) -> f64 {
+        self.0 as f64 / 10_000.0
+    }
+
+    /// Whole percent, rounded down so 100 means identical.
+    pub fn percent(self) -> u8 {
+        (self.0 / 100) as u8
+    }
+}
+
+/// Which candidates `resolve` keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threshold {
+    /// At most this many edits, whatever the titles' length (--threshold)
+    Distance(usize),
+    /// At least this similarity (--threshold-ratio, and the default)
+    Ratio(Similarity),
+}
+
+impl Threshold {
+    /// Keeps every candidate, for re-ranking that looks past the title.
+    pub const ANY: Threshold = Threshold::Distance(usize::MAX);
+
+    /// From the two flags: a ratio wins when both are given, and with neither the default
+    /// ratio applies.
+    pub fn pick(distance: Option<usize>, ratio: Option<f64>) -> Result<Self> {
+        match (distance, ratio) {
+            (_, Some(ratio)) if !(0.0..=1.0).contains(&ratio) => {
+                Err(anyhow!("--threshold-ratio must be between 0 and 1, got {}", ratio))
+            }
+            (_, Some(ratio)) => Ok(Threshold::Ratio(Similarity::from_ratio(ratio))),
+            (Some(distance), None) => Ok(Threshold::Distance(distance)),
+            (None, None) => Ok(Threshold::Ratio(Similarity::from_ratio(DEFAULT_THRESHOLD_RATIO))),
+        }
+    }
+
+    fn accepts(self, distance: usize, similarity: Similarity) -> bool {
+        match self {
+            Threshold::Distance(max) => distance <= max,
+            Threshold::Ratio(min) => similarity >= min,
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Threshold::Distance(max) => write!(f, "edit distance {}", max),
+            Threshold::Ratio(min) => write!(f, "{}% similarity", min.percent()),
+        }
+    }
+}
+
+// Edit distance and similarity between two normalized titles
+fn score(query: &str, title: &str) -> (usize, Similarity) {
+    let distance = levenshtein(query, title);
+    (distance, Similarity::from_distance(distance, query.chars().count(), title.chars().count()))
+}
+
+/// How close two titles are, normalized and scored as `Resolver` scores a query.
+pub fn title_similarity(a: &str, b: &str) -> Similarity {
+    let folding = TitleFolding::default();
+    normalized_title_similarity(&normalize_title(a, &folding), &normalize_title(b, &folding))
+}
+
+/// `title_similarity` for titles already through `normalize_title`, for callers that
+/// normalize each title once and compare it many times.
+pub fn normalized_title_similarity(a: &str, b: &str) -> Similarity {
+    score(a, b).1
+}
+
+// Share of a candidate's match score that comes from its authors, when the query names one
+const AUTHOR_WEIGHT: f64 = 0.25;
+
+// The author score of a candidate that lists no authors: neither confirmed nor ruled out
+const UNKNOWN_AUTHOR_SIMILARITY: f64 = 0.5;
+
+// How close a candidate surname must be to the queried one to count as the same person
+const AUTHOR_MATCH_RATIO: f64 = 0.8;
+
+/// What the candidates are matched against: the title searched for, and the author, if the
+/// search named one (--author).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResolutionQuery<'a> {
+    pub title: &'a str,
+    pub author: Option<&'a str>,
+}
+
+impl ResolutionQuery<'_> {
+    fn prepare(&self, folding: &TitleFolding) -> PreparedQuery {
+        PreparedQuery {
+            title: (!self.title.is_empty()).then(|| normalize_title(self.title, folding)),
+            surname: self.author.and_then(surname),
+        }
+    }
+
+    /// Whether the search named an author and none of the paper's is close to them. Papers
+    /// that list no authors aren't flagged, since nothing says they're wrong.
+    pub fn author_mismatch(&self, paper: &PaperMetadata) -> bool {
+        let Some(queried) = self.author.and_then(surname) else { return false };
+        author_similarity(&queried, paper).is_some_and(|s| s < Similarity::from_ratio(AUTHOR_MATCH_RATIO))
+    }
+}
+
+/// Whether two surnames are close enough to be the same person, as an `--author` search
+/// decides it: small spelling differences ("Muller", "Mueller") are allowed.
+pub fn same_surname(a: &str, b: &str) -> bool {
+    Similarity::from_ratio(normalized_levenshtein(a, b)) >= Similarity::from_ratio(AUTHOR_MATCH_RATIO)
+}
+
+// The query normalized once, for scoring many candidates
+struct PreparedQuery {
+    title: Option<String>,
+    surname: Option<String>,
+}
+
+impl PreparedQuery {
+    // The title's edit distance and similarity, which the threshold filters on, and the match
+    // score the candidates are ranked by: the title similarity blended with how well the
+    // closest author's surname matches the queried one
+    fn score(&self, paper: &PaperMetadata, folding: &TitleFolding) -> (usize, Similarity, Similarity) {
+        // A merged paper is as close as the best of the titles its sources gave it
+        let (distance, title) = match &self.title {
+            Some(query) => std::iter::once(&paper.title).chain(&paper.alternate_titles)
+                .map(|title| score(query, &normalize_title(title, folding)))
+                .max_by_key(|(distance, similarity)| (*similarity, Reverse(*distance)))
+                .expect("the primary title is always scored"),
+            None => (0, Similarity::EXACT),
+        };
+        let combined = match &self.surname {
+            Some(queried) => {
+                let author = author_similarity(queried, paper).map_or(UNKNOWN_AUTHOR_SIMILARITY, Similarity::ratio);
+                Similarity::from_ratio((1.0 - AUTHOR_WEIGHT) * title.ratio() + AUTHOR_WEIGHT * author)
+            }
+            None => title,
+        };
+        (distance, title, combined)
+    }
+}
+
+// The closest of the paper's author surnames to `queried`, or None when it lists no authors
+fn author_similarity(queried: &str, paper: &PaperMetadata) -> Option<Similarity> {
+    paper.authors.iter()
+        .filter_map(|a| surname(a))
+        .map(|candidate| Similarity::from_ratio(normalized_levenshtein(queried, &candidate)))
+        .max()
+}
 
 pub struct Resolver;
 
 impl Resolver {
-    pub fn resolve(query_title: &str, candidates: Vec<PaperMetadata>, threshold: usize) -> Vec<(PaperMetadata, usize)> {
-        if query_title.is_empty() {
-             // If no title provided (e.g. university search), return all candidates with 0 distance
-             return candidates.into_iter().map(|p| (p, 0)).collect();
+    /// Scores are computed on normalized titles (see `normalize_title`), so case, punctuation,
+    /// line breaks, entities and LaTeX cost nothing; the papers keep their original ones. The
+    /// threshold applies to the title alone; a queried author only moves candidates up or down.
+    pub fn resolve(query: &ResolutionQuery, candidates: Vec<PaperMetadata>, threshold: Threshold, folding: &TitleFolding) -> Vec<(PaperMetadata, Similarity)> {
+        if query.title.is_empty() && query.author.is_none() {
+             // If no title provided (e.g. university search), every candidate is an exact match
+             return candidates.into_iter().map(|p| (p, Similarity::EXACT)).collect();
         }
 
+        if candidates.len() >= PARALLEL_THRESHOLD {
+            Self::resolve_parallel(query, candidates, threshold, folding)
+        } else {
+            Self::resolve_serial(query, candidates, threshold, folding)
+        }
+    }
+
+    fn resolve_serial(query: &ResolutionQuery, candidates: Vec<PaperMetadata>, threshold: Threshold, folding: &TitleFolding) -> Vec<(PaperMetadata, Similarity)> {
+        let query = query.prepare(folding);
         candidates.into_iter()
-            .map(|p| {
-                let dist = levenshtein(query_title, &p.title);
-                tracing::debug!("Candidate: {} (Distance: {})", p.title, dist);
-                (p, dist)
+            .filter_map(|p| {
+                let (dist, title, similarity) = query.score(&p, folding);
+                tracing::debug!("Candidate: {} (Distance: {}, Similarity: {}%, Match: {}%)", p.title, dist, title.percent(), similarity.percent());
+                threshold.accepts(dist, title).then_some((p, similarity))
+            })
+            .collect()
+    }
+
+    fn resolve_parallel(query: &ResolutionQuery, candidates: Vec<PaperMetadata>, threshold: Threshold, folding: &TitleFolding) -> Vec<(PaperMetadata, Similarity)> {
+        tracing::debug!("Scoring {} candidates in parallel", candidates.len());
+        let query = query.prepare(folding);
+        // Indexed parallel iterators collect in input order, so output matches the serial path.
+        // Per-candidate logging stays at trace level to keep workers off the subscriber lock.
+        candidates.into_par_iter()
+            .filter_map(|p| {
+                let (dist, title, similarity) = query.score(&p, folding);
+                tracing::trace!("Candidate: {} (Distance: {}, Similarity: {}%, Match: {}%)", p.title, dist, title.percent(), similarity.percent());
+                threshold.accepts(dist, title).then_some((p, similarity))
             })
-            .filter(|(_, dist)| *dist <= threshold)
             .collect()
     }
 
-    pub fn sort_by_similarity(mut matches: Vec<(PaperMetadata, usize)>) -> Vec<(PaperMetadata, usize)> {
-        matches.sort_by_key(|(_, dist)| *dist);
+    /// Most similar first; ties keep their order.
+    pub fn sort_by_similarity(mut matches: Vec<(PaperMetadata, Similarity)>) -> Vec<(PaperMetadata, Similarity)> {
+        matches.sort_by_key(|(_, similarity)| Reverse(*similarity));
+        matches
+    }
+
+    /// Orders the matches by `key`, closest title first among ties. Papers without a citation
+    /// count or year go after every paper that has one.
+    pub fn sort_by(mut matches: Vec<(PaperMetadata, Similarity)>, key: SortBy) -> Vec<(PaperMetadata, Similarity)> {
+        match key {
+            SortBy::Similarity => matches.sort_by_key(|(_, similarity)| Reverse(*similarity)),
+            // Reverse(None) sorts after every Reverse(Some(_))
+            SortBy::Citations => matches.sort_by_key(|(p, similarity)| (Reverse(p.citation_count), Reverse(*similarity))),
+            SortBy::Year => matches.sort_by_key(|(p, similarity)| (Reverse(p.year), Reverse(*similarity))),
+        }
         matches
     }
+
+    /// `sort_by(matches, key)` cut to the first `k`, ranking matches as they come so at most
+    /// `k` are held at any time.
+    pub fn top_k(matches: impl IntoIterator<Item = (PaperMetadata, Similarity)>, key: SortBy, k: usize) -> Vec<(PaperMetadata, Similarity)> {
+        let mut top = TopK::new(k, key);
+        for (paper, similarity) in matches {
+            top.push(paper, similarity);
+        }
+        top.into_sorted()
+    }
+}
+
+struct Ranked {
+    // The citation count or year for those orders; None for similarity, and sorted last
+    primary: Option<u32>,
+    similarity: Similarity,
+    // Arrival order, so ties come out as a stable sort would leave them
+    seq: usize,
+    paper: PaperMetadata,
+}
+
+impl Ranked {
+    fn key(&self) -> (Reverse<Option<u32>>, Reverse<Similarity>, usize) {
+        (Reverse(self.primary), Reverse(self.similarity), self.seq)
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// The first `k` matches pushed so far in `SortBy` order. A max-heap on rank: the worst kept
+/// match is on top and is evicted when a better one arrives.
+pub struct TopK {
+    k: usize,
+    key: SortBy,
+    heap: BinaryHeap<Ranked>,
+    seq: usize,
+}
+
+impl TopK {
+    pub fn new(k: usize, key: SortBy) -> Self {
+        Self { k, key, heap: BinaryHeap::with_capacity(k.min(1024) + 1), seq: 0 }
+    }
+
+    pub fn push(&mut self, paper: PaperMetadata, similarity: Similarity) {
+        let primary = match self.key {
+            SortBy::Similarity => None,
+            SortBy::Citations => paper.citation_count,
+            SortBy::Year => paper.year,
+        };
+        let ranked = Ranked { primary, similarity, seq: self.seq, paper };
+        self.seq += 1;
+        if self.heap.len() < self.k {
+            self.heap.push(ranked);
+        } else if self.heap.peek().is_some_and(|worst| ranked < *worst) {
+            self.heap.pop();
+            self.heap.push(ranked);
+        }
+    }
+
+    /// Best first; equal ranks in the order they were pushed.
+    pub fn into_sorted(self) -> Vec<(PaperMetadata, Similarity)> {
+        self.heap.into_sorted_vec().into_iter().map(|r| (r.paper, r.similarity)).collect()
+    }
 }
 
 #[cfg(test)]
@@ -45,20 +358,25 @@ mod tests {
             pdf_url: None,
             is_oa: false,
             categories: vec![],
+            ..Default::default()
         }
     }
 
+    fn by_title(title: &str) -> ResolutionQuery<'_> {
+        ResolutionQuery { title, author: None }
+    }
+
     #[test]
     fn test_resolve_exact_match() {
         let p1 = create_dummy_paper("Quantum Computing");
         let p2 = create_dummy_paper("Introduction to ML");
         let candidates = vec![p1.clone(), p2.clone()];
 
-        let results = Resolver::resolve("Quantum Computing", candidates, 5);
+        let results = Resolver::resolve(&by_title("Quantum Computing"), candidates, Threshold::Distance(5), &TitleFolding::default());
         
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0.title, "Quantum Computing");
-        assert_eq!(results[0].1, 0); // Distance should be 0 for exact match
+        assert_eq!(results[0].1, Similarity::EXACT);
     }
 
     #[test]
@@ -67,11 +385,11 @@ mod tests {
         let candidates = vec![p1];
 
         // "Quantumm Computin" -> Typo
-        let results = Resolver::resolve("Quantumm Computin", candidates, 5);
+        let results = Resolver::resolve(&by_title("Quantumm Computin"), candidates, Threshold::Distance(5), &TitleFolding::default());
         
         assert_eq!(results.len(), 1);
-        assert!(results[0].1 > 0);
-        assert!(results[0].1 <= 5);
+        assert!(results[0].1 < Similarity::EXACT);
+        assert_eq!(results[0].1.percent(), 88);
     }
 
     #[test]
@@ -79,23 +397,240 @@ mod tests {
         let p1 = create_dummy_paper("Biology 101");
         let candidates = vec![p1];
 
-        let results = Resolver::resolve("Quantum Mechanics", candidates, 2);
+        let results = Resolver::resolve(&by_title("Quantum Mechanics"), candidates, Threshold::Distance(2), &TitleFolding::default());
         
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    fn test_parallel_matches_serial_ordering() {
+        let candidates: Vec<PaperMetadata> = (0..PARALLEL_THRESHOLD * 2)
+            .map(|i| create_dummy_paper(&format!("Quantum Computing {}", i % 37)))
+            .collect();
+
+        let serial = Resolver::resolve_serial(&by_title("Quantum Computing 3"), candidates.clone(), Threshold::Distance(2), &TitleFolding::default());
+        let parallel = Resolver::resolve_parallel(&by_title("Quantum Computing 3"), candidates, Threshold::Distance(2), &TitleFolding::default());
+
+        assert!(!serial.is_empty());
+        assert_eq!(serial.len(), parallel.len());
+        for ((sp, sd), (pp, pd)) in serial.iter().zip(parallel.iter()) {
+            assert_eq!(sp.title, pp.title);
+            assert_eq!(sd, pd);
+        }
+
+        let sorted_serial = Resolver::sort_by_similarity(serial);
+        let sorted_parallel = Resolver::sort_by_similarity(parallel);
+        let titles = |v: &[(PaperMetadata, Similarity)]| v.iter().map(|(p, _)| p.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(&sorted_serial), titles(&sorted_parallel));
+    }
+
+    #[test]
+    fn test_top_k_equals_sorting_everything() {
+        let candidates: Vec<PaperMetadata> = (0..20_000)
+            .map(|i| create_dummy_paper(&format!("Quantum Computing {}", (i * 7919) % 1000)))
+            .collect();
+        let folding = TitleFolding::default();
+        let batch = Resolver::sort_by_similarity(Resolver::resolve(&by_title("Quantum Computing 42"), candidates.clone(), Threshold::Distance(3), &folding));
+        for k in [0, 1, 25, batch.len(), batch.len() + 10] {
+            let top = Resolver::top_k(Resolver::resolve(&by_title("Quantum Computing 42"), candidates.clone(), Threshold::Distance(3), &folding), SortBy::Similarity, k);
+            let expected: Vec<_> = batch.iter().take(k).map(|(p, d)| (p.title.clone(), *d)).collect();
+            assert_eq!(top.into_iter().map(|(p, d)| (p.title, d)).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_top_k_equals_sorting_by_citations_and_year() {
+        let matches: Vec<_> = (0..2000u32).map(|i| {
+            let paper = PaperMetadata {
+                citation_count: (i % 7 != 0).then_some((i * 7919) % 300),
+                year: (i % 5 != 0).then_some(1990 + (i * 31) % 35),
+                ..create_dummy_paper(&format!("Paper {}", i))
+            };
+            (paper, Similarity::from_ratio(f64::from((i * 13) % 100) / 100.0))
+        }).collect();
+        for key in [SortBy::Citations, SortBy::Year] {
+            let batch = Resolver::sort_by(matches.clone(), key);
+            for k in [0, 1, 40, matches.len()] {
+                let top = Resolver::top_k(matches.clone(), key, k);
+                let titles = |v: &[(PaperMetadata, Similarity)]| v.iter().map(|(p, _)| p.title.clone()).collect::<Vec<_>>();
+                assert_eq!(titles(&top), titles(&batch[..k]), "{:?} top {}", key, k);
+            }
+        }
+    }
+
     #[test]
     fn test_sort_by_similarity() {
         let p1 = create_dummy_paper("A");
         let p2 = create_dummy_paper("B");
         
-        // Unsorted: dist 10 then dist 2
-        let matches = vec![(p1.clone(), 10), (p2.clone(), 2)];
+        // Unsorted: 50% then 90%
+        let matches = vec![(p1.clone(), Similarity::from_ratio(0.5)), (p2.clone(), Similarity::from_ratio(0.9))];
         
         let sorted = Resolver::sort_by_similarity(matches);
         
-        assert_eq!(sorted[0].1, 2);
-        assert_eq!(sorted[1].1, 10);
+        assert_eq!(sorted[0].1.percent(), 90);
+        assert_eq!(sorted[1].1.percent(), 50);
         assert_eq!(sorted[0].0.title, "B");
     }
+
+    // `dist` tenths below an exact match
+    fn ranked(title: &str, citations: Option<u32>, year: Option<u32>, dist: usize) -> (PaperMetadata, Similarity) {
+        (PaperMetadata { citation_count: citations, year, ..create_dummy_paper(title) }, Similarity::from_ratio(1.0 - dist as f64 / 10.0))
+    }
+
+    fn order(matches: Vec<(PaperMetadata, Similarity)>) -> Vec<String> {
+        matches.into_iter().map(|(p, _)| p.title).collect()
+    }
+
+    fn mixed() -> Vec<(PaperMetadata, Similarity)> {
+        vec![
+            ranked("uncounted close", None, None, 0),
+            ranked("cited old", Some(50), Some(2001), 3),
+            ranked("cited far", Some(900), Some(2020), 4),
+            ranked("cited near", Some(900), Some(2020), 1),
+            ranked("uncited", Some(0), Some(2024), 2),
+        ]
+    }
+
+    #[test]
+    fn test_sort_by_similarity_mode() {
+        assert_eq!(order(Resolver::sort_by(mixed(), SortBy::Similarity)),
+                   ["uncounted close", "cited near", "uncited", "cited old", "cited far"]);
+    }
+
+    #[test]
+    fn test_sort_by_citations_keeps_missing_counts_last() {
+        // A count of zero is still a count and ranks above a missing one
+        assert_eq!(order(Resolver::sort_by(mixed(), SortBy::Citations)),
+                   ["cited near", "cited far", "cited old", "uncited", "uncounted close"]);
+    }
+
+    #[test]
+    fn test_sort_by_year_keeps_missing_years_last() {
+        assert_eq!(order(Resolver::sort_by(mixed(), SortBy::Year)),
+                   ["uncited", "cited near", "cited far", "cited old", "uncounted close"]);
+    }
+
+    #[test]
+    fn test_resolve_folds_diacritics_and_ligatures() {
+        let candidates = vec![create_dummy_paper("Über Maßtheorie – eine Einführung")];
+        let results = Resolver::resolve(&by_title("Uber Masstheorie - eine Einfuhrung"), candidates, Threshold::Distance(0), &TitleFolding::default());
+        assert_eq!(results.len(), 1);
+        // The listing still shows the original title
+        assert_eq!(results[0].0.title, "Über Maßtheorie – eine Einführung");
+
+        let candidates = vec![create_dummy_paper("β-VAE: Learning Basic Visual Concepts")];
+        assert!(Resolver::resolve(&by_title("beta-VAE: Learning Basic Visual Concepts"), candidates.clone(), Threshold::Distance(1), &TitleFolding::default()).is_empty());
+        let greek = TitleFolding { transliterate_greek: true };
+        assert_eq!(Resolver::resolve(&by_title("beta-VAE: Learning Basic Visual Concepts"), candidates, Threshold::Distance(0), &greek).len(), 1);
+    }
+
+    #[test]
+    fn test_ratio_accepts_a_typo_in_a_long_title() {
+        let title = "Attention Is All You Need: A Comprehensive Survey of Transformer Architectures for Language Processing";
+        let candidates = vec![create_dummy_paper(title)];
+        let query = "Atention Is All You Need: A Comprehensve Surey of Transformer Architecures for Languge Procesing";
+        let folding = TitleFolding::default();
+        // Six edits over a hundred characters: too many for a fixed distance
+        assert!(Resolver::resolve(&by_title(query), candidates.clone(), Threshold::Distance(5), &folding).is_empty());
+        let results = Resolver::resolve(&by_title(query), candidates, Threshold::pick(None, None).unwrap(), &folding);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.percent() >= 90, "{:?}", results[0].1);
+    }
+
+    #[test]
+    fn test_ratio_rejects_a_different_short_title() {
+        let candidates = vec![create_dummy_paper("Graph Learning")];
+        let folding = TitleFolding::default();
+        // Within five edits of "Deep Learning", but a different subject
+        assert_eq!(Resolver::resolve(&by_title("Deep Learning"), candidates.clone(), Threshold::Distance(5), &folding).len(), 1);
+        assert!(Resolver::resolve(&by_title("Deep Learning"), candidates, Threshold::pick(None, None).unwrap(), &folding).is_empty());
+        // A one-letter typo in a short title is still fine
+        assert_eq!(Resolver::resolve(&by_title("Deep Leaning"), vec![create_dummy_paper("Deep Learning")], Threshold::pick(None, Some(0.85)).unwrap(), &folding).len(), 1);
+    }
+
+    #[test]
+    fn test_threshold_flags() {
+        let ratio = |r: f64| Threshold::Ratio(Similarity::from_ratio(r));
+        assert_eq!(Threshold::pick(None, None).unwrap(), ratio(DEFAULT_THRESHOLD_RATIO));
+        assert_eq!(Threshold::pick(Some(5), None).unwrap(), Threshold::Distance(5));
+        // The ratio wins when both are given
+        assert_eq!(Threshold::pick(Some(5), Some(0.9)).unwrap(), ratio(0.9));
+        assert!(Threshold::pick(None, Some(85.0)).is_err());
+        assert_eq!(Threshold::pick(None, None).unwrap().to_string(), "85% similarity");
+        assert_eq!(Threshold::Distance(5).to_string(), "edit distance 5");
+    }
+
+    #[test]
+    fn test_similarity_is_relative_to_length() {
+        assert_eq!(score("abcd", "abce"), (1, Similarity::from_ratio(0.75)));
+        assert_eq!(score("", ""), (0, Similarity::EXACT));
+        // Only identical titles show 100%
+        let (_, nearly) = score(&"a".repeat(1000), &format!("{}b", "a".repeat(999)));
+        assert_eq!(nearly.percent(), 99);
+    }
+
+    #[test]
+    fn test_formatting_costs_nothing() {
+        let arxiv = "Sorting Networks in $O(n \\log n)$ Depth:\n  Theory &amp; Practice";
+        let results = Resolver::resolve(&by_title("sorting networks in O(n log n) depth - theory & practice"),
+            vec![create_dummy_paper(arxiv)], Threshold::Distance(0), &TitleFolding::default());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, Similarity::EXACT);
+        // The listing still shows the title as the source sent it
+        assert_eq!(results[0].0.title, arxiv);
+    }
+
+    fn authored(title: &str, authors: &[&str]) -> PaperMetadata {
+        PaperMetadata { authors: authors.iter().map(|a| a.to_string()).collect(), ..create_dummy_paper(title) }
+    }
+
+    #[test]
+    fn test_right_authors_outrank_an_exact_title_by_others() {
+        let candidates = vec![
+            authored("Attention Is All You Need", &["J. Smith", "K. Lee"]),
+            authored("Attention Is All You Need Now", &["Ashish Vaswani", "Noam Shazeer"]),
+        ];
+        let query = ResolutionQuery { title: "Attention Is All You Need", author: Some("Vaswani, A.") };
+        let folding = TitleFolding::default();
+        let ranked = Resolver::sort_by_similarity(Resolver::resolve(&query, candidates.clone(), Threshold::Distance(15), &folding));
+        assert_eq!(order(ranked.clone()), ["Attention Is All You Need Now", "Attention Is All You Need"]);
+        assert!(ranked.iter().map(|(p, _)| query.author_mismatch(p)).eq([false, true]));
+
+        // Without an author the exact title wins
+        let ranked = Resolver::sort_by_similarity(Resolver::resolve(&by_title(query.title), candidates, Threshold::Distance(15), &folding));
+        assert_eq!(ranked[0].0.authors[0], "J. Smith");
+    }
+
+    #[test]
+    fn test_author_breaks_ties_without_filtering() {
+        let candidates = vec![authored("Deep Learning", &["Y. LeCun"]), authored("Deep Learning", &["Ian Goodfellow"]), create_dummy_paper("Deep Learning")];
+        // A typo in the surname still counts as the author
+        let query = ResolutionQuery { title: "Deep Learning", author: Some("Ian Goodfelow") };
+        let ranked = Resolver::top_k(Resolver::resolve(&query, candidates, Threshold::pick(None, None).unwrap(), &TitleFolding::default()), SortBy::Similarity, 10);
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].0.authors, ["Ian Goodfellow"]);
+        // Papers without authors get half the author credit and are never flagged
+        assert_eq!(ranked[1].0.authors, Vec::<String>::new());
+        assert_eq!(ranked[1].1, Similarity::from_ratio(0.875));
+        assert!(!query.author_mismatch(&ranked[0].0) && !query.author_mismatch(&ranked[1].0));
+        assert!(query.author_mismatch(&ranked[2].0));
+        assert!(!by_title("Deep Learning").author_mismatch(&ranked[2].0));
+    }
+
+    #[test]
+    fn test_alternate_title_matches() {
+        let merged = PaperMetadata {
+            alternate_titles: vec!["BERT: Pre-training of Deep Bidirectional Transformers for Language Understanding".to_string()],
+            ..create_dummy_paper("BERT")
+        };
+        let query = by_title("BERT: Pre-training of Deep Bidirectional Transformers for Language Understanding");
+        let folding = TitleFolding::default();
+        assert!(Resolver::resolve(&query, vec![create_dummy_paper("BERT")], Threshold::pick(None, None).unwrap(), &folding).is_empty());
+        let results = Resolver::resolve(&query, vec![merged], Threshold::pick(None, None).unwrap(), &folding);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, Similarity::EXACT);
+        // The listing keeps the primary title
+        assert_eq!(results[0].0.title, "BERT");
+    }
 }
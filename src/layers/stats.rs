@@ -0,0 +1,218 @@
+// Summaries of the downloaded library, such as one author's papers by year
+use std::collections::BTreeMap;
+use crate::layers::PaperMetadata;
+use crate::layers::download::ManifestEntry;
+use crate::layers::normalize::comparable_title;
+use crate::layers::resolution::same_surname;
+
+/// A downloaded paper: its manifest entry and, when still readable, its metadata.json.
+pub struct LibraryPaper {
+    pub entry: ManifestEntry,
+    pub metadata: Option<PaperMetadata>,
+}
+
+impl LibraryPaper {
+    /// The full author list from metadata.json, falling back to the manifest's first author.
+    pub fn authors(&self) -> Vec<&str> {
+        match &self.metadata {
+            Some(paper) if !paper.authors.is_empty() => paper.authors.iter().map(String::as_str).collect(),
+            _ => Some(self.entry.first_author.as_str()).filter(|a| *a != "Unknown").into_iter().collect(),
+        }
+    }
+
+    pub fn timeline_entry(&self) -> TimelineEntry {
+        let paper = self.metadata.as_ref();
+        TimelineEntry {
+            year: self.entry.year.or(paper.and_then(|p| p.year)),
+            title: self.entry.title.clone(),
+            venue: paper.and_then(|p| p.venue.clone()),
+            doi: paper.and_then(|p| p.doi.clone()),
+        }
+    }
+}
+
+// "Doe, Jane" -> ["jane", "doe"]; "J. Doe" -> ["j", "doe"]
+fn name_tokens(name: &str) -> Vec<String> {
+    let name = match name.split_once(',') {
+        Some((surname, given)) => format!("{} {}", given, surname),
+        None => name.to_string(),
+    };
+    comparable_title(&name).split(' ').filter(|t| !t.is_empty()).map(str::to_string).collect()
+}
+
+// An initial agrees with any given name starting with it
+fn given_names_agree(a: &str, b: &str) -> bool {
+    if a.chars().count() == 1 || b.chars().count() == 1 {
+        a.chars().next() == b.chars().next()
+    } else {
+        a == b
+    }
+}
+
+/// Whether `name` can be the author `query` refers to: the surnames agree by the resolver's
+/// `same_surname` and every given name or initial in the query has a counterpart.
+pub fn author_matches(query: &str, name: &str) -> bool {
+    let (query, name) = (name_tokens(query), name_tokens(name));
+    let (Some((q_surname, q_given)), Some((n_surname, n_given))) = (query.split_last(), name.split_last()) else {
+        return false;
+    };
+    same_surname(q_surname, n_surname)
+        && q_given.iter().all(|g| n_given.iter().any(|h| given_names_agree(g, h)))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub year: Option<u32>,
+    pub title: String,
+    pub venue: Option<String>,
+    pub doi: Option<String>,
+}
+
+/// Library papers grouped by year, oldest first; papers without a year come last.
+#[derive(Debug, Default)]
+pub struct Timeline {
+    pub years: BTreeMap<u32, Vec<TimelineEntry>>,
+    pub undated: Vec<TimelineEntry>,
+}
+
+impl Timeline {
+    /// The papers with an author matching `author`, or all of them without one.
+    pub fn build(papers: &[LibraryPaper], author: Option<&str>) -> Self {
+        let mut timeline = Timeline::default();
+        for paper in papers {
+            if let Some(author) = author {
+                if !paper.authors().iter().any(|name| author_matches(author, name)) {
+                    continue;
+                }
+            }
+            let entry = paper.timeline_entry();
+            match entry.year {
+                Some(year) => timeline.years.entry(year).or_default().push(entry),
+                None => timeline.undated.push(entry),
+            }
+        }
+        for entries in timeline.years.values_mut().chain([&mut timeline.undated]) {
+            entries.sort_by(|a, b| a.title.cmp(&b.title));
+        }
+        timeline
+    }
+
+    pub fn len(&self) -> usize {
+        self.years.values().map(Vec::len).sum::<usize>() + self.undated.len()
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &TimelineEntry> {
+        self.years.values().flatten().chain(&self.undated)
+    }
+
+    /// `year,title,venue,doi`, one row per paper in timeline order.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("year,title,venue,doi\n");
+        for entry in self.entries() {
+            let row = [
+                entry.year.map(|y| y.to_string()).unwrap_or_default(),
+                entry.title.clone(),
+                entry.venue.clone().unwrap_or_default(),
+                entry.doi.clone().unwrap_or_default(),
+            ];
+            out.push_str(&row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Quotes a field when it holds a comma, quote or line break, doubling any quotes (RFC 4180).
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{formatter, OutputFormat, StatsView};
+
+    fn paper(title: &str, year: Option<u32>, authors: &[&str]) -> LibraryPaper {
+        LibraryPaper {
+            entry: ManifestEntry {
+                title: title.to_string(),
+                first_author: authors.first().copied().unwrap_or("Unknown").to_string(),
+                year,
+                id: title.to_lowercase(),
+                relative_path: format!("{}/paper.pdf", title.to_lowercase()),
+                downloaded_at: "2026-01-01T00:00:00+00:00".to_string(),
+                ..Default::default()
+            },
+            metadata: Some(PaperMetadata {
+                title: title.to_string(),
+                authors: authors.iter().map(|a| a.to_string()).collect(),
+                year,
+                venue: Some("NeurIPS".to_string()),
+                doi: Some(format!("10.1/{}", year.unwrap_or(0))),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_author_forms() {
+        assert!(author_matches("Jane Doe", "Jane Doe"));
+        assert!(author_matches("Jane Doe", "J. Doe"));
+        assert!(author_matches("J. Doe", "Jane A. Doe"));
+        assert!(author_matches("Doe, Jane", "Jane Doe"));
+        assert!(author_matches("Doe", "John Doe"));
+        assert!(author_matches("Jürgen Müller", "Jurgen Mueller"));
+        assert!(!author_matches("Jane Doe", "John Doe"));
+        assert!(!author_matches("Jane Doe", "Jane Roe"));
+        assert!(!author_matches("Jane Doe", ""));
+    }
+
+    #[test]
+    fn test_bucketing_by_year() {
+        let papers = vec![
+            paper("Second", Some(2021), &["J. Doe", "A. Smith"]),
+            paper("First", Some(2019), &["Jane Doe"]),
+            paper("Also 2021", Some(2021), &["Smith, Alice", "Doe, Jane"]),
+            paper("Undated", None, &["Jane Doe"]),
+            paper("Someone Else", Some(2020), &["John Doe"]),
+        ];
+        let timeline = Timeline::build(&papers, Some("Jane Doe"));
+        assert_eq!(timeline.len(), 4);
+        assert_eq!(timeline.years.keys().copied().collect::<Vec<_>>(), vec![2019, 2021]);
+        assert_eq!(timeline.years[&2021].iter().map(|e| e.title.as_str()).collect::<Vec<_>>(), vec!["Also 2021", "Second"]);
+        let plain = |list| formatter(OutputFormat::Plain).stats(&StatsView::new(&timeline, Some("Jane Doe"), list));
+        assert_eq!(
+            plain(true),
+            "4 downloaded papers by Jane Doe\n2019: 1\n  - First (NeurIPS)\n2021: 2\n  - Also 2021 (NeurIPS)\n  - Second (NeurIPS)\nUnknown year: 1\n  - Undated (NeurIPS)\n"
+        );
+        assert_eq!(plain(false), "4 downloaded papers by Jane Doe\n2019: 1\n2021: 2\nUnknown year: 1\n");
+        assert_eq!(Timeline::build(&papers, None).len(), 5);
+    }
+
+    #[test]
+    fn test_manifest_first_author_is_the_fallback() {
+        let mut bare = paper("Bare", Some(2018), &["Jane Doe"]);
+        bare.metadata = None;
+        let timeline = Timeline::build(&[bare], Some("J. Doe"));
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline.years[&2018][0].venue, None);
+    }
+
+    #[test]
+    fn test_csv_escaping() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("Sparse, Fast"), "\"Sparse, Fast\"");
+        assert_eq!(csv_field("The \"Attention\" Paper"), "\"The \"\"Attention\"\" Paper\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+
+        let papers = vec![paper("Graphs, \"Quoted\", and More", Some(2020), &["Jane Doe"]), paper("Undated", None, &["Jane Doe"])];
+        assert_eq!(
+            Timeline::build(&papers, None).to_csv(),
+            "year,title,venue,doi\n2020,\"Graphs, \"\"Quoted\"\", and More\",NeurIPS,10.1/2020\n,Undated,NeurIPS,10.1/0\n"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+// Post-download check that the PDF we fetched is actually the paper we asked for
+use pdf_extract::{Document, MediaBox, OutputDev, OutputError, Transform};
+use strsim::normalized_levenshtein;
+use crate::layers::normalize::{normalize_title, TitleFolding};
+
+// Below this similarity the first-page title is treated as a different paper
+pub const TITLE_MATCH_THRESHOLD: f64 = 0.6;
+
+#[derive(Debug, PartialEq)]
+pub enum TitleCheck {
+    Match { score: f64 },
+    Mismatch { extracted: String, score: f64 },
+    // Scanned PDFs, unusual encodings, or parse failures: nothing to compare against
+    Unknown,
+}
+
+struct TextLine {
+    text: String,
+    size: f64,
+    y: f64,
+}
+
+// Collects first-page text as lines tagged with their rendered font size
+#[derive(Default)]
+struct LineCollector {
+    lines: Vec<TextLine>,
+    last_end: f64,
+}
+
+impl OutputDev for LineCollector {
+    fn begin_page(&mut self, _page_num: u32, _media_box: &MediaBox, _art_box: Option<(f64, f64, f64, f64)>) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(&mut self, trm: &Transform, width: f64, _spacing: f64, font_size: f64, char: &str) -> Result<(), OutputError> {
+        let size = font_size * (trm.m11 * trm.m22 - trm.m12 * trm.m21).abs().sqrt();
+        let (x, y) = (trm.m31, trm.m32);
+
+        match self.lines.last_mut() {
+            Some(line) if (line.y - y).abs() < size * 0.5 && (line.size - size).abs() < 0.5 => {
+                if x > self.last_end + size * 0.1 {
+                    line.text.push(' ');
+                }
+                line.text.push_str(char);
+            }
+            _ => self.lines.push(TextLine { text: char.to_string(), size, y }),
+        }
+        self.last_end = x + width * size;
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Picks the largest-font text on the first page, joining consecutive lines of that size (wrapped titles).
+pub fn extract_title(pdf: &[u8]) -> Option<String> {
+    // pdf-extract panics on some malformed inputs; a failed check must never fail the download
+    let lines = std::panic::catch_unwind(|| {
+        let doc = Document::load_mem(pdf).ok()?;
+        let mut collector = LineCollector::default();
+        pdf_extract::output_doc_page(&doc, &mut collector, 1).ok()?;
+        Some(collector.lines)
+    }).ok()??;
+
+    let lines: Vec<TextLine> = lines.into_iter()
+        .map(|l| TextLine { text: collapse_whitespace(&l.text), ..l })
+        .filter(|l| l.text.chars().filter(|c| c.is_alphanumeric()).count() >= 3)
+        .collect();
+
+    let max_size = lines.iter().map(|l| l.size).fold(0.0, f64::max);
+    let start = lines.iter().position(|l| (l.size - max_size).abs() < 0.5)?;
+    let title: Vec<&str> = lines[start..].iter()
+        .take_while(|l| (l.size - max_size).abs() < 0.5)
+        .map(|l| l.text.as_str())
+        .collect();
+
+    Some(title.join(" "))
+}
+
+// Both sides go through the resolver's normalization, so entities or LaTeX in the metadata
+// title and stray punctuation on the page don't count as differences
+pub fn compare_titles(extracted: &str, expected: &str) -> f64 {
+    let folding = TitleFolding::default();
+    normalized_levenshtein(&normalize_title(extracted, &folding), &normalize_title(expected, &folding))
+}
+
+pub fn check_pdf_title(pdf: &[u8], expected: &str) -> TitleCheck {
+    match extract_title(pdf) {
+        Some(extracted) => {
+            let score = compare_titles(&extracted, expected);
+            if score >= TITLE_MATCH_THRESHOLD {
+                TitleCheck::Match { score }
+            } else {
+                TitleCheck::Mismatch { extracted, score }
+            }
+        }
+        None => TitleCheck::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = format!("{}/tests/fixtures/pdf/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn test_extract_wrapped_title_from_largest_lines() {
+        let title = extract_title(&fixture("matching.pdf"));
+        assert_eq!(title.as_deref(), Some("Attention Is All You Need"));
+    }
+
+    #[test]
+    fn test_matching_pdf_passes() {
+        let check = check_pdf_title(&fixture("matching.pdf"), "Attention is All you Need");
+        assert!(matches!(check, TitleCheck::Match { .. }), "{:?}", check);
+    }
+
+    #[test]
+    fn test_mismatching_pdf_is_flagged() {
+        let check = check_pdf_title(&fixture("mismatching.pdf"), "Attention Is All You Need");
+        match check {
+            TitleCheck::Mismatch { extracted, score } => {
+                assert_eq!(extracted, "Deep Residual Learning for Image Recognition");
+                assert!(score < TITLE_MATCH_THRESHOLD);
+            }
+            other => panic!("expected mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_garbage_bytes_are_unknown() {
+        assert_eq!(check_pdf_title(b"<html>not a pdf</html>", "Anything"), TitleCheck::Unknown);
+    }
+
+    #[test]
+    fn test_compare_titles_threshold() {
+        assert!(compare_titles("Attention Is All You Need*", "Attention is all you need") >= TITLE_MATCH_THRESHOLD);
+        assert!(compare_titles("BERT: Pre-training of Deep Bidirectional Transformers", "Attention Is All You Need") < TITLE_MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn test_compare_titles_normalizes_metadata_markup() {
+        assert_eq!(compare_titles("Graphs & Trees: A Survey", "Graphs &amp; Trees: a survey."), 1.0);
+        assert_eq!(compare_titles("Sorting in O(n log n) Time", "Sorting in $O(n \\log n)$ Time"), 1.0);
+    }
+}
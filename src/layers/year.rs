@@ -0,0 +1,112 @@
+// Publication years as the sources report them, checked once on the way in so sorting,
+// filters and exports only ever see a plausible year or none at all
+use chrono::Datelike;
+
+pub const EARLIEST_YEAR: i64 = 1900;
+
+fn current_year() -> i64 {
+    chrono::Utc::now().year() as i64
+}
+
+/// `raw` if it lies in 1900..=next year. Next year is allowed because preprints and
+/// online-first articles are routinely dated ahead of publication.
+pub fn validate(raw: i64) -> Option<u32> {
+    validate_in(raw, current_year())
+}
+
+fn validate_in(raw: i64, current_year: i64) -> Option<u32> {
+    (EARLIEST_YEAR..=current_year + 1).contains(&raw).then_some(raw as u32)
+}
+
+/// The validated year, plus the raw value when it was rejected (for `rejected_years`).
+pub fn check(raw: Option<i64>) -> (Option<u32>, Vec<String>) {
+    check_in(raw, current_year())
+}
+
+fn check_in(raw: Option<i64>, current_year: i64) -> (Option<u32>, Vec<String>) {
+    match raw {
+        None => (None, Vec::new()),
+        Some(raw) => match validate_in(raw, current_year) {
+            Some(year) => (Some(year), Vec::new()),
+            None => {
+                tracing::debug!("Ignoring implausible publication year {}", raw);
+                (None, vec![raw.to_string()])
+            }
+        },
+    }
+}
+
+/// Like `check`, for sources that send text: "2017" or a date such as "2017-06-12".
+pub fn check_text(raw: Option<&str>) -> (Option<u32>, Vec<String>) {
+    let Some(text) = raw.map(str::trim).filter(|t| !t.is_empty()) else {
+        return (None, Vec::new());
+    };
+    match text.split('-').next().and_then(|y| y.parse::<i64>().ok()) {
+        Some(year) => check(Some(year)),
+        None => (None, vec![text.to_string()]),
+    }
+}
+
+/// The earliest plausible year among several dates for one record (print, online, issued).
+pub fn earliest(years: impl IntoIterator<Item = i64>) -> Option<u32> {
+    years.into_iter().filter_map(validate).min()
+}
+
+/// Combines the years two sources gave for the same paper. When they are a year apart the
+/// earlier wins (online-first vs print); otherwise the first source's year is kept.
+pub fn reconcile(first: Option<u32>, other: Option<u32>) -> Option<u32> {
+    match (first, other) {
+        (Some(a), Some(b)) if a.abs_diff(b) == 1 => Some(a.min(b)),
+        (Some(a), _) => Some(a),
+        (None, b) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_range() {
+        assert_eq!(validate_in(2017, 2026), Some(2017));
+        assert_eq!(validate_in(1900, 2026), Some(1900));
+        assert_eq!(validate_in(1899, 2026), None);
+        assert_eq!(validate_in(0, 2026), None);
+        assert_eq!(validate_in(-44, 2026), None);
+        // A preprint dated next year is fine, two years ahead is not
+        assert_eq!(validate_in(2027, 2026), Some(2027));
+        assert_eq!(validate_in(2028, 2026), None);
+    }
+
+    #[test]
+    fn test_rejected_values_are_kept() {
+        assert_eq!(check_in(Some(0), 2026), (None, vec!["0".to_string()]));
+        assert_eq!(check_in(Some(2031), 2026), (None, vec!["2031".to_string()]));
+        assert_eq!(check_in(Some(2026), 2026), (Some(2026), Vec::new()));
+        assert_eq!(check_in(None, 2026), (None, Vec::new()));
+    }
+
+    #[test]
+    fn test_text_years() {
+        assert_eq!(check_text(Some("2017-06-12T17:57:34Z")), (Some(2017), Vec::new()));
+        assert_eq!(check_text(Some(" 2020 ")), (Some(2020), Vec::new()));
+        assert_eq!(check_text(Some("n.d.")), (None, vec!["n.d.".to_string()]));
+        assert_eq!(check_text(Some("")), (None, Vec::new()));
+    }
+
+    #[test]
+    fn test_earliest_skips_implausible() {
+        assert_eq!(earliest([2016, 2015, 0]), Some(2015));
+        assert_eq!(earliest([9999]), None);
+    }
+
+    #[test]
+    fn test_reconcile_prefers_earlier_of_adjacent_years() {
+        assert_eq!(reconcile(Some(2016), Some(2015)), Some(2015));
+        assert_eq!(reconcile(Some(2015), Some(2016)), Some(2015));
+        // Further apart, the first source is trusted
+        assert_eq!(reconcile(Some(2016), Some(2012)), Some(2016));
+        assert_eq!(reconcile(None, Some(2012)), Some(2012));
+        assert_eq!(reconcile(Some(2012), None), Some(2012));
+    }
+}
@@ -0,0 +1,306 @@
+// Cooperative lock so two instances don't interleave writes to the same download dir
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+pub const LOCK_FILE_NAME: &str = ".cs-researcher.lock";
+
+// A lock file that doesn't parse is treated as held for this long after it was written, then as stale
+const UNREADABLE_GRACE: Duration = Duration::from_secs(10);
+
+// Distinguishes the temp files of several acquires in one process
+static TEMP_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockInfo {
+    pid: u32,
+    started_at: String,
+}
+
+/// Who holds the lock, as far as the lock file says.
+#[derive(Debug)]
+enum Holder {
+    Process(LockInfo),
+    // The file doesn't parse, and is too new to call stale
+    Unreadable,
+}
+
+impl std::fmt::Display for Holder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Holder::Process(info) => write!(f, "PID {}, started {}", info.pid, info.started_at),
+            Holder::Unreadable => write!(f, "unreadable lock file"),
+        }
+    }
+}
+
+// What the lock file at a path says right now
+enum LockState {
+    Held(Holder),
+    Stale,
+    Gone,
+}
+
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Takes the lock or errors if a live instance holds it.
+    pub fn acquire(dir: impl AsRef<Path>) -> Result<Self> {
+        let path = dir.as_ref().join(LOCK_FILE_NAME);
+        match Self::try_acquire(&path, process_alive)? {
+            Ok(lock) => Ok(lock),
+            Err(holder) => Err(anyhow!(
+                "Another instance ({}) is using {:?}. Rerun with --wait to queue behind it.",
+                holder, dir.as_ref()
+            )),
+        }
+    }
+
+    /// Blocks until the lock is free.
+    pub async fn acquire_wait(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::acquire_wait_with(dir.as_ref().join(LOCK_FILE_NAME), Duration::from_millis(500), process_alive).await
+    }
+
+    async fn acquire_wait_with(path: PathBuf, poll: Duration, is_alive: fn(u32) -> bool) -> Result<Self> {
+        let mut announced = false;
+        loop {
+            match Self::try_acquire(&path, is_alive)? {
+                Ok(lock) => return Ok(lock),
+                Err(holder) => {
+                    if !announced {
+                        tracing::info!("Waiting for the instance holding the lock ({}) to finish...", holder);
+                        announced = true;
+                    }
+                    tokio::time::sleep(poll).await;
+                }
+            }
+        }
+    }
+
+    // Ok(lock) on success, Err(holder) if a live process owns it
+    fn try_acquire(path: &Path, is_alive: fn(u32) -> bool) -> Result<std::result::Result<Self, Holder>> {
+        // Retries cover a stale lock being cleared, or the holder releasing, between attempts
+        for _ in 0..3 {
+            if Self::create(path)? {
+                return Ok(Ok(Self { path: path.to_path_buf() }));
+            }
+            match Self::state(path, is_alive)? {
+                LockState::Held(holder) => return Ok(Err(holder)),
+                LockState::Stale => Self::break_stale(path, is_alive)?,
+                LockState::Gone => {}
+            }
+        }
+        Err(anyhow!("Could not acquire lock {:?}", path))
+    }
+
+    // Writes the lock in full to a temp file and links it into place, so the lock file is
+    // never seen half-written and only one instance can create it. False if it already exists.
+    fn create(path: &Path) -> Result<bool> {
+        let info = LockInfo {
+            pid: std::process::id(),
+            started_at: chrono::Local::now().to_rfc3339(),
+        };
+        let temp = sibling(path, &format!("{}.{}.tmp", info.pid, TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        let written = file.write_all(serde_json::to_string(&info)?.as_bytes()).and_then(|_| file.sync_all());
+        drop(file);
+        let linked = written.and_then(|_| fs::hard_link(&temp, path));
+        let _ = fs::remove_file(&temp);
+        match linked {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn state(path: &Path, is_alive: fn(u32) -> bool) -> Result<LockState> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(LockState::Gone),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(match serde_json::from_str::<LockInfo>(&contents) {
+            Ok(info) if is_alive(info.pid) => LockState::Held(Holder::Process(info)),
+            Ok(_) => LockState::Stale,
+            Err(_) if age(path).is_some_and(|age| age < UNREADABLE_GRACE) => LockState::Held(Holder::Unreadable),
+            Err(_) => LockState::Stale,
+        })
+    }
+
+    // Removes a stale lock while holding a breaker file, rechecking it first: an instance that
+    // saw the same stale lock must not delete the fresh one another instance has just taken
+    fn break_stale(path: &Path, is_alive: fn(u32) -> bool) -> Result<()> {
+        let breaker = sibling(path, "break");
+        match OpenOptions::new().write(true).create_new(true).open(&breaker) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                // Left behind by an instance that died mid-break
+                if age(&breaker).is_some_and(|age| age >= UNREADABLE_GRACE) {
+                    let _ = fs::remove_file(&breaker);
+                }
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        }
+        let removed = match Self::state(path, is_alive) {
+            Ok(LockState::Stale) => {
+                tracing::warn!("Removing stale lock file {:?}", path);
+                fs::remove_file(path).or_else(|e| if e.kind() == std::io::ErrorKind::NotFound { Ok(()) } else { Err(e) })
+                    .map_err(Into::into)
+            }
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        };
+        let _ = fs::remove_file(&breaker);
+        removed
+    }
+}
+
+// `<lock file name>.<suffix>` next to the lock
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.{}", name, suffix))
+}
+
+fn age(path: &Path) -> Option<Duration> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()?.elapsed().ok()
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            tracing::warn!("Failed to remove lock file {:?}: {}", self.path, e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 performs the permission/existence check without delivering anything
+    let ret = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    ret == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    // No cheap liveness probe; treat every lock as held
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn write_lock(path: &Path, pid: u32) {
+        let info = LockInfo { pid, started_at: "2026-01-01T00:00:00+00:00".to_string() };
+        fs::write(path, serde_json::to_string(&info).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_acquire_and_release() {
+        let dir = temp_dir("lock-release");
+        let path = dir.join(LOCK_FILE_NAME);
+        {
+            let _lock = InstanceLock::acquire(&dir).unwrap();
+            let info: LockInfo = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(info.pid, std::process::id());
+            assert!(InstanceLock::acquire(&dir).is_err());
+        }
+        assert!(!path.exists());
+        assert!(InstanceLock::acquire(&dir).is_ok());
+    }
+
+    #[test]
+    fn test_live_holder_blocks() {
+        let dir = temp_dir("lock-live");
+        let path = dir.join(LOCK_FILE_NAME);
+        write_lock(&path, 4242);
+
+        let result = InstanceLock::try_acquire(&path, |_| true).unwrap();
+        assert!(matches!(result, Err(Holder::Process(LockInfo { pid: 4242, .. }))));
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_stale_lock_is_replaced() {
+        let dir = temp_dir("lock-stale");
+        let path = dir.join(LOCK_FILE_NAME);
+        write_lock(&path, 4242);
+
+        let lock = InstanceLock::try_acquire(&path, |_| false).unwrap();
+        assert!(lock.is_ok());
+        let info: LockInfo = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(info.pid, std::process::id());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1, "temp or breaker files left behind");
+    }
+
+    #[test]
+    fn test_unreadable_lock_is_held_until_the_grace_period_ends() {
+        let dir = temp_dir("lock-unreadable");
+        let path = dir.join(LOCK_FILE_NAME);
+        fs::write(&path, "").unwrap();
+        let result = InstanceLock::try_acquire(&path, |_| false).unwrap();
+        assert!(matches!(result, Err(Holder::Unreadable)));
+        assert!(path.exists());
+
+        let old = std::time::SystemTime::now() - UNREADABLE_GRACE - Duration::from_secs(1);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(old).unwrap();
+        assert!(InstanceLock::try_acquire(&path, |_| false).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_one_winner_when_instances_race_for_a_stale_lock() {
+        let dir = temp_dir("lock-race");
+        let path = dir.join(LOCK_FILE_NAME);
+        write_lock(&path, 4242);
+
+        // 4242 is dead; this process, which every racer's lock names, is alive
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(8));
+        let racers: Vec<_> = (0..8).map(|_| {
+            let (path, barrier) = (path.clone(), barrier.clone());
+            std::thread::spawn(move || {
+                barrier.wait();
+                InstanceLock::try_acquire(&path, |pid| pid != 4242).ok().and_then(Result::ok)
+            })
+        }).collect();
+        let locks: Vec<_> = racers.into_iter().filter_map(|r| r.join().unwrap()).collect();
+        assert_eq!(locks.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_alive_with_real_pids() {
+        assert!(process_alive(std::process::id()));
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!process_alive(pid));
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_holder_releases() {
+        let dir = temp_dir("lock-wait");
+        let path = dir.join(LOCK_FILE_NAME);
+        write_lock(&path, 4242);
+
+        let release_path = path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            fs::remove_file(release_path).unwrap();
+        });
+
+        let lock = tokio::time::timeout(
+            Duration::from_secs(5),
+            InstanceLock::acquire_wait_with(path.clone(), Duration::from_millis(20), |_| true),
+        ).await.expect("wait should finish once the holder releases");
+        assert!(lock.is_ok());
+    }
+}
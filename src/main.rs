@@ -1,18 +1,51 @@
 mod layers;
+mod lock;
+mod interaction;
+mod output;
+mod schema;
+mod wizard;
+#[cfg(test)]
+mod test_support;
 
-use crate::layers::{DiscoveryQuery};
-use crate::layers::resolution::Resolver;
-use crate::layers::download::Downloader;
-use crate::layers::discovery::DiscoveryOrchestrator;
+use crate::layers::{ArxivSort, DiscoveryQuery, DiscoverySource, PublicationFilter, Cancelled, DEFAULT_MAX_AUTHORS};
+use crate::layers::resolution::{ResolutionQuery, Resolver, SortBy, Threshold};
+use crate::layers::normalize::TitleFolding;
+use crate::layers::download::{Downloader, ManifestEntry, REMOVED_ARCHIVE};
+use crate::layers::discovery::{DiscoveryOrchestrator, OpenAlexClient, SEMANTIC_SCHOLAR_BASE_URL};
+use crate::layers::enrichment::{CrossrefEnricher, EnrichmentQueue, ENRICHMENT_CONCURRENCY};
+use crate::layers::legality::{LegalityChecker, PaywallHeuristic, VersionPreference, classify_version, prefer_version};
+use crate::layers::{PaperMetadata, VersionHint};
+use crate::layers::filter::{CandidateFilter, YearRange};
+use crate::layers::library::LibraryIndex;
+use crate::layers::stats::{LibraryPaper, Timeline};
+use crate::layers::mirror::LocalMirror;
+use crate::layers::{dedupe, integrity, merge};
+use crate::layers::estimate::{self, SearchPlan};
+use crate::layers::proxy::InstitutionalProxy;
+use crate::layers::readme::describe_query;
+use crate::layers::rerank;
+use crate::layers::diagnostics::Diagnostics;
+use crate::lock::InstanceLock;
+use crate::interaction::{Interaction, Refused, EXIT_USAGE};
+use crate::output::{CandidateView, DedupeView, OutputFormat, StatsView, VerifyView};
+use crate::schema::SchemaKind;
+use tokio_util::sync::CancellationToken;
 use dotenvy::dotenv;
 use std::env;
-use anyhow::{Result};
-use clap::Parser;
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::process::ExitCode;
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use clap::{Parser, Subcommand};
 
 /// OpenScholar: Intelligent Research Discovery Engine
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Title of the paper
     #[arg(short, long)]
     title: Option<String>,
@@ -29,17 +62,258 @@ struct Args {
     #[arg(short, long)]
     university: Option<String>,
 
-    /// Custom Levenshtein threshold for fuzzy matching
-    #[arg(long, default_value_t = 5)]
-    threshold: usize,
+    /// Match titles within this many edits, whatever their length, instead of by --threshold-ratio
+    #[arg(long, value_name = "N")]
+    threshold: Option<usize>,
+
+    /// Minimum title similarity (0-1), the edit distance relative to the title's length; the
+    /// default is 0.85, and it overrides --threshold when both are given
+    #[arg(long, value_name = "RATIO")]
+    threshold_ratio: Option<f64>,
+
+    /// How to order the matches; ties fall back to title similarity
+    #[arg(long, value_enum, default_value_t = SortBy::Similarity)]
+    sort_by: SortBy,
+
+    /// Which PDF to download when the sources link several versions of a paper
+    #[arg(long, value_enum, default_value_t = VersionPreference::Any)]
+    prefer_version: VersionPreference,
+
+    /// Re-rank the top matches by similarity to the title using the sentence-embedding model
+    /// in DIR (model.onnx and tokenizer.json); needs a build with --features semantic
+    #[arg(long, value_name = "DIR")]
+    semantic_model: Option<std::path::PathBuf>,
+
+    /// Spell out Greek letters in titles (α -> alpha) before fuzzy matching
+    #[arg(long)]
+    transliterate_greek: bool,
+
+    /// Keep at most this many authors per paper (the true count is still recorded)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_AUTHORS)]
+    max_authors: usize,
 
     /// Maximum number of results to return
     #[arg(short = 'n', long, default_value_t = 10)]
     limit: usize,
+
+    /// Results to request from arXiv (defaults to --limit)
+    #[arg(long, value_name = "N")]
+    limit_arxiv: Option<usize>,
+
+    /// Results to request from OpenAlex (defaults to --limit)
+    #[arg(long, value_name = "N")]
+    limit_openalex: Option<usize>,
+
+    /// Results to request from CORE, when CORE_API_KEY is set (defaults to --limit)
+    #[arg(long, value_name = "N")]
+    limit_core: Option<usize>,
+
+    /// Results to request from Europe PMC (defaults to --limit)
+    #[arg(long, value_name = "N")]
+    limit_europepmc: Option<usize>,
+
+    /// Results to request from DBLP, when --dblp is set (defaults to --limit)
+    #[arg(long, value_name = "N")]
+    limit_dblp: Option<usize>,
+
+    /// Results to request from Semantic Scholar (defaults to --limit)
+    #[arg(long, value_name = "N")]
+    limit_semantic_scholar: Option<usize>,
+
+    /// Also search DBLP, for its venue, year and author data on computer science papers (it has no PDFs)
+    #[arg(long)]
+    dblp: bool,
+
+    /// Restrict Semantic Scholar results to a publication type
+    #[arg(long, value_enum)]
+    only: Option<PublicationFilter>,
+
+    /// Ask Semantic Scholar to return only papers with an open-access PDF
+    #[arg(long)]
+    oa_only_search: bool,
+
+    /// Order of arXiv's results, e.g. newest first for an author's recent work
+    #[arg(long, value_enum, default_value_t = ArxivSort::Relevance)]
+    arxiv_sort: ArxivSort,
+
+    /// Only keep candidates published in or after this year, from any source
+    // --year-from is the name earlier releases used
+    #[arg(long, value_name = "YEAR", alias = "year-from")]
+    min_year: Option<u32>,
+
+    /// Only keep candidates published in or before this year, from any source
+    #[arg(long, value_name = "YEAR", alias = "year-to")]
+    max_year: Option<u32>,
+
+    /// Drop candidates whose year is unknown (kept and marked by default)
+    #[arg(long)]
+    require_year: bool,
+
+    /// Only keep candidates that have an abstract
+    #[arg(long)]
+    require_abstract: bool,
+
+    /// Only keep candidates whose abstract has at least this many words
+    #[arg(long, value_name = "N")]
+    min_abstract_words: Option<usize>,
+
+    /// Quarantine downloads whose first-page title doesn't match the paper's metadata
+    #[arg(long)]
+    strict_verify: bool,
+
+    /// Download the top --limit matches without prompting, and answer yes to confirmations
+    #[arg(short, long, global = true)]
+    yes: bool,
+
+    /// Selection to use instead of prompting (e.g. "1,3" or "all")
+    #[arg(long, value_name = "LIST")]
+    select: Option<String>,
+
+    /// Rewrite manifest.json after every download instead of once at the end of the batch
+    #[arg(long)]
+    flush_per_download: bool,
+
+    /// Also list closed papers, and download the ones you select through EZPROXY_PREFIX
+    #[arg(long)]
+    via_proxy: bool,
+
+    /// How to print the candidate listing and the stats, dedupe and verify results
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Plain)]
+    output: OutputFormat,
+
+    /// Write a README.md cover note (metadata, provenance, file hashes) into each paper directory
+    #[arg(long)]
+    write_readme: bool,
+
+    /// Also download the LaTeX source of arXiv papers, next to paper.pdf
+    #[arg(long)]
+    with_source: bool,
+
+    /// Wait for another instance using the same download directory to finish instead of exiting
+    #[arg(long, global = true)]
+    wait: bool,
+
+    /// Don't offer the setup questions when there is no .env yet; copy .env.example instead
+    #[arg(long, global = true)]
+    no_wizard: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Delete a downloaded paper, archiving its manifest entry and metadata in archive/removed.jsonl
+    Remove {
+        /// The paper's ID, as in manifest.json (the name of its directory)
+        id: String,
+
+        /// Why the paper was removed; kept in the archive
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Count downloaded papers per year, optionally for one author
+    Stats {
+        /// Only papers with this author ("Jane Doe", "J. Doe" and "Doe, Jane" all work)
+        #[arg(short, long)]
+        author: Option<String>,
+
+        /// List each year's papers under its count
+        #[arg(long)]
+        timeline: bool,
+
+        /// Print the papers as CSV (year,title,venue,doi) instead
+        #[arg(long)]
+        csv: bool,
+    },
+    /// List downloaded papers that look like duplicates of each other, without changing anything
+    Dedupe {
+        /// Print the suspected duplicate groups with their paths and sizes
+        #[arg(long)]
+        report: bool,
+    },
+    /// Re-hash downloaded PDFs and compare them with the SHA-256 in manifest.json
+    Verify {
+        /// Only papers downloaded or modified at or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "DATE")]
+        changed_since: Option<String>,
+
+        /// Files hashed at once (default: the number of CPUs)
+        #[arg(long)]
+        workers: Option<usize>,
+
+        /// Record the hash of entries that have none and rewrite out-of-date README.md cover notes
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Print the JSON Schema of one of the JSON documents the tool writes
+    Schema {
+        #[arg(value_enum)]
+        kind: SchemaKind,
+    },
+}
+
+impl Args {
+    fn source_limits(&self) -> HashMap<DiscoverySource, usize> {
+        [
+            (DiscoverySource::Arxiv, self.limit_arxiv),
+            (DiscoverySource::OpenAlex, self.limit_openalex),
+            (DiscoverySource::SemanticScholar, self.limit_semantic_scholar),
+            (DiscoverySource::Core, self.limit_core),
+            (DiscoverySource::Dblp, self.limit_dblp),
+            (DiscoverySource::EuropePmc, self.limit_europepmc),
+        ]
+        .into_iter()
+        .filter_map(|(source, limit)| limit.map(|l| (source, l)))
+        .collect()
+    }
+
+    /// The selection string given up front, if any; `--yes` alone stands for "all".
+    fn preselection(&self) -> Option<&str> {
+        self.select.as_deref().or(self.yes.then_some("all"))
+    }
+}
+
+// Where a candidate came from, as the listing names it
+fn source_label(paper: &PaperMetadata) -> &'static str {
+    if paper.arxiv_id.is_some() {
+        "ArXiv"
+    } else if paper.open_alex_id.is_some() {
+        "OpenAlex"
+    } else if paper.core_id.is_some() {
+        "CORE"
+    } else if paper.europe_pmc_id.is_some() {
+        "EuropePMC"
+    } else if paper.dblp_key.is_some() && paper.semantic_scholar_id.is_none() {
+        "DBLP"
+    } else {
+        "SemanticScholar"
+    }
+}
+
+// "Title (2017) by A. Vaswani, N. Shazeer et al. [ArXiv]", to tell conflicting records apart
+fn describe_record(paper: &PaperMetadata) -> String {
+    let year = paper.year.map(|y| y.to_string()).unwrap_or_else(|| "n.d.".to_string());
+    let authors = match paper.authors.len() {
+        0 => "unknown authors".to_string(),
+        1..=2 => paper.authors.join(", "),
+        _ => format!("{} et al.", paper.authors[..2].join(", ")),
+    };
+    format!("{} ({}) by {} [{}]", paper.title, year, authors, source_label(paper))
+}
+
+/// Zero-based indices for a selection like "1,3" or "all". Numbers out of range are dropped,
+/// and "all" leaves out the candidates `manual_only` picks.
+fn parse_selection(input: &str, available: usize, limit: usize, manual_only: impl Fn(usize) -> bool) -> Vec<usize> {
+    if input.eq_ignore_ascii_case("all") {
+        return (0..available.min(limit)).filter(|&i| !manual_only(i)).collect();
+    }
+    input.split(',')
+        .filter_map(|s| s.trim().parse::<usize>().ok())
+        .map(|i| i.wrapping_sub(1)) // Convert 1-based to 0-based
+        .filter(|&i| i < available)
+        .collect()
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     // 0. Load Configuration
     dotenv().ok();
     
@@ -59,13 +333,247 @@ async fn main() -> Result<()> {
 
     tracing::info!("\n\n--- New Execution Started: {} ---", chrono::Local::now());
 
+    let args = Args::parse();
+
     // Auto-create .env if it doesn't exist
-    if !std::path::Path::new(".env").exists() && std::path::Path::new(".env.example").exists() {
-        tracing::info!("Creating .env from .env.example...");
-        std::fs::copy(".env.example", ".env")?;
+    let wizard = !args.no_wizard && args.preselection().is_none()
+        && !matches!(args.command, Some(Command::Schema { .. }))
+        && std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+    if !std::path::Path::new(".env").exists() {
+        if wizard {
+            first_run_setup().await?;
+        } else if std::path::Path::new(".env.example").exists() {
+            tracing::info!("Creating .env from .env.example...");
+            std::fs::copy(".env.example", ".env")?;
+        }
     }
 
-    let args = Args::parse();
+    // Ctrl-C cancels the pipeline; a second Ctrl-C exits immediately
+    let cancel = CancellationToken::new();
+    {
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                tracing::warn!("Ctrl-C received, cancelling...");
+                cancel.cancel();
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    std::process::exit(130);
+                }
+            }
+        });
+    }
+
+    // Repeated warnings are printed once, grouped, however the run ends
+    let diagnostics = Arc::new(Diagnostics::default());
+    let result = run(args, cancel, diagnostics.clone()).await;
+    eprint!("{}", diagnostics.render());
+    // Returned rather than exited on, so the log guard still flushes
+    match result {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        Err(e) => match e.downcast_ref::<Refused>() {
+            Some(refused) => {
+                eprintln!("{}", refused);
+                Ok(ExitCode::from(EXIT_USAGE))
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Asks the first-run questions on stderr, writes .env from .env.example with the answers,
+/// loads it and tries one search. Runs before the Ctrl-C handler, so Ctrl-C simply exits.
+async fn first_run_setup() -> Result<()> {
+    let cancel = CancellationToken::new();
+    eprintln!("No .env found; a few questions to set one up (Enter skips, --no-wizard turns this off).");
+    let mut setup = wizard::Wizard::default();
+    while setup.step() != wizard::Step::Done {
+        if let Some(key) = setup.pending_key() {
+            let valid = match wizard::check_key(SEMANTIC_SCHOLAR_BASE_URL, key).await {
+                Ok(valid) => valid,
+                Err(e) => {
+                    eprintln!("Couldn't check the key ({}); keeping it.", e);
+                    true
+                }
+            };
+            if !valid {
+                eprintln!("Semantic Scholar refused that key.");
+            }
+            setup.key_checked(valid);
+            continue;
+        }
+        if let Some(prompt) = setup.prompt() {
+            eprintln!("{}", prompt);
+        }
+        let line = read_line_cancellable(&cancel).await?;
+        if let Err(message) = setup.answer(&line) {
+            eprintln!("{}", message);
+        }
+    }
+
+    let template = std::fs::read_to_string(".env.example").ok();
+    std::fs::write(".env", wizard::render_env(template.as_deref(), setup.answers()))?;
+    dotenvy::from_path(".env").ok();
+    eprintln!("Wrote .env.");
+
+    let email = setup.answers().email.clone();
+    let query = DiscoveryQuery { title: Some("Attention Is All You Need".to_string()), limit: 1, ..Default::default() };
+    match OpenAlexClient::new(email).search(&query).await {
+        Ok(papers) => eprintln!("Self-test: OpenAlex returned {} result(s) for a sample search.", papers.len()),
+        Err(e) => eprintln!("Self-test failed ({}); check your network and the settings in .env.", e),
+    }
+    Ok(())
+}
+
+/// Creates the download directory if needed and takes its lock, which is held until the guard drops.
+async fn open_library(download_dir: &str, wait: bool) -> Result<InstanceLock> {
+    if !std::path::Path::new(download_dir).exists() {
+        tracing::info!("Creating download directory: {}", download_dir);
+        std::fs::create_dir_all(download_dir)?;
+    }
+
+    let lock = if wait {
+        InstanceLock::acquire_wait(download_dir).await?
+    } else {
+        InstanceLock::acquire(download_dir)?
+    };
+
+    // Fold in manifest entries journaled by a run that died mid-batch
+    let recovered = Downloader::new(download_dir).flush_pending()?;
+    if recovered > 0 {
+        tracing::info!("Recovered {} manifest entries from an interrupted run.", recovered);
+    }
+    Ok(lock)
+}
+
+async fn remove(args: &Args, id: &str, reason: Option<String>, cancel: &CancellationToken) -> Result<()> {
+    let interaction = Interaction::detect(args.yes);
+    if interaction == Interaction::Refuse {
+        return Err(Refused("error: stdin is not a terminal, so the removal can't be confirmed. Pass --yes to remove without asking.").into());
+    }
+
+    let download_dir = env::var("DOWNLOAD_DIR").unwrap_or_else(|_| "downloads".to_string());
+    let _lock = open_library(&download_dir, args.wait).await?;
+    let downloader = Downloader::new(download_dir);
+
+    let entry = downloader.find_entry(id)?;
+    // Checked before asking, so a bad path is reported instead of confirmed
+    let dir = downloader.paper_dir(&entry)?;
+    if !args.yes {
+        let target = dir.as_ref().map(|d| format!("delete {}", d.display())).unwrap_or_else(|| "forget it (its files are already gone)".to_string());
+        interaction.say(&format!("Remove '{}' from the library and {}? [y/N]", entry.title, target));
+        let answer = read_line_cancellable(cancel).await?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            interaction.say("Nothing removed.");
+            return Ok(());
+        }
+    }
+
+    downloader.remove_paper(id, reason)?;
+    interaction.say(&format!("Removed '{}'. Its manifest entry and metadata are kept in {}.",
+        entry.title, downloader.base_dir().join(REMOVED_ARCHIVE).display()));
+    Ok(())
+}
+
+// Every manifest entry with its metadata.json, when that is still readable
+async fn read_library(downloader: &Downloader) -> Result<Vec<LibraryPaper>> {
+    let mut papers = Vec::new();
+    for entry in downloader.read_manifest().await? {
+        let metadata = downloader.read_metadata(&entry).unwrap_or_else(|e| {
+            tracing::warn!("Using only the manifest for '{}': {}", entry.id, e);
+            None
+        });
+        papers.push(LibraryPaper { entry, metadata });
+    }
+    Ok(papers)
+}
+
+async fn stats(author: Option<&str>, timeline: bool, csv: bool, format: OutputFormat) -> Result<()> {
+    let download_dir = env::var("DOWNLOAD_DIR").unwrap_or_else(|_| "downloads".to_string());
+    let downloader = Downloader::new(download_dir);
+    let papers = read_library(&downloader).await?;
+
+    let built = Timeline::build(&papers, author);
+    if csv {
+        print!("{}", built.to_csv());
+        return Ok(());
+    }
+    print!("{}", output::formatter(format).stats(&StatsView::new(&built, author, timeline)));
+    Ok(())
+}
+
+async fn dedupe(report: bool, format: OutputFormat) -> Result<()> {
+    if !report {
+        return Err(anyhow!("dedupe only reports for now; pass --report"));
+    }
+    let download_dir = env::var("DOWNLOAD_DIR").unwrap_or_else(|_| "downloads".to_string());
+    let downloader = Downloader::new(download_dir);
+    let papers = read_library(&downloader).await?;
+    let groups = dedupe::find_duplicates(&papers);
+    let size = |entry: &ManifestEntry| std::fs::metadata(downloader.base_dir().join(&entry.relative_path)).ok().map(|m| m.len());
+    print!("{}", output::formatter(format).duplicates(&DedupeView::new(&groups, &papers, size)));
+    Ok(())
+}
+
+async fn verify(changed_since: Option<&str>, workers: Option<usize>, fix: bool, args: &Args) -> Result<()> {
+    let download_dir = env::var("DOWNLOAD_DIR").unwrap_or_else(|_| "downloads".to_string());
+    // Only --fix writes to the library
+    let _lock = match fix {
+        true => Some(open_library(&download_dir, args.wait).await?),
+        false => None,
+    };
+    let downloader = Downloader::new(&download_dir);
+    let since = changed_since.map(integrity::parse_since).transpose()?;
+    let entries: Vec<_> = downloader.read_manifest().await?.into_iter()
+        .filter(|e| since.is_none_or(|since| integrity::changed_since(e, &downloader.base_dir().join(&e.relative_path), since)))
+        .collect();
+    let workers = workers.unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+
+    let progress = Arc::new(integrity::Progress::new(entries.len()));
+    // Redrawn in place on a terminal; a redirected stderr only gets the final line
+    let ticker = std::io::stderr().is_terminal().then(|| {
+        let progress = progress.clone();
+        std::thread::spawn(move || {
+            while !progress.is_done() {
+                eprint!("\r{}", progress.render(progress.elapsed()));
+                std::thread::sleep(std::time::Duration::from_millis(250));
+            }
+        })
+    });
+    let base_dir = downloader.base_dir().to_path_buf();
+    let checks = {
+        let progress = progress.clone();
+        tokio::task::spawn_blocking(move || integrity::verify(&base_dir, &entries, workers, &progress)).await??
+    };
+    if let Some(ticker) = ticker {
+        let _ = ticker.join();
+        eprint!("\r");
+    }
+    eprintln!("{}", progress.render(progress.elapsed()));
+
+    let report = VerifyView::new(&checks);
+    print!("{}", output::formatter(args.output).verification(&report));
+    if fix {
+        let fixed = downloader.fix(&checks)?;
+        eprintln!("Recorded {} missing hashes, rewrote {} README.md cover notes", fixed.hashes_recorded, fixed.readmes_rewritten);
+    }
+    if !report.problems.is_empty() {
+        return Err(anyhow!("{} of {} papers failed verification", report.problems.len(), report.checked));
+    }
+    Ok(())
+}
+
+async fn run(args: Args, cancel: CancellationToken, diagnostics: Arc<Diagnostics>) -> Result<()> {
+    match &args.command {
+        Some(Command::Remove { id, reason }) => return remove(&args, id, reason.clone(), &cancel).await,
+        Some(Command::Stats { author, timeline, csv }) => return stats(author.as_deref(), *timeline, *csv, args.output).await,
+        Some(Command::Dedupe { report }) => return dedupe(*report, args.output).await,
+        Some(Command::Verify { changed_since, workers, fix }) => return verify(changed_since.as_deref(), *workers, *fix, &args).await,
+        Some(Command::Schema { kind }) => {
+            println!("{}", serde_json::to_string_pretty(&schema::schema(*kind))?);
+            return Ok(());
+        }
+        None => {}
+    }
 
     if args.title.is_none() && args.author.is_none() && args.university.is_none() {
         tracing::error!("Please provide at least a --title, --author, or --university.");
@@ -73,15 +581,29 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Checked before any network work so a piped run fails immediately rather than hanging
+    let preselection = args.preselection().map(str::to_string);
+    let interaction = Interaction::detect(preselection.is_some());
+    if interaction == Interaction::Refuse {
+        return Err(Refused(Interaction::refusal_message()).into());
+    }
+
+    // A bad ratio is reported before the search rather than after it
+    let threshold = Threshold::pick(args.threshold, args.threshold_ratio)?;
+    let year_range = YearRange::new(args.min_year, args.max_year, args.require_year)?;
     let ss_api_key = env::var("SEMANTIC_SCHOLAR_API_KEY").ok();
     let email = env::var("OPENALEX_EMAIL").ok();
     let download_dir = env::var("DOWNLOAD_DIR").unwrap_or_else(|_| "downloads".to_string());
-    
-    // Ensure download directory exists
-    if !std::path::Path::new(&download_dir).exists() {
-        tracing::info!("Creating download directory: {}", download_dir);
-        std::fs::create_dir_all(&download_dir)?;
-    }
+
+    // Held until main returns; manifest and unavailable.json are rewritten wholesale
+    let _lock = open_library(&download_dir, args.wait).await?;
+
+    // Closed papers become downloadable only through the user's own library proxy
+    let proxy = match args.via_proxy {
+        true => Some(InstitutionalProxy::from_env()
+            .ok_or_else(|| anyhow!("--via-proxy needs EZPROXY_PREFIX set to your library's proxy prefix (e.g. https://login.proxy.example.edu/login?url=)"))?),
+        false => None,
+    };
 
     // 1. Discovery (Layer 1)
     tracing::info!("--- Step 1: Discovery (Parallel) ---");
@@ -91,10 +613,46 @@ async fn main() -> Result<()> {
         university: args.university.clone(),
         category: args.category.clone(),
         limit: args.limit,
+        // Semantic Scholar narrows by year server-side; the other sources are filtered after discovery
+        year_from: year_range.min,
+        year_to: year_range.max,
+        publication_filter: args.only,
+        oa_only: args.oa_only_search,
+        source_limits: args.source_limits(),
+        max_authors: Some(args.max_authors),
+        arxiv_sort: args.arxiv_sort,
     };
 
-    let orchestrator = DiscoveryOrchestrator::new(ss_api_key, email);
-    let results = orchestrator.search_all(&query).await;
+    // Large limits page through thousands of results; say what that costs before spending it
+    let core_key = env::var("CORE_API_KEY").ok().filter(|k| !k.trim().is_empty());
+    let cost = estimate::estimate(&query, SearchPlan { ss_api_key: ss_api_key.is_some(), core: core_key.is_some(), dblp: args.dblp });
+    if cost.needs_confirmation() && !args.yes {
+        // Stdout stays a clean JSON document
+        let say = |line: &str| if args.output == OutputFormat::Json { eprintln!("{}", line) } else { interaction.say(line) };
+        say(&cost.render());
+        if interaction == Interaction::NonInteractive {
+            return Err(anyhow!("this search makes about {} requests; pass --yes to run it without confirming", cost.total_requests()));
+        }
+        say("Run it? [y/N]");
+        let answer = read_line_cancellable(&cancel).await?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            say("Nothing searched.");
+            return Ok(());
+        }
+    } else {
+        tracing::info!("Estimated {} requests, about {:?}", cost.total_requests(), cost.duration());
+    }
+
+    // Unpaywall is also asked about OA papers whose PDF sits on one of these hosts
+    let paywall = PaywallHeuristic::from_env();
+    let orchestrator = DiscoveryOrchestrator::new(ss_api_key, email.clone())
+        .with_core(core_key)
+        .with_dblp(args.dblp)
+        .with_unpaywall(env::var("UNPAYWALL_EMAIL").ok().filter(|e| !e.trim().is_empty()), paywall.clone())
+        .with_diagnostics(diagnostics.clone());
+    // Unpaywall and Crossref lookups share one limit on requests in flight
+    let enrichment = EnrichmentQueue::new(ENRICHMENT_CONCURRENCY).with_diagnostics(diagnostics.clone());
+    let results = orchestrator.search_all(&query, &cancel).await?;
     tracing::info!("Found {} candidates from combined sources.", results.len());
 
     if results.is_empty() {
@@ -102,19 +660,77 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    let candidate_filter = CandidateFilter {
+        require_abstract: args.require_abstract,
+        min_abstract_words: args.min_abstract_words,
+        category: args.category.clone(),
+    };
+    let results = year_range.apply(candidate_filter.apply(results));
+    if year_range.is_active() && results.is_empty() {
+        tracing::warn!("No candidates {}.", year_range);
+        return Ok(());
+    }
+
     // 2. Resolution (Layer 2)
     tracing::info!("--- Step 2: Fuzzy Resolution ---");
     let search_title = args.title.as_deref().unwrap_or("");
-    let matches = Resolver::resolve(search_title, results, args.threshold);
-    let all_sorted = Resolver::sort_by_similarity(matches);
+    let folding = TitleFolding { transliterate_greek: args.transliterate_greek };
+    // A paraphrased title is far from the real one by edit distance, so the model sees all candidates
+    let semantic_model = args.semantic_model.as_deref().filter(|_| !search_title.is_empty());
+    let reranking = semantic_model.is_some() && rerank::SEMANTIC_ENABLED;
+    let resolution = ResolutionQuery { title: search_title, author: args.author.as_deref() };
+    // Only show papers that are Open Access AND have a PDF URL, or closed ones with an open copy
+    let openly_available = |p: &PaperMetadata| (p.is_oa && p.pdf_url.is_some()) || LegalityChecker::open_copy(p).is_some();
+    // With --via-proxy closed papers are listed too, but only fetched when picked by number
+    let proxied = |p: &PaperMetadata| !openly_available(p) && proxy.as_ref().is_some_and(|proxy| !p.is_oa && proxy.rewrite(p).is_some());
+    let downloadable = |p: &PaperMetadata| openly_available(p) || proxied(p);
+    let matches = Resolver::resolve(&resolution, results, if reranking { Threshold::ANY } else { threshold }, &folding);
+    // Only matches are looked up; repository copies of closed or PDF-less ones would otherwise
+    // be listed as unavailable below
+    let (mut papers, similarities): (Vec<_>, Vec<_>) = matches.into_iter().unzip();
+    orchestrator.enrich_oa(&mut papers, &enrichment, &cancel).await?;
+    let matches = papers.into_iter().zip(similarities);
+    let (mut sorted_matches, unavailable_matches): (Vec<_>, Vec<_>) = match semantic_model {
+        // The model reorders the full list, so it sees every match
+        Some(model_dir) => {
+            let mut all_sorted = Resolver::sort_by_similarity(matches.collect());
+            if let Err(e) = rerank::rerank_with_model(model_dir, search_title, &mut all_sorted) {
+                tracing::warn!("Semantic re-ranking failed: {}", e);
+                eprintln!("Semantic re-ranking skipped: {}", e);
+            }
+            // After re-ranking, so a citation or year order isn't undone by it
+            if args.sort_by != SortBy::Similarity {
+                all_sorted = Resolver::sort_by(all_sorted, args.sort_by);
+            }
+            let (mut open, closed): (Vec<_>, Vec<_>) = all_sorted.into_iter().partition(|(p, _)| downloadable(p));
+            open.truncate(args.limit);
+            (open, closed)
+        }
+        // Only the first --limit downloadable matches are listed, so they're kept in a running
+        // top-k rather than sorting every match; unavailable.json lists all the others
+        None => {
+            let (open, closed): (Vec<_>, Vec<_>) = matches.partition(|(p, _)| downloadable(p));
+            (Resolver::top_k(open, args.sort_by, args.limit), Resolver::sort_by(closed, args.sort_by))
+        }
+    };
+
+    // OA flags on some publisher hosts are unreliable; prefer an open copy when we know one
+    for (paper, _) in sorted_matches.iter_mut() {
+        prefer_version(paper, args.prefer_version);
+        paywall.resolve_alternate(paper);
+        // Sources other than OpenAlex don't report a version; fall back to the PDF host
+        if paper.version_hint.is_none() {
+            paper.version_hint = classify_version(None, None, paper.pdf_url.as_deref());
+        }
+    }
 
-    // Filter: Only show papers that are Open Access AND have a PDF URL
-    let (sorted_matches, unavailable_matches): (Vec<_>, Vec<_>) = all_sorted.into_iter()
-        .partition(|(p, _)| p.is_oa && p.pdf_url.is_some());
+    // ", published 2015-2020" for the messages below, when a year range is set
+    let years = Some(year_range).filter(YearRange::is_active).map(|r| format!(", {}", r)).unwrap_or_default();
 
     // Save unavailable papers
     if !unavailable_matches.is_empty() {
         let unavailable_papers: Vec<_> = unavailable_matches.into_iter().map(|(p, _)| p).collect();
+        tracing::info!("{} matches{} have no open PDF; listing them in unavailable.json.", unavailable_papers.len(), years);
         let downloader = crate::layers::download::Downloader::new(download_dir.clone());
         if let Err(e) = downloader.save_unavailable(&query, unavailable_papers).await {
             tracing::warn!("Failed to save unavailable papers: {}", e);
@@ -122,70 +738,239 @@ async fn main() -> Result<()> {
     }
     
     if sorted_matches.is_empty() {
-        tracing::warn!("No downloadable (Open Access + PDF) matches found within threshold {}.", args.threshold);
+        tracing::warn!("No downloadable (Open Access + PDF) matches found within {}{}.", threshold, years);
+        // A script asked for a download and none can happen; let it notice
+        if preselection.is_some() {
+            return Err(anyhow!("no downloadable matches to select from"));
+        }
         return Ok(());
     }
 
     // Interactive Selection
-    println!("\n--- candidates found ---");
-    // Interactive Selection
-    println!("\n--- candidates found ---");
-    for (i, (paper, dist)) in sorted_matches.iter().enumerate().take(args.limit) {
-        let source_hint = if paper.arxiv_id.is_some() { "[ArXiv]" } else if paper.open_alex_id.is_some() { "[OpenAlex]" } else { "[SemanticScholar]" };
-        let oa_status = if paper.is_oa { "Open Access" } else { "Closed Access" };
-        println!("[{}] {} (Dist: {}) {} - {}", i + 1, paper.title, dist, source_hint, oa_status);
+    let views: Vec<CandidateView> = sorted_matches.iter().enumerate().take(args.limit).map(|(i, (paper, similarity))| {
+        CandidateView {
+            index: i + 1,
+            title: paper.title.clone(),
+            year: paper.year,
+            doi: paper.doi.clone(),
+            similarity: similarity.percent(),
+            citations: paper.citation_count,
+            source: source_label(paper).to_string(),
+            // The primary arXiv category, else Semantic Scholar's top field of study
+            field: paper.primary_arxiv_category().or_else(|| paper.s2_fields().next()).map(str::to_string),
+            open_access: paper.is_oa,
+            accepted_manuscript: paper.version_hint == Some(VersionHint::AcceptedManuscript),
+            paywall_caution: paywall.is_suspicious(paper),
+            author_mismatch: resolution.author_mismatch(paper),
+            year_unknown: year_range.is_unchecked(paper),
+            conflicting_records: !paper.conflicting_records.is_empty(),
+        }
+    }).collect();
+    let listing = output::formatter(args.output).candidates(&views, &diagnostics.summary());
+    // Keep stdout a clean JSON document: the listing goes there, prompts and notes go to stderr
+    let json_output = args.output == OutputFormat::Json;
+    let say = |line: &str| if json_output { eprintln!("{}", line) } else { interaction.say(line) };
+    if json_output {
+        print!("{}", listing);
+    } else {
+        interaction.say(listing.trim_end_matches('\n'));
     }
 
-    println!("\nEnter numbers to download (e.g., '1', '1,3'), 'all' for top 10, or 'q' to quit:");
-    
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
+    let input = match &preselection {
+        Some(selection) => selection.clone(),
+        None => {
+            say(&format!("\nEnter numbers to download (e.g., '1', '1,3'), 'all' for top {}, or 'q' to quit:", args.limit));
+            read_line_cancellable(&cancel).await?
+        }
+    };
     let input = input.trim();
 
-    if input.eq_ignore_ascii_case("q") {
+    if preselection.is_none() && input.eq_ignore_ascii_case("q") {
         tracing::info!("Exiting.");
         return Ok(());
     }
 
-    let indices: Vec<usize> = if input.eq_ignore_ascii_case("all") {
-        (0..sorted_matches.len().min(args.limit)).collect()
-    } else {
-        input.split(',')
-            .filter_map(|s| s.trim().parse::<usize>().ok())
-            .map(|i| i.wrapping_sub(1)) // Convert 1-based to 0-based
-            .filter(|&i| i < sorted_matches.len())
-            .collect()
-    };
+    let indices = parse_selection(input, sorted_matches.len(), args.limit, |i| proxied(&sorted_matches[i].0));
+    let held_back = sorted_matches.iter().take(args.limit).filter(|(p, _)| proxied(p)).count();
+    if input.eq_ignore_ascii_case("all") && held_back > 0 {
+        say(&format!("Skipping {} closed paper(s) that would go through the proxy; select them by number to download them.", held_back));
+    }
 
     if indices.is_empty() {
         tracing::warn!("No valid selection made.");
+        if preselection.is_some() {
+            return Err(anyhow!("selection '{}' matches none of the {} candidates", input, sorted_matches.len()));
+        }
         return Ok(());
     }
 
     // 4. Download (Layer 4)
     tracing::info!("--- Step 3: Download ---");
-    let downloader = Downloader::new(download_dir);
+    let downloader = Downloader::new(download_dir)
+        .with_strict_verify(args.strict_verify)
+        .with_flush_per_download(args.flush_per_download)
+        .with_mirror(LocalMirror::from_env())
+        .with_proxy(proxy)
+        .with_readme(args.write_readme.then(|| describe_query(&query)))
+        .with_source(args.with_source)
+        .with_arxiv_limiter(orchestrator.arxiv_limiter())
+        .with_diagnostics(diagnostics.clone());
+    let enricher = CrossrefEnricher::new(email);
     
+    // Fill volume/issue/pages from Crossref before metadata.json is written
+    let mut selected = Vec::new();
     for idx in indices {
-        let (paper, _) = &sorted_matches[idx];
-        
-        // 3. Legality Check (Layer 3) - Late binding check
-        if !crate::layers::legality::LegalityChecker::is_legally_downloadable(paper) {
-            tracing::warn!("Skipping '{}': Not Open Access.", paper.title);
-            continue;
+        let mut paper = sorted_matches[idx].0.clone();
+        if !paper.conflicting_records.is_empty() {
+            let records = merge::records(&paper);
+            let pick = if interaction == Interaction::NonInteractive {
+                let pick = merge::richest(&records);
+                tracing::info!("Conflicting records for '{}'; picked record {} of {} as the richest", paper.title, pick + 1, records.len());
+                say(&format!("The sources disagree about '{}'; using the {} record, which has the most metadata.", paper.title, source_label(&records[pick])));
+                pick
+            } else {
+                say(&format!("The sources disagree about '{}':", paper.title));
+                for (i, record) in records.iter().enumerate() {
+                    say(&format!("  [{}] {}", i + 1, describe_record(record)));
+                }
+                say(&format!("Which record should the download use? [1-{}, default 1]", records.len()));
+                let answer = read_line_cancellable(&cancel).await?;
+                answer.trim().parse::<usize>().ok().filter(|n| (1..=records.len()).contains(n)).map_or(0, |n| n - 1)
+            };
+            paper = merge::trusted(records, pick);
         }
+        selected.push(paper);
+    }
+    enrichment.run(&enricher, &mut selected, &cancel).await?;
 
-        if paper.pdf_url.is_none() {
-             tracing::warn!("Skipping '{}': No PDF URL available.", paper.title);
-             continue;
+    // Warn before fetching what looks like another version of something already downloaded
+    let library = LibraryIndex::new(downloader.read_manifest().await?).with_removed(downloader.read_removed()?);
+    let mut confirmed = Vec::new();
+    for paper in selected {
+        if let Some(removed) = library.previously_removed(&paper) {
+            let reason = removed.reason.as_ref().map(|r| format!(" ({})", r)).unwrap_or_default();
+            say(&format!("Note: '{}' was previously removed on {}{}.", paper.title, removed.removed_on(), reason));
         }
-
-        tracing::info!("Downloading: {}", paper.title);
-        match downloader.download_paper(paper).await {
-            Ok(path) => tracing::info!("Success! Saved to: {:?}", path),
-            Err(e) => tracing::error!("Failed to download '{}': {}", paper.title, e),
+        if let Some((existing, score)) = library.near_match(&paper) {
+            let year = existing.year.map(|y| y.to_string()).unwrap_or_else(|| "n.d.".to_string());
+            let path = downloader.base_dir().join(&existing.relative_path);
+            tracing::info!("'{}' resembles library entry '{}' (similarity {:.2})", paper.title, existing.title, score.ratio());
+            if !args.yes {
+                // Nobody is there to answer; skipping is the prompt's default
+                if interaction == Interaction::NonInteractive {
+                    say(&format!("Skipping '{}': you have '{} ({})' at {} (pass --yes to download anyway)", paper.title, existing.title, year, path.display()));
+                    continue;
+                }
+                say(&format!("You have '{} ({})' at {}; download '{}' anyway? [y/N]", existing.title, year, path.display(), paper.title));
+                let answer = read_line_cancellable(&cancel).await?;
+                if !answer.trim().eq_ignore_ascii_case("y") {
+                    tracing::info!("Skipping '{}' at user request.", paper.title);
+                    continue;
+                }
+            }
         }
+        confirmed.push(paper);
     }
+    let selected = confirmed;
+
+    downloader.download_many(&selected, &cancel).await?;
 
     Ok(())
 }
+
+// stdin reads can't be interrupted, so read on a detached thread and race it against the token
+async fn read_line_cancellable(cancel: &CancellationToken) -> Result<String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let mut input = String::new();
+        let result = std::io::stdin().read_line(&mut input).map(|_| input);
+        let _ = tx.send(result);
+    });
+
+    tokio::select! {
+        _ = cancel.cancelled() => Err(Cancelled.into()),
+        line = rx => Ok(line??),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_year_range_flags() {
+        let args = Args::try_parse_from(["openscholar", "-t", "x", "--min-year", "2015", "--max-year", "2020", "--require-year"]).unwrap();
+        let range = YearRange::new(args.min_year, args.max_year, args.require_year).unwrap();
+        assert_eq!(range, YearRange { min: Some(2015), max: Some(2020), require_year: true });
+        let args = Args::try_parse_from(["openscholar", "-t", "x", "--min-year", "2021", "--max-year", "2020"]).unwrap();
+        assert!(YearRange::new(args.min_year, args.max_year, args.require_year).is_err());
+        // The old flag names still work
+        let args = Args::try_parse_from(["openscholar", "-t", "x", "--year-from", "2018", "--year-to", "2019"]).unwrap();
+        assert_eq!((args.min_year, args.max_year), (Some(2018), Some(2019)));
+    }
+
+    #[test]
+    fn test_per_source_limits_fall_back_to_global() {
+        let args = Args::try_parse_from(["openscholar", "-t", "x", "-n", "20", "--limit-arxiv", "50", "--limit-openalex", "5"]).unwrap();
+        let query = DiscoveryQuery { limit: args.limit, source_limits: args.source_limits(), ..Default::default() };
+        assert_eq!(query.limit_for(DiscoverySource::Arxiv), 50);
+        assert_eq!(query.limit_for(DiscoverySource::OpenAlex), 5);
+        assert_eq!(query.limit_for(DiscoverySource::SemanticScholar), 20);
+    }
+
+    #[test]
+    fn test_per_source_limits_must_be_numbers() {
+        assert!(Args::try_parse_from(["openscholar", "-t", "x", "--limit-arxiv", "many"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        let none = |_: usize| false;
+        assert_eq!(parse_selection("1,3", 5, 10, none), vec![0, 2]);
+        assert_eq!(parse_selection(" 2 , x, 9", 5, 10, none), vec![1]);
+        assert_eq!(parse_selection("0", 5, 10, none), Vec::<usize>::new());
+        assert_eq!(parse_selection("ALL", 5, 3, none), vec![0, 1, 2]);
+        assert_eq!(parse_selection("all", 2, 10, none), vec![0, 1]);
+        assert_eq!(parse_selection("7", 5, 10, none), Vec::<usize>::new());
+        // Proxied candidates are only taken by number
+        assert_eq!(parse_selection("all", 4, 10, |i| i == 1), vec![0, 2, 3]);
+        assert_eq!(parse_selection("2", 4, 10, |i| i == 1), vec![1]);
+    }
+
+    #[test]
+    fn test_preselection_flags() {
+        let args = Args::try_parse_from(["openscholar", "-t", "x"]).unwrap();
+        assert_eq!(args.preselection(), None);
+        let args = Args::try_parse_from(["openscholar", "-t", "x", "--yes"]).unwrap();
+        assert_eq!(args.preselection(), Some("all"));
+        let args = Args::try_parse_from(["openscholar", "-t", "x", "--select", "1,3"]).unwrap();
+        assert_eq!(args.preselection(), Some("1,3"));
+        // --yes still answers confirmations when the selection is explicit
+        let args = Args::try_parse_from(["openscholar", "-t", "x", "--yes", "--select", "2"]).unwrap();
+        assert_eq!(args.preselection(), Some("2"));
+    }
+
+    #[test]
+    fn test_remove_subcommand_takes_global_flags() {
+        let args = Args::try_parse_from(["openscholar", "remove", "10.1234_abc", "--reason", "retracted", "--yes"]).unwrap();
+        assert!(args.yes);
+        assert!(matches!(args.command, Some(Command::Remove { ref id, ref reason }) if id == "10.1234_abc" && reason.as_deref() == Some("retracted")));
+
+        let search = Args::try_parse_from(["openscholar", "-t", "remove"]).unwrap();
+        assert!(search.command.is_none());
+    }
+
+    #[test]
+    fn test_stats_subcommand_has_its_own_author_flag() {
+        let args = Args::try_parse_from(["openscholar", "stats", "--author", "J. Doe", "--timeline", "--csv"]).unwrap();
+        assert!(args.author.is_none());
+        assert!(matches!(args.command, Some(Command::Stats { ref author, timeline: true, csv: true }) if author.as_deref() == Some("J. Doe")));
+    }
+
+    #[test]
+    fn test_schema_subcommand() {
+        let args = Args::try_parse_from(["openscholar", "schema", "manifest"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Schema { kind: SchemaKind::Manifest })));
+        assert!(Args::try_parse_from(["openscholar", "schema", "report"]).is_err());
+    }
+}
@@ -0,0 +1,635 @@
+// Renders listings in the format picked with --output. Every renderer consumes the same
+// view models, so a listing added here is available in all formats at once.
+use serde::Serialize;
+use crate::layers::diagnostics::WarningGroup;
+use crate::layers::download::ManifestEntry;
+use crate::layers::integrity::{Check, Verdict};
+use crate::layers::stats::{LibraryPaper, Timeline, TimelineEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// One line per candidate (the default)
+    #[default]
+    Plain,
+    /// Aligned columns
+    Table,
+    /// A JSON document, for scripts
+    Json,
+    /// A Markdown table
+    Markdown,
+}
+
+/// One row of the candidate listing.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct CandidateView {
+    // 1-based, as typed at the selection prompt
+    pub index: usize,
+    pub title: String,
+    pub year: Option<u32>,
+    pub doi: Option<String>,
+    // Match score against the query, in whole percent: the title's similarity, weighed with
+    // the authors' when the search named one
+    pub similarity: u8,
+    pub citations: Option<u32>,
+    pub source: String,
+    pub field: Option<String>,
+    pub open_access: bool,
+    pub accepted_manuscript: bool,
+    // OA is claimed but the host often serves a paywall
+    pub paywall_caution: bool,
+    // The search named an author and the paper doesn't list them
+    pub author_mismatch: bool,
+    // A year range is set and the paper's year is unknown
+    pub year_unknown: bool,
+    // Sources sharing its identifier disagree on the year or authors (see merge::conflict)
+    pub conflicting_records: bool,
+}
+
+/// The `stats` summary: the papers counted, by year.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsView {
+    // Set when only one author's papers were counted
+    pub author: Option<String>,
+    pub total: usize,
+    // Oldest first; the undated papers last, with no year
+    pub years: Vec<YearView>,
+    // --timeline: list each year's papers under its count (the JSON document always has them)
+    #[serde(skip)]
+    pub list: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct YearView {
+    pub year: Option<u32>,
+    pub papers: Vec<TimelinePaperView>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelinePaperView {
+    pub title: String,
+    pub venue: Option<String>,
+    pub doi: Option<String>,
+}
+
+impl StatsView {
+    pub fn new(timeline: &Timeline, author: Option<&str>, list: bool) -> Self {
+        let papers = |entries: &[TimelineEntry]| entries.iter()
+            .map(|e| TimelinePaperView { title: e.title.clone(), venue: e.venue.clone(), doi: e.doi.clone() })
+            .collect();
+        let years = timeline.years.iter().map(|(year, entries)| YearView { year: Some(*year), papers: papers(entries) })
+            .chain((!timeline.undated.is_empty()).then(|| YearView { year: None, papers: papers(&timeline.undated) }))
+            .collect();
+        StatsView { author: author.map(str::to_string), total: timeline.len(), years, list }
+    }
+}
+
+impl YearView {
+    fn label(&self) -> String {
+        self.year.map(|y| y.to_string()).unwrap_or_else(|| "Unknown year".to_string())
+    }
+}
+
+/// The `verify` result: how many files were checked, and the ones that failed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerifyView {
+    pub checked: usize,
+    // Downloaded before hashes were recorded; only their presence was checked
+    pub unhashed: usize,
+    pub problems: Vec<ProblemView>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProblemView {
+    pub kind: ProblemKind,
+    pub id: String,
+    pub path: String,
+    // The hashes that disagree, or why the file couldn't be read
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemKind {
+    Corrupt,
+    Missing,
+    Unreadable,
+}
+
+impl VerifyView {
+    pub fn new(checks: &[Check]) -> Self {
+        let unhashed = checks.iter().filter(|c| matches!(c.verdict, Verdict::Unhashed { .. })).count();
+        let problems = checks.iter().filter_map(|check| {
+            let (kind, detail) = match &check.verdict {
+                Verdict::Ok | Verdict::Unhashed { .. } => return None,
+                Verdict::Mismatch { expected, actual } => (ProblemKind::Corrupt, Some(format!("expected {}, found {}", expected, actual))),
+                Verdict::Missing => (ProblemKind::Missing, None),
+                Verdict::Unreadable(e) => (ProblemKind::Unreadable, Some(e.clone())),
+            };
+            Some(ProblemView { kind, id: check.id.clone(), path: check.path.display().to_string(), detail })
+        }).collect();
+        VerifyView { checked: checks.len(), unhashed, problems }
+    }
+
+    fn summary(&self) -> String {
+        format!("{} checked, {} problems, {} without a recorded hash\n", self.checked, self.problems.len(), self.unhashed)
+    }
+}
+
+impl ProblemKind {
+    fn label(self) -> &'static str {
+        match self {
+            ProblemKind::Corrupt => "CORRUPT",
+            ProblemKind::Missing => "MISSING",
+            ProblemKind::Unreadable => "UNREADABLE",
+        }
+    }
+}
+
+/// The `dedupe --report` result: groups of papers that look like the same work.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DedupeView {
+    // How many papers the library holds
+    pub library: usize,
+    pub groups: Vec<Vec<DuplicateView>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateView {
+    pub id: String,
+    pub title: String,
+    pub year: Option<u32>,
+    pub path: String,
+    // None when the file is missing
+    pub bytes: Option<u64>,
+}
+
+impl DedupeView {
+    /// `groups` as found by `dedupe::find_duplicates`; `size` gives a file's size in bytes.
+    pub fn new(groups: &[Vec<usize>], papers: &[LibraryPaper], size: impl Fn(&ManifestEntry) -> Option<u64>) -> Self {
+        let groups = groups.iter().map(|group| group.iter().map(|&i| {
+            let entry = &papers[i].entry;
+            DuplicateView {
+                id: entry.id.clone(),
+                title: entry.title.clone(),
+                year: entry.year,
+                path: entry.relative_path.clone(),
+                bytes: size(entry),
+            }
+        }).collect()).collect();
+        DedupeView { library: papers.len(), groups }
+    }
+
+    fn none_found(&self) -> String {
+        format!("No suspected duplicates among {} papers.\n", self.library)
+    }
+}
+
+fn megabytes(bytes: Option<u64>) -> String {
+    bytes.map(|b| format!("{:.1} MB", b as f64 / 1_000_000.0)).unwrap_or_else(|| "missing".to_string())
+}
+
+pub trait Formatter {
+    /// `warnings` are those recorded while searching; only the JSON document carries them,
+    /// the other formats leave them to the end-of-run summary on stderr.
+    fn candidates(&self, candidates: &[CandidateView], warnings: &[WarningGroup]) -> String;
+    fn stats(&self, stats: &StatsView) -> String;
+    fn verification(&self, report: &VerifyView) -> String;
+    fn duplicates(&self, report: &DedupeView) -> String;
+}
+
+pub fn formatter(format: OutputFormat) -> Box<dyn Formatter> {
+    match format {
+        OutputFormat::Plain => Box::new(Plain),
+        OutputFormat::Table => Box::new(Table),
+        OutputFormat::Json => Box::new(Json),
+        OutputFormat::Markdown => Box::new(Markdown),
+    }
+}
+
+pub struct Plain;
+pub struct Table;
+pub struct Json;
+pub struct Markdown;
+
+fn access_label(c: &CandidateView) -> &'static str {
+    if c.open_access { "Open Access" } else { "Closed Access" }
+}
+
+impl Formatter for Plain {
+    fn candidates(&self, candidates: &[CandidateView], _warnings: &[WarningGroup]) -> String {
+        let mut out = String::from("\n--- candidates found ---\n");
+        for c in candidates {
+            let field = c.field.as_ref().map(|f| format!(" ({})", f)).unwrap_or_default();
+            let version = if c.accepted_manuscript { " [Accepted Manuscript]" } else { "" };
+            let caution = if c.paywall_caution { " (OA claimed, host often paywalled)" } else { "" };
+            let author = if c.author_mismatch { " (author not listed)" } else { "" };
+            let year = if c.year_unknown { " (year unknown)" } else { "" };
+            let conflict = if c.conflicting_records { " ⚠ conflicting records" } else { "" };
+            let citations = c.citations.map(|n| format!(" (Cited: {})", n)).unwrap_or_default();
+            out.push_str(&format!("[{}] {} (Match: {}%){} [{}]{} - {}{}{}{}{}{}\n",
+                c.index, c.title, c.similarity, citations, c.source, field, access_label(c), version, caution, author, year, conflict));
+        }
+        out
+    }
+
+    fn stats(&self, stats: &StatsView) -> String {
+        let whose = stats.author.as_ref().map(|a| format!(" by {}", a)).unwrap_or_default();
+        let mut out = format!("{} downloaded papers{}\n", stats.total, whose);
+        for year in &stats.years {
+            out.push_str(&format!("{}: {}\n", year.label(), year.papers.len()));
+            if stats.list {
+                for paper in &year.papers {
+                    let venue = paper.venue.as_ref().map(|v| format!(" ({})", v)).unwrap_or_default();
+                    out.push_str(&format!("  - {}{}\n", paper.title, venue));
+                }
+            }
+        }
+        out
+    }
+
+    fn verification(&self, report: &VerifyView) -> String {
+        let mut out = String::new();
+        for problem in &report.problems {
+            let detail = problem.detail.as_ref().map(|d| format!(": {}", d)).unwrap_or_default();
+            out.push_str(&format!("{}  {} ({}){}\n", problem.kind.label(), problem.id, problem.path, detail));
+        }
+        out.push_str(&report.summary());
+        out
+    }
+
+    fn duplicates(&self, report: &DedupeView) -> String {
+        if report.groups.is_empty() {
+            return report.none_found();
+        }
+        let plural = if report.groups.len() == 1 { "" } else { "s" };
+        let mut out = format!("{} group{} of suspected duplicates among {} papers:\n", report.groups.len(), plural, report.library);
+        for (n, group) in report.groups.iter().enumerate() {
+            out.push_str(&format!("\n[{}]\n", n + 1));
+            for paper in group {
+                let year = paper.year.map(|y| y.to_string()).unwrap_or_else(|| "n.d.".to_string());
+                out.push_str(&format!("  {} ({})\n    {}  {}\n", paper.title, year, paper.path, megabytes(paper.bytes)));
+            }
+        }
+        out
+    }
+}
+
+// Flags shown in the last column of the table formats
+fn notes(c: &CandidateView) -> String {
+    let mut notes = Vec::new();
+    if c.accepted_manuscript { notes.push("accepted manuscript"); }
+    if c.paywall_caution { notes.push("host often paywalled"); }
+    if c.author_mismatch { notes.push("author not listed"); }
+    if c.year_unknown { notes.push("year unknown"); }
+    if c.conflicting_records { notes.push("conflicting records"); }
+    notes.join(", ")
+}
+
+fn cells(c: &CandidateView) -> [String; 8] {
+    [
+        c.index.to_string(),
+        c.title.clone(),
+        c.year.map(|y| y.to_string()).unwrap_or_default(),
+        format!("{}%", c.similarity),
+        c.citations.map(|n| n.to_string()).unwrap_or_default(),
+        c.source.clone(),
+        access_label(c).to_string(),
+        notes(c),
+    ]
+}
+
+const HEADERS: [&str; 8] = ["#", "Title", "Year", "Match", "Cited", "Source", "Access", "Notes"];
+
+// One row per paper with --timeline, otherwise one per year and a total
+fn stats_table(stats: &StatsView) -> (Vec<&'static str>, Vec<Vec<String>>) {
+    if stats.list {
+        let rows = stats.years.iter().flat_map(|year| year.papers.iter().map(move |paper| vec![
+            year.year.map(|y| y.to_string()).unwrap_or_default(),
+            paper.title.clone(),
+            paper.venue.clone().unwrap_or_default(),
+            paper.doi.clone().unwrap_or_default(),
+        ])).collect();
+        (vec!["Year", "Title", "Venue", "DOI"], rows)
+    } else {
+        let rows = stats.years.iter().map(|year| vec![year.label(), year.papers.len().to_string()])
+            .chain([vec!["Total".to_string(), stats.total.to_string()]])
+            .collect();
+        (vec!["Year", "Papers"], rows)
+    }
+}
+
+const PROBLEM_HEADERS: [&str; 4] = ["Problem", "ID", "Path", "Detail"];
+
+fn problem_cells(problem: &ProblemView) -> [String; 4] {
+    [problem.kind.label().to_string(), problem.id.clone(), problem.path.clone(), problem.detail.clone().unwrap_or_default()]
+}
+
+const DUPLICATE_HEADERS: [&str; 5] = ["Group", "Title", "Year", "Size", "Path"];
+
+fn duplicate_rows(report: &DedupeView) -> Vec<[String; 5]> {
+    report.groups.iter().enumerate().flat_map(|(n, group)| group.iter().map(move |paper| [
+        (n + 1).to_string(),
+        paper.title.clone(),
+        paper.year.map(|y| y.to_string()).unwrap_or_default(),
+        megabytes(paper.bytes),
+        paper.path.clone(),
+    ])).collect()
+}
+
+// Columns padded to their widest cell, under a rule
+fn aligned<R: AsRef<[String]>>(headers: &[&str], rows: &[R]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row.as_ref()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| -> String {
+        let padded: Vec<String> = cells.iter().zip(&widths).map(|(c, w)| format!("{:<w$}", c, w = w)).collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+
+    let mut out = line(headers.to_vec());
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    out.push_str(&line(rule.iter().map(|s| s.as_str()).collect()));
+    for row in rows {
+        out.push_str(&line(row.as_ref().iter().map(|s| s.as_str()).collect()));
+    }
+    out
+}
+
+impl Formatter for Table {
+    fn candidates(&self, candidates: &[CandidateView], _warnings: &[WarningGroup]) -> String {
+        let rows: Vec<[String; 8]> = candidates.iter().map(cells).collect();
+        aligned(&HEADERS, &rows)
+    }
+
+    fn stats(&self, stats: &StatsView) -> String {
+        let (headers, rows) = stats_table(stats);
+        aligned(&headers, &rows)
+    }
+
+    fn verification(&self, report: &VerifyView) -> String {
+        if report.problems.is_empty() {
+            return report.summary();
+        }
+        let rows: Vec<[String; 4]> = report.problems.iter().map(problem_cells).collect();
+        format!("{}\n{}", aligned(&PROBLEM_HEADERS, &rows), report.summary())
+    }
+
+    fn duplicates(&self, report: &DedupeView) -> String {
+        if report.groups.is_empty() {
+            return report.none_found();
+        }
+        aligned(&DUPLICATE_HEADERS, &duplicate_rows(report))
+    }
+}
+
+/// The `--output json` document.
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct CandidateList<'a> {
+    candidates: &'a [CandidateView],
+    // Grouped warnings from the search and enrichment, so a script sees a source that failed
+    warnings: &'a [WarningGroup],
+}
+
+impl Formatter for Json {
+    fn candidates(&self, candidates: &[CandidateView], warnings: &[WarningGroup]) -> String {
+        let mut out = serde_json::to_string_pretty(&CandidateList { candidates, warnings })
+            .expect("candidate views always serialize");
+        out.push('\n');
+        out
+    }
+
+    fn stats(&self, stats: &StatsView) -> String {
+        let mut out = serde_json::to_string_pretty(stats).expect("stats views always serialize");
+        out.push('\n');
+        out
+    }
+
+    fn verification(&self, report: &VerifyView) -> String {
+        let mut out = serde_json::to_string_pretty(report).expect("verify views always serialize");
+        out.push('\n');
+        out
+    }
+
+    fn duplicates(&self, report: &DedupeView) -> String {
+        let mut out = serde_json::to_string_pretty(report).expect("dedupe views always serialize");
+        out.push('\n');
+        out
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|").replace('\n', " ")
+}
+
+fn markdown_table<R: AsRef<[String]>>(headers: &[&str], rows: &[R]) -> String {
+    let mut out = format!("| {} |\n|{}\n", headers.join(" | "), " --- |".repeat(headers.len()));
+    for row in rows {
+        let row: Vec<String> = row.as_ref().iter().map(|cell| markdown_cell(cell)).collect();
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    out
+}
+
+impl Formatter for Markdown {
+    fn candidates(&self, candidates: &[CandidateView], _warnings: &[WarningGroup]) -> String {
+        let rows: Vec<[String; 8]> = candidates.iter().map(cells).collect();
+        markdown_table(&HEADERS, &rows)
+    }
+
+    fn stats(&self, stats: &StatsView) -> String {
+        let (headers, rows) = stats_table(stats);
+        markdown_table(&headers, &rows)
+    }
+
+    fn verification(&self, report: &VerifyView) -> String {
+        if report.problems.is_empty() {
+            return report.summary();
+        }
+        let rows: Vec<[String; 4]> = report.problems.iter().map(problem_cells).collect();
+        format!("{}\n{}", markdown_table(&PROBLEM_HEADERS, &rows), report.summary())
+    }
+
+    fn duplicates(&self, report: &DedupeView) -> String {
+        if report.groups.is_empty() {
+            return report.none_found();
+        }
+        markdown_table(&DUPLICATE_HEADERS, &duplicate_rows(report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::diagnostics::{Source, WarningKind};
+
+    fn snapshot(name: &str) -> String {
+        std::fs::read_to_string(format!("{}/tests/fixtures/output/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    fn candidates() -> Vec<CandidateView> {
+        vec![
+            CandidateView {
+                index: 1,
+                title: "Attention Is All You Need".to_string(),
+                year: Some(2017),
+                doi: None,
+                similarity: 100,
+                citations: Some(132154),
+                source: "ArXiv".to_string(),
+                field: Some("cs.CL".to_string()),
+                open_access: true,
+                accepted_manuscript: false,
+                paywall_caution: false,
+                author_mismatch: false,
+                year_unknown: false,
+                conflicting_records: true,
+            },
+            CandidateView {
+                index: 2,
+                title: "Deep Learning | A Review".to_string(),
+                year: Some(2015),
+                doi: Some("10.1038/nature14539".to_string()),
+                similarity: 91,
+                citations: Some(0),
+                source: "OpenAlex".to_string(),
+                field: Some("Computer Science".to_string()),
+                open_access: true,
+                accepted_manuscript: true,
+                paywall_caution: true,
+                author_mismatch: false,
+                year_unknown: false,
+                conflicting_records: false,
+            },
+            CandidateView {
+                index: 3,
+                title: "Über Maßtheorie".to_string(),
+                year: None,
+                doi: None,
+                similarity: 67,
+                citations: None,
+                source: "SemanticScholar".to_string(),
+                field: Some("Mathematics".to_string()),
+                open_access: true,
+                accepted_manuscript: false,
+                paywall_caution: false,
+                author_mismatch: true,
+                year_unknown: true,
+                conflicting_records: false,
+            },
+        ]
+    }
+
+    fn warnings() -> Vec<WarningGroup> {
+        vec![WarningGroup {
+            kind: WarningKind::SourceFailed,
+            source: Source::Core,
+            count: 1,
+            examples: vec!["HTTP 503 Service Unavailable".to_string()],
+        }]
+    }
+
+    fn stats(list: bool) -> StatsView {
+        let paper = |title: &str, venue: Option<&str>, doi: Option<&str>| TimelinePaperView {
+            title: title.to_string(),
+            venue: venue.map(str::to_string),
+            doi: doi.map(str::to_string),
+        };
+        StatsView {
+            author: Some("Jane Doe".to_string()),
+            total: 3,
+            years: vec![
+                YearView { year: Some(2019), papers: vec![paper("Sparse, Fast | Small", Some("NeurIPS"), Some("10.5555/sparse"))] },
+                YearView { year: Some(2021), papers: vec![paper("Graphs", None, None)] },
+                YearView { year: None, papers: vec![paper("Undated", Some("arXiv"), None)] },
+            ],
+            list,
+        }
+    }
+
+    fn verification() -> VerifyView {
+        let check = |id: &str, verdict| Check { id: id.to_string(), path: format!("downloads/{}/paper.pdf", id).into(), verdict };
+        VerifyView::new(&[
+            check("arxiv_1706.03762", Verdict::Ok),
+            check("doi_10.1038_nature14539", Verdict::Mismatch { expected: "ab12".to_string(), actual: "cd34".to_string() }),
+            check("graph-networks", Verdict::Missing),
+            check("old-paper", Verdict::Unhashed { actual: "ef56".to_string() }),
+            check("locked", Verdict::Unreadable("Permission denied (os error 13)".to_string())),
+        ])
+    }
+
+    fn duplicates() -> DedupeView {
+        let paper = |id: &str, title: &str, year, bytes| DuplicateView {
+            id: id.to_string(),
+            title: title.to_string(),
+            year,
+            path: format!("{}/paper.pdf", id),
+            bytes,
+        };
+        DedupeView {
+            library: 7,
+            groups: vec![
+                vec![
+                    paper("arxiv_1706.03762", "Attention Is All You Need", Some(2017), Some(2_215_244)),
+                    paper("doi_10.5555_attention", "Attention is all you need", None, None),
+                ],
+                vec![
+                    paper("doi_10.1038_nature14539", "Deep learning", Some(2015), Some(2_500_000)),
+                    paper("deep-learning-review", "Deep Learning | A Review", Some(2015), Some(980_000)),
+                ],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_plain_snapshot() {
+        assert_eq!(formatter(OutputFormat::Plain).candidates(&candidates(), &warnings()), snapshot("candidates.txt"));
+    }
+
+    #[test]
+    fn test_table_snapshot() {
+        assert_eq!(formatter(OutputFormat::Table).candidates(&candidates(), &warnings()), snapshot("candidates.table.txt"));
+    }
+
+    #[test]
+    fn test_json_snapshot() {
+        assert_eq!(formatter(OutputFormat::Json).candidates(&candidates(), &warnings()), snapshot("candidates.json"));
+    }
+
+    #[test]
+    fn test_markdown_snapshot() {
+        assert_eq!(formatter(OutputFormat::Markdown).candidates(&candidates(), &warnings()), snapshot("candidates.md"));
+    }
+
+    #[test]
+    fn test_stats_snapshots() {
+        assert_eq!(formatter(OutputFormat::Plain).stats(&stats(true)), snapshot("stats.txt"));
+        assert_eq!(formatter(OutputFormat::Plain).stats(&stats(false)), "3 downloaded papers by Jane Doe\n2019: 1\n2021: 1\nUnknown year: 1\n");
+        assert_eq!(formatter(OutputFormat::Table).stats(&stats(true)), snapshot("stats.table.txt"));
+        assert_eq!(formatter(OutputFormat::Table).stats(&stats(false)), snapshot("stats.counts.table.txt"));
+        assert_eq!(formatter(OutputFormat::Json).stats(&stats(false)), snapshot("stats.json"));
+        assert_eq!(formatter(OutputFormat::Markdown).stats(&stats(true)), snapshot("stats.md"));
+    }
+
+    #[test]
+    fn test_verification_snapshots() {
+        assert_eq!(formatter(OutputFormat::Plain).verification(&verification()), snapshot("verify.txt"));
+        assert_eq!(formatter(OutputFormat::Table).verification(&verification()), snapshot("verify.table.txt"));
+        assert_eq!(formatter(OutputFormat::Json).verification(&verification()), snapshot("verify.json"));
+        assert_eq!(formatter(OutputFormat::Markdown).verification(&verification()), snapshot("verify.md"));
+        let clean = VerifyView::new(&[]);
+        assert_eq!(formatter(OutputFormat::Table).verification(&clean), "0 checked, 0 problems, 0 without a recorded hash\n");
+    }
+
+    #[test]
+    fn test_duplicates_snapshots() {
+        assert_eq!(formatter(OutputFormat::Plain).duplicates(&duplicates()), snapshot("duplicates.txt"));
+        assert_eq!(formatter(OutputFormat::Table).duplicates(&duplicates()), snapshot("duplicates.table.txt"));
+        assert_eq!(formatter(OutputFormat::Json).duplicates(&duplicates()), snapshot("duplicates.json"));
+        assert_eq!(formatter(OutputFormat::Markdown).duplicates(&duplicates()), snapshot("duplicates.md"));
+        let none = DedupeView { library: 7, groups: Vec::new() };
+        assert_eq!(formatter(OutputFormat::Markdown).duplicates(&none), "No suspected duplicates among 7 papers.\n");
+    }
+}
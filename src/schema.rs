@@ -0,0 +1,76 @@
+// JSON Schema for the documents the tool writes, generated from the same structs serde
+// writes them with, so the schemas can't drift from the files.
+use crate::layers::PaperMetadata;
+use crate::layers::download::{ManifestFile, MetadataFile, RemovedRecord, UnavailableFile};
+use crate::output::CandidateList;
+use schemars::schema_for;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaKind {
+    /// The `--output json` candidate list
+    Candidates,
+    /// manifest.json
+    Manifest,
+    /// A paper's metadata.json
+    Metadata,
+    /// unavailable.json
+    Unavailable,
+    /// One line of archive/removed.jsonl
+    Removed,
+}
+
+pub fn schema(kind: SchemaKind) -> Value {
+    let schema = match kind {
+        SchemaKind::Candidates => schema_for!(CandidateList<'static>),
+        SchemaKind::Manifest => schema_for!(ManifestFile),
+        SchemaKind::Metadata => schema_for!(MetadataFile<PaperMetadata>),
+        SchemaKind::Unavailable => schema_for!(UnavailableFile),
+        SchemaKind::Removed => schema_for!(RemovedRecord),
+    };
+    schema.to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(path: &str) -> Value {
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), path);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn assert_valid(kind: SchemaKind, document: &Value) {
+        let validator = jsonschema::validator_for(&schema(kind)).unwrap();
+        let errors: Vec<String> = validator.iter_errors(document).map(|e| format!("{} at {}", e, e.instance_path())).collect();
+        assert!(errors.is_empty(), "{:?}: {:?}", kind, errors);
+    }
+
+    #[test]
+    fn test_fixtures_validate() {
+        assert_valid(SchemaKind::Candidates, &fixture("output/candidates.json"));
+        assert_valid(SchemaKind::Manifest, &fixture("schema/manifest_v1.json"));
+        assert_valid(SchemaKind::Metadata, &fixture("schema/metadata_v1.json"));
+        assert_valid(SchemaKind::Unavailable, &fixture("schema/unavailable_v1.json"));
+        assert_valid(SchemaKind::Removed, &fixture("schema/removed.json"));
+    }
+
+    #[test]
+    fn test_written_documents_validate() {
+        let paper = PaperMetadata { title: "Deep learning".to_string(), year: Some(2015), ..Default::default() };
+        let metadata = MetadataFile { schema_version: 1, paper: paper.clone() };
+        assert_valid(SchemaKind::Metadata, &serde_json::to_value(&metadata).unwrap());
+        let unavailable = serde_json::json!({"schema_version": 1, "searches": {"MIT": {"ML": [paper]}}});
+        assert_valid(SchemaKind::Unavailable, &unavailable);
+
+        let wrong = serde_json::json!({"schema_version": 1, "searches": {"MIT": [{"year": 2015}]}});
+        assert!(!jsonschema::validator_for(&schema(SchemaKind::Unavailable)).unwrap().is_valid(&wrong));
+        // Warnings are keyed by a known kind and source, not free text
+        let wrong = serde_json::json!({"candidates": [], "warnings": [{"kind": "no PDF URL", "source": "Core", "count": 1, "examples": []}]});
+        assert!(!jsonschema::validator_for(&schema(SchemaKind::Candidates)).unwrap().is_valid(&wrong));
+        // An archived paper's metadata is checked like metadata.json, not taken as any JSON
+        let mut wrong = fixture("schema/removed.json");
+        wrong["metadata"]["authors"] = serde_json::json!("Yann LeCun");
+        assert!(!jsonschema::validator_for(&schema(SchemaKind::Removed)).unwrap().is_valid(&wrong));
+    }
+}
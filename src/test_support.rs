@@ -0,0 +1,118 @@
+// Minimal scripted HTTP server for exercising the API clients in tests
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[derive(Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    // When set, the body is written in 1 KiB pieces with this pause between them
+    pub chunk_delay: Option<Duration>,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self { status, headers: Vec::new(), body: body.into(), chunk_delay: None }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn slow(mut self, chunk_delay: Duration) -> Self {
+        self.chunk_delay = Some(chunk_delay);
+        self
+    }
+}
+
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    heads: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// Serves `responses` in order, one per connection; anything past the end gets a 404.
+    pub async fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let heads = Arc::new(Mutex::new(Vec::new()));
+        let queue = Arc::new(Mutex::new(VecDeque::from(responses)));
+
+        let recorded = requests.clone();
+        let recorded_heads = heads.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { break };
+                let recorded = recorded.clone();
+                let recorded_heads = recorded_heads.clone();
+                let queue = queue.clone();
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => head.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let head = String::from_utf8_lossy(&head);
+                    let path = head.split_whitespace().nth(1).unwrap_or("").to_string();
+                    recorded.lock().unwrap().push(path);
+                    recorded_heads.lock().unwrap().push(head.to_string());
+
+                    let response = queue.lock().unwrap().pop_front()
+                        .unwrap_or_else(|| MockResponse::new(404, "not found"));
+                    let mut out = format!("HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n", response.status, response.body.len());
+                    for (name, value) in &response.headers {
+                        out.push_str(&format!("{}: {}\r\n", name, value));
+                    }
+                    out.push_str("\r\n");
+                    let _ = socket.write_all(out.as_bytes()).await;
+                    match response.chunk_delay {
+                        Some(delay) => {
+                            for piece in response.body.chunks(1024) {
+                                if socket.write_all(piece).await.is_err() {
+                                    return;
+                                }
+                                tokio::time::sleep(delay).await;
+                            }
+                        }
+                        None => {
+                            let _ = socket.write_all(&response.body).await;
+                        }
+                    }
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        Self { url, requests, heads }
+    }
+
+    /// Request paths (with query strings) in the order they arrived.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Full request heads (request line and headers) in the order they arrived.
+    pub fn request_heads(&self) -> Vec<String> {
+        self.heads.lock().unwrap().clone()
+    }
+}
+
+/// An empty directory under the system temp dir, unique to this test process; `name` must be
+/// unique across tests, so callers prefix it with their module.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("openscholar-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,229 @@
+// First-run setup: asks for the few settings worth knowing about and writes `.env` from
+// `.env.example`. The questions are a small state machine, so the flow can be tested
+// without a terminal; main.rs only reads lines and performs the key check it asks for.
+use anyhow::{Result, anyhow};
+use reqwest::StatusCode;
+
+pub const DEFAULT_DOWNLOAD_DIR: &str = "downloads";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Email,
+    SemanticScholarKey,
+    /// The key just entered has to be tried against the API before moving on
+    CheckKey,
+    DownloadDir,
+    Done,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    pub email: Option<String>,
+    pub semantic_scholar_key: Option<String>,
+    pub download_dir: Option<String>,
+}
+
+pub struct Wizard {
+    step: Step,
+    answers: Answers,
+    pending_key: Option<String>,
+}
+
+impl Default for Wizard {
+    fn default() -> Self {
+        Self { step: Step::Email, answers: Answers::default(), pending_key: None }
+    }
+}
+
+fn plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((user, domain)) => !user.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace),
+        None => false,
+    }
+}
+
+impl Wizard {
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    pub fn prompt(&self) -> Option<&'static str> {
+        match self.step {
+            Step::Email => Some("Email address for the OpenAlex and Unpaywall polite pools (blank to skip):"),
+            Step::SemanticScholarKey => Some("Semantic Scholar API key (optional; blank to skip):"),
+            Step::DownloadDir => Some("Download directory [downloads]:"),
+            Step::CheckKey | Step::Done => None,
+        }
+    }
+
+    /// Takes the answer to the current prompt; a blank answer skips it. An error is a message
+    /// to show before the same prompt is asked again.
+    pub fn answer(&mut self, input: &str) -> std::result::Result<(), String> {
+        let input = input.trim();
+        match self.step {
+            Step::Email => {
+                if !input.is_empty() && !plausible_email(input) {
+                    return Err(format!("'{}' doesn't look like an email address.", input));
+                }
+                self.answers.email = Some(input.to_string()).filter(|e| !e.is_empty());
+                self.step = Step::SemanticScholarKey;
+            }
+            Step::SemanticScholarKey => {
+                if input.is_empty() {
+                    self.step = Step::DownloadDir;
+                } else {
+                    self.pending_key = Some(input.to_string());
+                    self.step = Step::CheckKey;
+                }
+            }
+            Step::DownloadDir => {
+                self.answers.download_dir = Some(input.to_string()).filter(|d| !d.is_empty());
+                self.step = Step::Done;
+            }
+            Step::CheckKey | Step::Done => {}
+        }
+        Ok(())
+    }
+
+    /// The key waiting to be checked, while at `Step::CheckKey`.
+    pub fn pending_key(&self) -> Option<&str> {
+        self.pending_key.as_deref().filter(|_| self.step == Step::CheckKey)
+    }
+
+    /// Records the key check: a valid key is kept, a refused one is asked for again.
+    pub fn key_checked(&mut self, valid: bool) {
+        if self.step != Step::CheckKey {
+            return;
+        }
+        let key = self.pending_key.take();
+        if valid {
+            self.answers.semantic_scholar_key = key;
+            self.step = Step::DownloadDir;
+        } else {
+            self.step = Step::SemanticScholarKey;
+        }
+    }
+
+    pub fn answers(&self) -> &Answers {
+        &self.answers
+    }
+}
+
+/// Whether Semantic Scholar accepts `key`: a one-result search sent with it. Only 401 and 403
+/// count as a refusal; anything else unexpected is an error, so a flaky network doesn't
+/// discard a good key.
+pub async fn check_key(base_url: &str, key: &str) -> Result<bool> {
+    let url = format!("{}/paper/search?query=attention&limit=1&fields=title", base_url);
+    let resp = reqwest::Client::new().get(&url).header("x-api-key", key).send().await?;
+    match resp.status() {
+        status if status.is_success() => Ok(true),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Ok(false),
+        status => Err(anyhow!("Semantic Scholar API error: {}", status)),
+    }
+}
+
+/// `.env` contents: `template` (normally `.env.example`) with the answered settings filled
+/// in. Settings the template lacks are appended.
+pub fn render_env(template: Option<&str>, answers: &Answers) -> String {
+    let settings = [
+        ("OPENALEX_EMAIL", answers.email.as_deref()),
+        ("UNPAYWALL_EMAIL", answers.email.as_deref()),
+        ("SEMANTIC_SCHOLAR_API_KEY", answers.semantic_scholar_key.as_deref()),
+        ("DOWNLOAD_DIR", Some(answers.download_dir.as_deref().unwrap_or(DEFAULT_DOWNLOAD_DIR))),
+    ];
+    let mut out = String::new();
+    let mut written = Vec::new();
+    for line in template.unwrap_or("").lines() {
+        let setting = settings.iter().find(|(name, _)| line.strip_prefix(name).is_some_and(|rest| rest.starts_with('=')));
+        match setting {
+            Some((name, Some(value))) => {
+                out.push_str(&format!("{}={}\n", name, value));
+                written.push(*name);
+            }
+            Some((name, None)) => {
+                // Skipped: blank it rather than keep the template's placeholder
+                out.push_str(&format!("{}=\n", name));
+                written.push(*name);
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    for (name, value) in settings {
+        if let (Some(value), false) = (value, written.contains(&name)) {
+            out.push_str(&format!("{}={}\n", name, value));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+
+    #[test]
+    fn test_walks_through_every_question() {
+        let mut wizard = Wizard::default();
+        assert_eq!(wizard.step(), Step::Email);
+        assert!(wizard.answer("not an email").is_err());
+        assert_eq!(wizard.step(), Step::Email);
+        wizard.answer(" me@example.org ").unwrap();
+
+        assert_eq!(wizard.step(), Step::SemanticScholarKey);
+        wizard.answer("bad-key").unwrap();
+        assert_eq!(wizard.step(), Step::CheckKey);
+        assert_eq!(wizard.prompt(), None);
+        assert_eq!(wizard.pending_key(), Some("bad-key"));
+        wizard.key_checked(false);
+        assert_eq!(wizard.step(), Step::SemanticScholarKey);
+        wizard.answer("good-key").unwrap();
+        wizard.key_checked(true);
+
+        assert_eq!(wizard.step(), Step::DownloadDir);
+        wizard.answer("papers").unwrap();
+        assert_eq!(wizard.step(), Step::Done);
+        assert_eq!(wizard.answers(), &Answers {
+            email: Some("me@example.org".to_string()),
+            semantic_scholar_key: Some("good-key".to_string()),
+            download_dir: Some("papers".to_string()),
+        });
+    }
+
+    #[test]
+    fn test_blank_answers_skip() {
+        let mut wizard = Wizard::default();
+        for _ in 0..3 {
+            wizard.answer("").unwrap();
+        }
+        assert_eq!(wizard.step(), Step::Done);
+        assert_eq!(wizard.answers(), &Answers::default());
+        assert_eq!(wizard.pending_key(), None);
+    }
+
+    #[test]
+    fn test_env_from_template() {
+        let template = "# OpenAlex email\nOPENALEX_EMAIL=your-email@example.com\nSEMANTIC_SCHOLAR_API_KEY=\nUNPAYWALL_EMAIL=your-email@example.com\n# LOCAL_MIRROR=x\n";
+        let answers = Answers { email: Some("me@example.org".to_string()), ..Default::default() };
+        assert_eq!(
+            render_env(Some(template), &answers),
+            "# OpenAlex email\nOPENALEX_EMAIL=me@example.org\nSEMANTIC_SCHOLAR_API_KEY=\nUNPAYWALL_EMAIL=me@example.org\n# LOCAL_MIRROR=x\nDOWNLOAD_DIR=downloads\n"
+        );
+        assert_eq!(render_env(None, &Answers::default()), "DOWNLOAD_DIR=downloads\n");
+    }
+
+    #[tokio::test]
+    async fn test_key_check() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"total": 1, "data": []}"#),
+            MockResponse::new(403, r#"{"message": "Forbidden"}"#),
+            MockResponse::new(500, "oops"),
+        ]).await;
+        assert!(check_key(&server.url, "good").await.unwrap());
+        assert!(!check_key(&server.url, "bad").await.unwrap());
+        assert!(check_key(&server.url, "any").await.is_err());
+        assert!(server.request_heads()[0].to_lowercase().contains("x-api-key: good"));
+    }
+}